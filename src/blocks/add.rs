@@ -0,0 +1,65 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::combine::Combine;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct AddBlock {}
+impl ESDRBlock for AddBlock {
+    fn name(self) -> &'static str {
+        "Add"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in 1").build(),
+            Param::input_stream("in 2").build(),
+            Param::output_stream("out").build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Combine::new(
+            ["in 1", "in 2"],
+            "out",
+            |a: &Complex32, b: &Complex32| a + b,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatAddBlock {}
+impl ESDRBlock for FloatAddBlock {
+    fn name(self) -> &'static str {
+        "Add (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in 1")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::input_stream("in 2")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Combine::new(
+            ["in 1", "in 2"],
+            "out",
+            |a: &f32, b: &f32| a + b,
+            input.work_timer(),
+        ))
+    }
+}
@@ -0,0 +1,121 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::agc;
+use crate::kernels::agc::Agc;
+use crate::kernels::agc::AgcSettings;
+use crate::kernels::agc::AGC_PRESETS;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+/// Params of the AGC, shared by the complex and real blocks.
+fn agc_params(sample_rate: f64) -> Vec<Param> {
+    vec![
+        Param::scalar("sample rate")
+            .initial_value(sample_rate)
+            .build(),
+        Param::choice("preset")
+            .choices(
+                AGC_PRESETS
+                    .iter()
+                    .map(|(name, _)| Choice::new(name, name))
+                    .collect::<Vec<_>>(),
+            )
+            .allow_updates(true)
+            .build(),
+        Param::scalar("attack")
+            .initial_value(0.005)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("decay")
+            .initial_value(0.3)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("hang")
+            .initial_value(0.2)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("target")
+            .initial_value(0.5)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("max gain")
+            .initial_value(60.0)
+            .allow_updates(true)
+            .build(),
+    ]
+}
+
+fn agc_settings(input: &ESDRBlockInput) -> Result<(f64, AgcSettings)> {
+    let sample_rate = input.scalar("sample rate")?;
+    if sample_rate <= 0.0 {
+        bail!("The sample rate must be positive");
+    }
+    Ok((
+        sample_rate,
+        AgcSettings {
+            preset: agc::agc_preset(&input.choice("preset")?)?,
+            attack: input.scalar("attack")?,
+            decay: input.scalar("decay")?,
+            hang: input.scalar("hang")?,
+            target: input.scalar("target")?,
+            max_gain: input.scalar("max gain")?,
+        },
+    ))
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct AgcBlock {}
+impl ESDRBlock for AgcBlock {
+    fn name(self) -> &'static str {
+        "AGC"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+        ];
+        params.extend(agc_params(consts::RATE));
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let (sample_rate, settings) = agc_settings(&input)?;
+        Ok(Agc::<Complex32>::new(
+            sample_rate,
+            settings,
+            input.status()?,
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatAgcBlock {}
+impl ESDRBlock for FloatAgcBlock {
+    fn name(self) -> &'static str {
+        "AGC (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ];
+        params.extend(agc_params(consts::AUDIO_RATE as f64));
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let (sample_rate, settings) = agc_settings(&input)?;
+        Ok(Agc::<f32>::new(sample_rate, settings, input.status()?))
+    }
+}
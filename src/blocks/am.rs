@@ -0,0 +1,69 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::am::AmReceiver;
+use crate::kernels::am::AM_MODES;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct AmDemodulatorBlock {}
+impl ESDRBlock for AmDemodulatorBlock {
+    fn name(self) -> &'static str {
+        "AM Demodulator"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::AUDIO_RATE as f64)
+                .build(),
+            Param::choice("mode")
+                .choices(
+                    AM_MODES
+                        .iter()
+                        .map(|name| Choice::new(name, name))
+                        .collect::<Vec<_>>(),
+                )
+                .allow_updates(true)
+                .build(),
+            Param::scalar("bandwidth")
+                .initial_value(5_000.0)
+                .allow_updates(true)
+                .build(),
+            Param::toggle("agc")
+                .initial_value(true)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("gain")
+                .initial_value(1.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let sample_rate = input.scalar("sample rate")?;
+        let bandwidth = input.scalar("bandwidth")?;
+        if bandwidth <= 0.0 || bandwidth >= sample_rate / 2.0 {
+            bail!("The bandwidth must be below {} Hz", sample_rate / 2.0);
+        }
+        AmReceiver::new(
+            sample_rate,
+            &input.choice("mode")?,
+            bandwidth,
+            input.toggle("agc")?,
+            input.scalar("gain")?,
+            input.status()?,
+        )
+    }
+}
@@ -1,8 +1,11 @@
 use crate::blocks::ESDRBlock;
 use crate::blocks::ESDRBlockInput;
 use crate::consts;
+use crate::kernels::stereo_audio_sink::StereoAudioSink;
 use crate::params::Param;
+use crate::params::StreamType;
 
+use anyhow::Result;
 use futuresdr::blocks::audio::AudioSink;
 use futuresdr::runtime::Block;
 
@@ -14,10 +17,33 @@ impl ESDRBlock for AudioOutputBlock {
     }
 
     fn params(self) -> Vec<Param> {
-        vec![Param::input_stream("in").build()]
+        vec![Param::input_stream("in").item_type(StreamType::F32).build()]
     }
 
-    fn block(self, _input: ESDRBlockInput) -> Block {
-        AudioSink::new(consts::AUDIO_RATE, 1)
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(AudioSink::new(consts::AUDIO_RATE, 1))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct StereoAudioOutputBlock {}
+impl ESDRBlock for StereoAudioOutputBlock {
+    fn name(self) -> &'static str {
+        "Stereo Audio Output"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("left")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::input_stream("right")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(StereoAudioSink::new(consts::AUDIO_RATE, input.status()?))
     }
 }
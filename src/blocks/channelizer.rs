@@ -0,0 +1,176 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::blocks::NodeCache;
+use crate::consts;
+use crate::dsp::firdes;
+use crate::dsp::firdes::DesignMethod;
+use crate::dsp::firdes::FilterShape;
+use crate::dsp::firdes::FilterSpec;
+use crate::dsp::firdes::Taps;
+use crate::kernels::channelizer::Channelizer;
+use crate::params::Param;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Result;
+use eframe::egui;
+use futuresdr::runtime::Block;
+
+const DEFAULT_CHANNELS: usize = 8;
+
+fn output_name(channel: usize) -> String {
+    format!("ch {}", channel)
+}
+
+fn output_channel(name: &str) -> Option<usize> {
+    name.strip_prefix("ch ")?.parse().ok()
+}
+
+fn num_channels(input: &ESDRBlockInput) -> Result<usize> {
+    let num_channels = input.scalar("channels")?.round();
+    if num_channels < 2.0 {
+        bail!("At least 2 channels are needed");
+    }
+    Ok(num_channels as usize)
+}
+
+/// Parses a list of channels and ranges like "0-3, 7". Empty means all of
+/// them.
+fn selected_channels(input: &ESDRBlockInput) -> Result<Vec<usize>> {
+    let num_channels = num_channels(input)?;
+    let text = input.text("selected")?;
+    if text.trim().is_empty() {
+        return Ok((0..num_channels).collect());
+    }
+    let mut channels = vec![];
+    for part in text.split(',').map(str::trim) {
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid channel \"{}\"", s.trim()))
+        };
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => (parse(part)?, parse(part)?),
+        };
+        if first > last || last >= num_channels {
+            bail!(
+                "Invalid channels \"{}\", there are {} of them",
+                part,
+                num_channels
+            );
+        }
+        channels.extend(first..=last);
+    }
+    channels.sort_unstable();
+    channels.dedup();
+    Ok(channels)
+}
+
+/// Prototype lowpass, passing one channel. Frequencies are relative to the
+/// channel spacing.
+fn design(input: &ESDRBlockInput) -> Result<Vec<f32>> {
+    let num_channels = num_channels(input)?;
+    let transition = input.scalar("transition")?;
+    if transition <= 0.0 || transition >= 1.0 {
+        bail!("The transition must be between 0 and 1");
+    }
+    let taps = firdes::design(&FilterSpec {
+        shape: FilterShape::Lowpass,
+        method: DesignMethod::Kaiser,
+        sample_rate: num_channels as f64,
+        cutoff: 0.5,
+        cutoff_high: 0.0,
+        transition,
+        attenuation: input.scalar("attenuation")?,
+        symbol_rate: 0.0,
+        rolloff: 0.0,
+        num_taps: 0,
+        complex: false,
+    })?;
+    match taps {
+        Taps::Real(taps) => Ok(taps),
+        Taps::Complex(_) => bail!("The prototype filter must be real"),
+    }
+}
+
+fn summary(input: &ESDRBlockInput) -> Result<String> {
+    let taps = design(input)?;
+    let spacing = input.scalar("sample rate")? / num_channels(input)? as f64;
+    Ok(format!("{} Hz channels, {} taps", spacing, taps.len()))
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ChannelizerBlock {}
+impl ESDRBlock for ChannelizerBlock {
+    fn name(self) -> &'static str {
+        "Polyphase Channelizer"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::RATE)
+                .build(),
+            Param::scalar("channels")
+                .initial_value(DEFAULT_CHANNELS as f64)
+                .build(),
+            Param::text("selected").build(),
+            Param::scalar("transition").initial_value(0.2).build(),
+            Param::scalar("attenuation").initial_value(60.0).build(),
+        ];
+        params.extend(
+            (0..DEFAULT_CHANNELS)
+                .map(|channel| Param::output_stream(&output_name(channel)).build()),
+        );
+        params
+    }
+
+    fn refresh(self, input: ESDRBlockInput) -> Result<Option<Vec<Param>>> {
+        Ok(Some(
+            selected_channels(&input)?
+                .into_iter()
+                .map(|channel| Param::output_stream(&output_name(channel)).build())
+                .collect(),
+        ))
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let selected = selected_channels(&input)?;
+        // only connected channels are computed
+        let mut channels = vec![];
+        for name in input.output_names() {
+            if !input.output_connected(&name) {
+                continue;
+            }
+            match output_channel(&name) {
+                Some(channel) if selected.contains(&channel) => channels.push((channel, name)),
+                _ => bail!("Output \"{}\" is not one of the selected channels", name),
+            }
+        }
+        Ok(Channelizer::new(
+            num_channels(&input)?,
+            design(&input)?,
+            channels,
+            input.work_timer(),
+        ))
+    }
+
+    fn node_ui(self, ui: &mut egui::Ui, input: ESDRBlockInput, cache: &NodeCache) {
+        let key = format!(
+            "{:?}",
+            ["sample rate", "channels", "transition", "attenuation"]
+                .map(|name| input.scalar(name).ok())
+        );
+        let summary = cache.get(key, || summary(&input).map_err(|e| format!("{:#}", e)));
+        match summary {
+            Ok(summary) => {
+                ui.label(summary);
+            }
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+    }
+}
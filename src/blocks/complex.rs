@@ -0,0 +1,159 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::combine::Combine;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::blocks::Apply;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct ConjugateBlock {}
+impl ESDRBlock for ConjugateBlock {
+    fn name(self) -> &'static str {
+        "Complex Conjugate"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+        ]
+    }
+
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(Apply::new(|v: &Complex32| -> Complex32 { v.conj() }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ComplexToMagBlock {}
+impl ESDRBlock for ComplexToMagBlock {
+    fn name(self) -> &'static str {
+        "Complex to Mag"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(Apply::new(|v: &Complex32| -> f32 { v.norm() }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ComplexToMagSquaredBlock {}
+impl ESDRBlock for ComplexToMagSquaredBlock {
+    fn name(self) -> &'static str {
+        "Complex to Mag²"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(Apply::new(|v: &Complex32| -> f32 { v.norm_sqr() }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ComplexToArgBlock {}
+impl ESDRBlock for ComplexToArgBlock {
+    fn name(self) -> &'static str {
+        "Complex to Arg"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(Apply::new(|v: &Complex32| -> f32 { v.arg() }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ComplexToRealBlock {}
+impl ESDRBlock for ComplexToRealBlock {
+    fn name(self) -> &'static str {
+        "Complex to Real"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(Apply::new(|v: &Complex32| -> f32 { v.re }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ComplexToImagBlock {}
+impl ESDRBlock for ComplexToImagBlock {
+    fn name(self) -> &'static str {
+        "Complex to Imag"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ]
+    }
+
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
+        Ok(Apply::new(|v: &Complex32| -> f32 { v.im }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatToComplexBlock {}
+impl ESDRBlock for FloatToComplexBlock {
+    fn name(self) -> &'static str {
+        "Float to Complex"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("re").item_type(StreamType::F32).build(),
+            Param::input_stream("im").item_type(StreamType::F32).build(),
+            Param::output_stream("out").build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Combine::new(
+            ["re", "im"],
+            "out",
+            |re: &f32, im: &f32| Complex32::new(*re, *im),
+            input.work_timer(),
+        ))
+    }
+}
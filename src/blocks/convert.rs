@@ -0,0 +1,114 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::blocks::Apply;
+use futuresdr::runtime::Block;
+
+/// Reads the scale between full scale floats (±1) and integers.
+fn scale(input: &ESDRBlockInput) -> Result<f32> {
+    let scale = input.scalar("scale")?;
+    if scale <= 0.0 {
+        bail!("The scale must be positive");
+    }
+    Ok(scale as f32)
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatToShortBlock {}
+impl ESDRBlock for FloatToShortBlock {
+    fn name(self) -> &'static str {
+        "Float to Short"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::I16)
+                .build(),
+            Param::scalar("scale").initial_value(32767.0).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let scale = scale(&input)?;
+        Ok(Apply::new(move |v: &f32| -> i16 {
+            (v * scale).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+        }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ShortToFloatBlock {}
+impl ESDRBlock for ShortToFloatBlock {
+    fn name(self) -> &'static str {
+        "Short to Float"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::I16).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("scale").initial_value(32767.0).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let scale = scale(&input)?;
+        Ok(Apply::new(move |v: &i16| -> f32 { *v as f32 / scale }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatToCharBlock {}
+impl ESDRBlock for FloatToCharBlock {
+    fn name(self) -> &'static str {
+        "Float to Char"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::I8)
+                .build(),
+            Param::scalar("scale").initial_value(127.0).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let scale = scale(&input)?;
+        Ok(Apply::new(move |v: &f32| -> i8 {
+            (v * scale).round().clamp(i8::MIN as f32, i8::MAX as f32) as i8
+        }))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct CharToFloatBlock {}
+impl ESDRBlock for CharToFloatBlock {
+    fn name(self) -> &'static str {
+        "Char to Float"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::I8).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("scale").initial_value(127.0).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let scale = scale(&input)?;
+        Ok(Apply::new(move |v: &i8| -> f32 { *v as f32 / scale }))
+    }
+}
@@ -0,0 +1,43 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::dc_blocker::DcBlocker;
+use crate::params::Param;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct DcBlockerBlock {}
+impl ESDRBlock for DcBlockerBlock {
+    fn name(self) -> &'static str {
+        "DC Blocker"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::RATE)
+                .build(),
+            Param::scalar("cutoff")
+                .initial_value(10.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let sample_rate = input.scalar("sample rate")?;
+        if sample_rate <= 0.0 {
+            bail!("The sample rate must be positive");
+        }
+        Ok(DcBlocker::new(
+            sample_rate,
+            input.scalar("cutoff")?,
+            input.work_timer(),
+        ))
+    }
+}
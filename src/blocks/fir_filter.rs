@@ -0,0 +1,202 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::blocks::NodeCache;
+use crate::consts;
+use crate::dsp::firdes;
+use crate::dsp::firdes::DesignMethod;
+use crate::dsp::firdes::FilterShape;
+use crate::dsp::firdes::FilterSpec;
+use crate::kernels::fir::Fir;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use eframe::egui;
+use eframe::egui::plot::Line;
+use eframe::egui::plot::Plot;
+use eframe::egui::plot::Value;
+use eframe::egui::plot::Values;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+const RESPONSE_POINTS: usize = 256;
+/// how long the params have to stay the same before the filter is designed
+/// again, so that dragging a value doesn't design it on every step
+const RESPONSE_DELAY: Duration = Duration::from_millis(300);
+/// lowest gain shown on the response plot, in dB
+const RESPONSE_FLOOR: f64 = -120.0;
+
+/// Params describing the filter, shared by the blocks designing one.
+pub fn filter_params(complex: bool) -> Vec<Param> {
+    let mut params = vec![
+        Param::choice("shape")
+            .choices(
+                FilterShape::ALL
+                    .iter()
+                    .map(|shape| Choice::new(shape.name(), shape.name()))
+                    .collect::<Vec<_>>(),
+            )
+            .build(),
+        Param::choice("design")
+            .choices(
+                DesignMethod::ALL
+                    .iter()
+                    .map(|method| Choice::new(method.name(), method.name()))
+                    .collect::<Vec<_>>(),
+            )
+            .build(),
+        Param::scalar("sample rate")
+            .initial_value(consts::RATE)
+            .build(),
+        Param::scalar("cutoff")
+            .initial_value(100_000.0)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("cutoff high")
+            .initial_value(200_000.0)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("transition")
+            .initial_value(20_000.0)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("attenuation")
+            .initial_value(60.0)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("symbol rate")
+            .initial_value(10_000.0)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("rolloff")
+            .initial_value(0.35)
+            .allow_updates(true)
+            .build(),
+        Param::scalar("taps").initial_value(0.0).build(),
+    ];
+    if complex {
+        params.push(Param::toggle("complex taps").build());
+    }
+    params
+}
+
+pub fn filter_spec(input: &ESDRBlockInput) -> Result<FilterSpec> {
+    let num_taps = input.scalar("taps")?;
+    if num_taps < 0.0 {
+        bail!("The number of taps can't be negative");
+    }
+    Ok(FilterSpec {
+        shape: FilterShape::from_name(&input.choice("shape")?)?,
+        method: DesignMethod::from_name(&input.choice("design")?)?,
+        sample_rate: input.scalar("sample rate")?,
+        cutoff: input.scalar("cutoff")?,
+        cutoff_high: input.scalar("cutoff high")?,
+        transition: input.scalar("transition")?,
+        attenuation: input.scalar("attenuation")?,
+        symbol_rate: input.scalar("symbol rate")?,
+        rolloff: input.scalar("rolloff")?,
+        num_taps: num_taps.round() as usize,
+        complex: input
+            .param_names()
+            .iter()
+            .any(|name| name == "complex taps")
+            && input.toggle("complex taps")?,
+    })
+}
+
+/// Plots the magnitude response of the filter described by the params, or
+/// the reason it can't be designed.
+pub fn response_plot(ui: &mut egui::Ui, input: &ESDRBlockInput, cache: &NodeCache) {
+    let (response, outdated) = match filter_spec(input) {
+        Ok(spec) => cache.get_settled(format!("{:?}", spec), RESPONSE_DELAY, || {
+            firdes::design(&spec)
+                .map(|taps| (taps.len(), taps.response(spec.sample_rate, RESPONSE_POINTS)))
+                .map_err(|e| format!("{:#}", e))
+        }),
+        Err(e) => (Err(format!("{:#}", e)), false),
+    };
+    if outdated {
+        ui.ctx().request_repaint();
+    }
+    match response {
+        Ok((num_taps, response)) => {
+            if outdated {
+                ui.label(format!("{} taps (updating…)", num_taps));
+            } else {
+                ui.label(format!("{} taps", num_taps));
+            }
+            let values = response
+                .iter()
+                .map(|(freq, gain)| Value::new(*freq, gain.max(RESPONSE_FLOOR)))
+                .collect();
+            Plot::new((input.node_id(), "response"))
+                .height(100.0)
+                .width(240.0)
+                .allow_zoom(false)
+                .allow_drag(false)
+                .include_y(0.0)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(Values::from_values(values)))
+                });
+        }
+        Err(e) => {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FirFilterBlock {}
+impl ESDRBlock for FirFilterBlock {
+    fn name(self) -> &'static str {
+        "FIR Filter"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+        ];
+        params.extend(filter_params(true));
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Fir::<Complex32, Complex32>::new(filter_spec(&input)?, input.status()?)
+    }
+
+    fn node_ui(self, ui: &mut egui::Ui, input: ESDRBlockInput, cache: &NodeCache) {
+        response_plot(ui, &input, cache);
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatFirFilterBlock {}
+impl ESDRBlock for FloatFirFilterBlock {
+    fn name(self) -> &'static str {
+        "FIR Filter (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+        ];
+        params.extend(filter_params(false));
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Fir::<f32, f32>::new(filter_spec(&input)?, input.status()?)
+    }
+
+    fn node_ui(self, ui: &mut egui::Ui, input: ESDRBlockInput, cache: &NodeCache) {
+        response_plot(ui, &input, cache);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::delay::Delay;
+use crate::kernels::head::Head;
+use crate::kernels::null::NullSink;
+use crate::kernels::null::NullSource;
+use crate::kernels::skip::Skip;
+use crate::kernels::throttle::Throttle;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+/// An input and an output of the same type, with the params of the block.
+fn pass_params(item_type: StreamType, params: Vec<Param>) -> Vec<Param> {
+    let mut all = vec![
+        Param::input_stream("in").item_type(item_type).build(),
+        Param::output_stream("out").item_type(item_type).build(),
+    ];
+    all.extend(params);
+    all
+}
+
+fn samples_param(name: &str, initial_value: f64) -> Param {
+    Param::scalar(name).initial_value(initial_value).build()
+}
+
+fn samples(input: &ESDRBlockInput, name: &str) -> Result<usize> {
+    let samples = input.scalar(name)?.round();
+    if samples < 0.0 {
+        bail!("The {} can't be negative", name);
+    }
+    Ok(samples as usize)
+}
+
+fn sample_rate(input: &ESDRBlockInput) -> Result<f64> {
+    let sample_rate = input.scalar("sample rate")?;
+    if sample_rate <= 0.0 {
+        bail!("The sample rate must be positive");
+    }
+    Ok(sample_rate)
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct DelayBlock {}
+impl ESDRBlock for DelayBlock {
+    fn name(self) -> &'static str {
+        "Delay"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(StreamType::Complex32, vec![samples_param("delay", 0.0)])
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Delay::<Complex32>::new(
+            samples(&input, "delay")?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct HeadBlock {}
+impl ESDRBlock for HeadBlock {
+    fn name(self) -> &'static str {
+        "Head"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(
+            StreamType::Complex32,
+            vec![samples_param("samples", 1024.0)],
+        )
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Head::<Complex32>::new(
+            samples(&input, "samples")?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SkipBlock {}
+impl ESDRBlock for SkipBlock {
+    fn name(self) -> &'static str {
+        "Skip"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(
+            StreamType::Complex32,
+            vec![samples_param("samples", 1024.0)],
+        )
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Skip::<Complex32>::new(
+            samples(&input, "samples")?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ThrottleBlock {}
+impl ESDRBlock for ThrottleBlock {
+    fn name(self) -> &'static str {
+        "Throttle"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(
+            StreamType::Complex32,
+            vec![Param::scalar("sample rate")
+                .initial_value(consts::RATE)
+                .build()],
+        )
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Throttle::<Complex32>::new(
+            sample_rate(&input)?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct NullSourceBlock {}
+impl ESDRBlock for NullSourceBlock {
+    fn name(self) -> &'static str {
+        "Null Source"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![Param::output_stream("out")
+            .item_type(StreamType::Complex32)
+            .build()]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(NullSource::<Complex32>::new(input.work_timer()))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct NullSinkBlock {}
+impl ESDRBlock for NullSinkBlock {
+    fn name(self) -> &'static str {
+        "Null Sink"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![Param::input_stream("in")
+            .item_type(StreamType::Complex32)
+            .build()]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(NullSink::<Complex32>::new(input.work_timer()))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatDelayBlock {}
+impl ESDRBlock for FloatDelayBlock {
+    fn name(self) -> &'static str {
+        "Delay (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(StreamType::F32, vec![samples_param("delay", 0.0)])
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Delay::<f32>::new(
+            samples(&input, "delay")?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatHeadBlock {}
+impl ESDRBlock for FloatHeadBlock {
+    fn name(self) -> &'static str {
+        "Head (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(StreamType::F32, vec![samples_param("samples", 1024.0)])
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Head::<f32>::new(
+            samples(&input, "samples")?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatSkipBlock {}
+impl ESDRBlock for FloatSkipBlock {
+    fn name(self) -> &'static str {
+        "Skip (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(StreamType::F32, vec![samples_param("samples", 1024.0)])
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Skip::<f32>::new(
+            samples(&input, "samples")?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatThrottleBlock {}
+impl ESDRBlock for FloatThrottleBlock {
+    fn name(self) -> &'static str {
+        "Throttle (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        pass_params(
+            StreamType::F32,
+            vec![Param::scalar("sample rate")
+                .initial_value(consts::AUDIO_RATE as f64)
+                .build()],
+        )
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Throttle::<f32>::new(
+            sample_rate(&input)?,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatNullSourceBlock {}
+impl ESDRBlock for FloatNullSourceBlock {
+    fn name(self) -> &'static str {
+        "Null Source (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![Param::output_stream("out")
+            .item_type(StreamType::F32)
+            .build()]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(NullSource::<f32>::new(input.work_timer()))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatNullSinkBlock {}
+impl ESDRBlock for FloatNullSinkBlock {
+    fn name(self) -> &'static str {
+        "Null Sink (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![Param::input_stream("in").item_type(StreamType::F32).build()]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(NullSink::<f32>::new(input.work_timer()))
+    }
+}
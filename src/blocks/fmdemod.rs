@@ -1,7 +1,9 @@
 use crate::blocks::ESDRBlock;
 use crate::blocks::ESDRBlockInput;
 use crate::params::Param;
+use crate::params::StreamType;
 
+use anyhow::Result;
 use futuresdr::blocks::Apply;
 use futuresdr::num_complex::Complex32;
 use futuresdr::runtime::Block;
@@ -16,16 +18,18 @@ impl ESDRBlock for FMDemodulatorBlock {
     fn params(self) -> Vec<Param> {
         vec![
             Param::input_stream("in").build(),
-            Param::output_stream("out").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
         ]
     }
 
-    fn block(self, _input: ESDRBlockInput) -> Block {
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
         let mut last = Complex32::new(0.0, 0.0); // store sample x[n-1]
-        Apply::new(move |v: &Complex32| -> f32 {
+        Ok(Apply::new(move |v: &Complex32| -> f32 {
             let arg = (v * last.conj()).arg(); // Obtain phase of x[n] * conj(x[n-1])
             last = *v;
             arg
-        })
+        }))
     }
 }
@@ -0,0 +1,41 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::frequency_translator::FrequencyTranslator;
+use crate::params::Param;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct FrequencyTranslatorBlock {}
+impl ESDRBlock for FrequencyTranslatorBlock {
+    fn name(self) -> &'static str {
+        "Frequency Translator"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("shift").allow_updates(true).build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::RATE)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let sample_rate = input.scalar("sample rate")?;
+        if sample_rate <= 0.0 {
+            bail!("The sample rate must be positive");
+        }
+        Ok(FrequencyTranslator::new(
+            input.scalar("shift")?,
+            sample_rate,
+            input.work_timer(),
+        ))
+    }
+}
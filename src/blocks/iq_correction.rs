@@ -0,0 +1,32 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::iq_correction::IqCorrection;
+use crate::params::Param;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct IqCorrectionBlock {}
+impl ESDRBlock for IqCorrectionBlock {
+    fn name(self) -> &'static str {
+        "IQ Correction"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("alpha").initial_value(0.0001).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let alpha = input.scalar("alpha")?;
+        if alpha <= 0.0 || alpha > 1.0 {
+            bail!("The averaging alpha must be between 0 and 1");
+        }
+        Ok(IqCorrection::new(alpha, input.status()?))
+    }
+}
@@ -0,0 +1,34 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::split::Split;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct IQSplitBlock {}
+impl ESDRBlock for IQSplitBlock {
+    fn name(self) -> &'static str {
+        "I/Q Split"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("i").item_type(StreamType::F32).build(),
+            Param::output_stream("q").item_type(StreamType::F32).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Split::new(
+            "in",
+            ["i", "q"],
+            |v: &Complex32| (v.re, v.im),
+            input.work_timer(),
+        ))
+    }
+}
@@ -0,0 +1,91 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::mixer::Mixer;
+use crate::kernels::mixer::MAX_INPUTS;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+const DEFAULT_INPUTS: usize = 2;
+
+fn input_name(input: usize) -> String {
+    format!("in {}", input)
+}
+
+fn gain_name(input: usize) -> String {
+    format!("gain {}", input)
+}
+
+fn input_index(name: &str) -> Option<usize> {
+    name.strip_prefix("in ")?.parse().ok()
+}
+
+fn input_params(input: usize) -> Vec<Param> {
+    vec![
+        Param::input_stream(&input_name(input))
+            .item_type(StreamType::F32)
+            .build(),
+        Param::scalar(&gain_name(input))
+            .initial_value(1.0)
+            .allow_updates(true)
+            .build(),
+    ]
+}
+
+fn num_inputs(input: &ESDRBlockInput) -> Result<usize> {
+    let num_inputs = input.scalar("inputs")?.round();
+    if num_inputs < 1.0 || num_inputs > MAX_INPUTS as f64 {
+        bail!("The mixer takes 1 to {} inputs", MAX_INPUTS);
+    }
+    Ok(num_inputs as usize)
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct AudioMixerBlock {}
+impl ESDRBlock for AudioMixerBlock {
+    fn name(self) -> &'static str {
+        "Audio Mixer"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("inputs")
+                .initial_value(DEFAULT_INPUTS as f64)
+                .refresh(true)
+                .build(),
+        ];
+        params.extend((1..=DEFAULT_INPUTS).flat_map(input_params));
+        params
+    }
+
+    /// Adds an input and its gain for each of the inputs asked for.
+    fn refresh(self, input: ESDRBlockInput) -> Result<Option<Vec<Param>>> {
+        Ok(Some(
+            (1..=num_inputs(&input)?).flat_map(input_params).collect(),
+        ))
+    }
+
+    /// Mixes the inputs the node has, which are only out of step with
+    /// "inputs" when it wasn't refreshed.
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let mut inputs = vec![];
+        for index in input
+            .param_names()
+            .iter()
+            .filter_map(|name| input_index(name))
+        {
+            inputs.push((
+                input_name(index),
+                gain_name(index),
+                input.scalar(&gain_name(index))?,
+            ));
+        }
+        Mixer::new(inputs, input.work_timer())
+    }
+}
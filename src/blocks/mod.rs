@@ -1,53 +1,322 @@
 use crate::params::Param;
+use crate::radio::NodeStatus;
+use crate::stats::WorkTimer;
 use crate::ui::ESDRGraph;
 use crate::ui::ESDRNodeData;
 use crate::ui::ESDRValueType;
 
+use std::any::Any;
+use std::cell::RefCell;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Result;
+use eframe::egui;
 use egui_node_graph::Node;
+use egui_node_graph::NodeId;
 use futuresdr::runtime::Block;
 use strum_macros::EnumIter;
 
 #[enum_dispatch]
 pub trait ESDRBlock {
     fn name(self) -> &'static str;
-    fn block(self, input: ESDRBlockInput) -> Block;
+    fn block(self, input: ESDRBlockInput) -> Result<Block>;
     fn params(self) -> Vec<Param>;
+
+    /// Returns parameters which depend on other parameters or on something
+    /// outside of eSDR, like the capabilities of a device. Parameters missing
+    /// on the node are added to it, existing choices get their list of choices
+    /// updated, and parameters returned by the previous refresh but not this
+    /// one are removed. Returns None while looking things up in the
+    /// background, in which case the node is refreshed again later.
+    fn refresh(self, _input: ESDRBlockInput) -> Result<Option<Vec<Param>>> {
+        Ok(Some(vec![]))
+    }
+
+    /// Extra UI shown at the bottom of the node, e.g. the response of the
+    /// filter designed from the params.
+    fn node_ui(self, _ui: &mut egui::Ui, _input: ESDRBlockInput, _cache: &NodeCache) {}
+}
+
+/// Keeps the result of an expensive computation made for the node UI (like
+/// designing a filter) around until its inputs change.
+#[derive(Default)]
+pub struct NodeCache {
+    value: RefCell<Option<(String, Box<dyn Any>)>>,
+    /// key waiting to stay the same long enough to be computed, and since when
+    pending: RefCell<Option<(String, Instant)>>,
+}
+
+impl NodeCache {
+    pub fn get<T: Clone + 'static>(&self, key: String, compute: impl FnOnce() -> T) -> T {
+        if let Some(value) = self.cached(&key) {
+            return value;
+        }
+        self.compute(key, compute)
+    }
+
+    /// Like `get`, but while the key keeps changing (e.g. while a value is
+    /// dragged) the previous value is returned, and the new one is only
+    /// computed once the key stayed the same for `delay`. Also returns whether
+    /// the value is outdated, in which case the UI needs repainting to get
+    /// the new one.
+    pub fn get_settled<T: Clone + 'static>(
+        &self,
+        key: String,
+        delay: Duration,
+        compute: impl FnOnce() -> T,
+    ) -> (T, bool) {
+        if let Some(value) = self.cached(&key) {
+            return (value, false);
+        }
+        let previous = match &*self.value.borrow() {
+            Some((_, value)) => value.downcast_ref::<T>().cloned(),
+            None => None,
+        };
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return (self.compute(key, compute), false),
+        };
+        let mut pending = self.pending.borrow_mut();
+        match &*pending {
+            Some((pending_key, since)) if *pending_key == key => {
+                if since.elapsed() >= delay {
+                    drop(pending);
+                    return (self.compute(key, compute), false);
+                }
+            }
+            _ => *pending = Some((key, Instant::now())),
+        }
+        (previous, true)
+    }
+
+    fn cached<T: Clone + 'static>(&self, key: &str) -> Option<T> {
+        match &*self.value.borrow() {
+            Some((cached_key, value)) if cached_key == key => value.downcast_ref::<T>().cloned(),
+            _ => None,
+        }
+    }
+
+    fn compute<T: Clone + 'static>(&self, key: String, compute: impl FnOnce() -> T) -> T {
+        let value = compute();
+        *self.value.borrow_mut() = Some((key, Box::new(value.clone())));
+        *self.pending.borrow_mut() = None;
+        value
+    }
 }
 
 pub struct ESDRBlockInput<'a> {
     graph: &'a ESDRGraph,
     node: &'a Node<ESDRNodeData>,
+    status: Option<NodeStatus>,
 }
 
 impl ESDRBlockInput<'_> {
-    pub fn new<'a>(graph: &'a ESDRGraph, node: &'a Node<ESDRNodeData>) -> ESDRBlockInput<'a> {
-        ESDRBlockInput { graph, node }
+    pub fn new<'a>(
+        graph: &'a ESDRGraph,
+        node: &'a Node<ESDRNodeData>,
+        status: Option<NodeStatus>,
+    ) -> ESDRBlockInput<'a> {
+        ESDRBlockInput {
+            graph,
+            node,
+            status,
+        }
+    }
+
+    pub fn status(&self) -> Result<NodeStatus> {
+        self.status
+            .clone()
+            .ok_or_else(|| anyhow!("The radio is not running"))
+    }
+
+    /// Returns the timer measuring the work calls of the block, which measures
+    /// nothing when the radio isn't running.
+    pub fn work_timer(&self) -> WorkTimer {
+        self.status
+            .as_ref()
+            .map(|status| status.work_timer())
+            .unwrap_or_default()
+    }
+
+    pub fn node_id(&self) -> NodeId {
+        self.node.id
+    }
+
+    pub fn param_names(&self) -> Vec<String> {
+        self.node
+            .inputs
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn output_names(&self) -> Vec<String> {
+        self.node
+            .outputs
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Whether the given output is connected to another node.
+    pub fn output_connected(&self, name: &str) -> bool {
+        match self.node.get_output(name) {
+            Ok(output_id) => self
+                .graph
+                .connections
+                .values()
+                .any(|connected| *connected == output_id),
+            Err(_) => false,
+        }
     }
 
-    pub fn scalar(&self, name: &str) -> f64 {
-        let input_id = self.node.get_input(name).unwrap();
+    pub fn scalar(&self, name: &str) -> Result<f64> {
+        let input_id = self
+            .node
+            .get_input(name)
+            .map_err(|_| anyhow!("Missing parameter {}", name))?;
         let input = self.graph.get_input(input_id);
         match input.value {
-            ESDRValueType::Scalar { value, .. } => value,
-            _ => panic!("Unexpected value type"),
+            ESDRValueType::Scalar { value, .. } => Ok(value),
+            _ => bail!("Parameter {} is not a scalar", name),
+        }
+    }
+
+    pub fn toggle(&self, name: &str) -> Result<bool> {
+        let input_id = self
+            .node
+            .get_input(name)
+            .map_err(|_| anyhow!("Missing parameter {}", name))?;
+        let input = self.graph.get_input(input_id);
+        match input.value {
+            ESDRValueType::Toggle { value, .. } => Ok(value),
+            _ => bail!("Parameter {} is not a toggle", name),
+        }
+    }
+
+    pub fn choice(&self, name: &str) -> Result<String> {
+        let input_id = self
+            .node
+            .get_input(name)
+            .map_err(|_| anyhow!("Missing parameter {}", name))?;
+        let input = self.graph.get_input(input_id);
+        match &input.value {
+            ESDRValueType::Choice { value, .. } => Ok(value.clone()),
+            _ => bail!("Parameter {} is not a choice", name),
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Result<String> {
+        let input_id = self
+            .node
+            .get_input(name)
+            .map_err(|_| anyhow!("Missing parameter {}", name))?;
+        let input = self.graph.get_input(input_id);
+        match &input.value {
+            ESDRValueType::Text { value, .. } => Ok(value.clone()),
+            _ => bail!("Parameter {} is not a text", name),
         }
     }
 }
 
+mod add;
+mod agc;
+mod am;
 mod audio_output;
+mod channelizer;
+mod complex;
+mod convert;
+mod dc_blocker;
+mod fir_filter;
+mod flow;
 mod fmdemod;
+mod frequency_translator;
+mod iq_correction;
+mod iq_split;
+mod mixer;
+mod multiply;
+mod multiply_const;
+mod nbfm;
+mod noise_reduction;
+mod power_squelch;
+mod rds;
 mod resamp1;
 mod resamp2;
-mod shift;
+mod resampler;
 mod soapysdr;
+mod soapysdr_sink;
+mod ssb;
+mod subtract;
+mod tone_filter;
+mod volume;
+mod wbfm;
+mod xlating_fir;
 
 #[enum_dispatch(ESDRBlock)]
 #[derive(Clone, Copy, EnumIter)]
 pub enum ESDRBlockType {
     SoapySDR(self::soapysdr::SoapySDRBlock),
-    Shift(self::shift::ShiftBlock),
+    FrequencyTranslator(self::frequency_translator::FrequencyTranslatorBlock),
     Resamp1(self::resamp1::Resamp1Block),
     FMDemodulator(self::fmdemod::FMDemodulatorBlock),
     Resamp2(self::resamp2::Resamp2Block),
     AudioOutput(self::audio_output::AudioOutputBlock),
+    SoapySDRSink(self::soapysdr_sink::SoapySDRSinkBlock),
+    Add(self::add::AddBlock),
+    Multiply(self::multiply::MultiplyBlock),
+    IQSplit(self::iq_split::IQSplitBlock),
+    FirFilter(self::fir_filter::FirFilterBlock),
+    FloatFirFilter(self::fir_filter::FloatFirFilterBlock),
+    RationalResampler(self::resampler::RationalResamplerBlock),
+    Decimator(self::resampler::DecimatorBlock),
+    ArbitraryResampler(self::resampler::ArbitraryResamplerBlock),
+    XlatingFir(self::xlating_fir::XlatingFirBlock),
+    Channelizer(self::channelizer::ChannelizerBlock),
+    WbfmReceiver(self::wbfm::WbfmReceiverBlock),
+    StereoAudioOutput(self::audio_output::StereoAudioOutputBlock),
+    RdsDecoder(self::rds::RdsDecoderBlock),
+    NbfmDemodulator(self::nbfm::NbfmDemodulatorBlock),
+    AmDemodulator(self::am::AmDemodulatorBlock),
+    SsbDemodulator(self::ssb::SsbDemodulatorBlock),
+    Agc(self::agc::AgcBlock),
+    FloatAgc(self::agc::FloatAgcBlock),
+    PowerSquelch(self::power_squelch::PowerSquelchBlock),
+    DcBlocker(self::dc_blocker::DcBlockerBlock),
+    IqCorrection(self::iq_correction::IqCorrectionBlock),
+    Volume(self::volume::VolumeBlock),
+    AudioMixer(self::mixer::AudioMixerBlock),
+    ToneFilter(self::tone_filter::ToneFilterBlock),
+    NoiseReduction(self::noise_reduction::NoiseReductionBlock),
+    Subtract(self::subtract::SubtractBlock),
+    FloatAdd(self::add::FloatAddBlock),
+    FloatSubtract(self::subtract::FloatSubtractBlock),
+    FloatMultiply(self::multiply::FloatMultiplyBlock),
+    MultiplyConst(self::multiply_const::MultiplyConstBlock),
+    FloatMultiplyConst(self::multiply_const::FloatMultiplyConstBlock),
+    Conjugate(self::complex::ConjugateBlock),
+    ComplexToMag(self::complex::ComplexToMagBlock),
+    ComplexToMagSquared(self::complex::ComplexToMagSquaredBlock),
+    ComplexToArg(self::complex::ComplexToArgBlock),
+    ComplexToReal(self::complex::ComplexToRealBlock),
+    ComplexToImag(self::complex::ComplexToImagBlock),
+    FloatToComplex(self::complex::FloatToComplexBlock),
+    FloatToShort(self::convert::FloatToShortBlock),
+    ShortToFloat(self::convert::ShortToFloatBlock),
+    FloatToChar(self::convert::FloatToCharBlock),
+    CharToFloat(self::convert::CharToFloatBlock),
+    Delay(self::flow::DelayBlock),
+    FloatDelay(self::flow::FloatDelayBlock),
+    Head(self::flow::HeadBlock),
+    FloatHead(self::flow::FloatHeadBlock),
+    Skip(self::flow::SkipBlock),
+    FloatSkip(self::flow::FloatSkipBlock),
+    Throttle(self::flow::ThrottleBlock),
+    FloatThrottle(self::flow::FloatThrottleBlock),
+    NullSource(self::flow::NullSourceBlock),
+    FloatNullSource(self::flow::FloatNullSourceBlock),
+    NullSink(self::flow::NullSinkBlock),
+    FloatNullSink(self::flow::FloatNullSinkBlock),
 }
@@ -0,0 +1,69 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::multiply_const::MultiplyConst;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct MultiplyConstBlock {}
+impl ESDRBlock for MultiplyConstBlock {
+    fn name(self) -> &'static str {
+        "Multiply Const"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("constant")
+                .initial_value(1.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("constant imag")
+                .initial_value(0.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let constant = Complex32::new(
+            input.scalar("constant")? as f32,
+            input.scalar("constant imag")? as f32,
+        );
+        Ok(MultiplyConst::<Complex32>::new(
+            constant,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct FloatMultiplyConstBlock {}
+impl ESDRBlock for FloatMultiplyConstBlock {
+    fn name(self) -> &'static str {
+        "Multiply Const (float)"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("constant")
+                .initial_value(1.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let constant = Complex32::new(input.scalar("constant")? as f32, 0.0);
+        Ok(MultiplyConst::<f32>::new(constant, input.work_timer()))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::dsp::tones::Tone;
+use crate::kernels::nbfm::NbfmReceiver;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+const MIN_SAMPLE_RATE: f64 = 8_000.0;
+
+#[derive(Clone, Copy, Default)]
+pub struct NbfmDemodulatorBlock {}
+impl ESDRBlock for NbfmDemodulatorBlock {
+    fn name(self) -> &'static str {
+        "NBFM Demodulator"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::AUDIO_RATE as f64)
+                .build(),
+            Param::scalar("deviation")
+                .initial_value(5_000.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("gain")
+                .initial_value(1.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("squelch")
+                .initial_value(-10.0)
+                .allow_updates(true)
+                .build(),
+            Param::text("tone").allow_updates(true).build(),
+            Param::toggle("tone filter")
+                .initial_value(true)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let sample_rate = input.scalar("sample rate")?;
+        if sample_rate < MIN_SAMPLE_RATE {
+            bail!("The sample rate must be at least {} S/s", MIN_SAMPLE_RATE);
+        }
+        let deviation = input.scalar("deviation")?;
+        if deviation <= 0.0 {
+            bail!("The deviation must be positive");
+        }
+        Ok(NbfmReceiver::new(
+            sample_rate,
+            deviation,
+            input.scalar("gain")?,
+            input.scalar("squelch")?,
+            Tone::parse(&input.text("tone")?)?,
+            input.toggle("tone filter")?,
+            input.status()?,
+        ))
+    }
+}
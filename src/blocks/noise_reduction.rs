@@ -0,0 +1,41 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::noise_reduction::NoiseReduction;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct NoiseReductionBlock {}
+impl ESDRBlock for NoiseReductionBlock {
+    fn name(self) -> &'static str {
+        "Noise Reduction"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("strength")
+                .initial_value(1.5)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("floor")
+                .initial_value(-20.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(NoiseReduction::new(
+            input.scalar("strength")?,
+            input.scalar("floor")?,
+            input.work_timer(),
+        ))
+    }
+}
@@ -0,0 +1,61 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::power_squelch::PowerSquelch;
+use crate::kernels::power_squelch::SQUELCH_MODES;
+use crate::params::choice::Choice;
+use crate::params::Param;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct PowerSquelchBlock {}
+impl ESDRBlock for PowerSquelchBlock {
+    fn name(self) -> &'static str {
+        "Power Squelch"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("threshold")
+                .initial_value(-40.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("hysteresis")
+                .initial_value(3.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("alpha").initial_value(0.001).build(),
+            Param::choice("mode")
+                .choices(
+                    SQUELCH_MODES
+                        .iter()
+                        .map(|name| Choice::new(name, name))
+                        .collect::<Vec<_>>(),
+                )
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let alpha = input.scalar("alpha")?;
+        if alpha <= 0.0 || alpha > 1.0 {
+            bail!("The averaging alpha must be between 0 and 1");
+        }
+        let hysteresis = input.scalar("hysteresis")?;
+        if hysteresis < 0.0 {
+            bail!("The hysteresis can't be negative");
+        }
+        PowerSquelch::new(
+            alpha,
+            input.scalar("threshold")?,
+            hysteresis,
+            &input.choice("mode")?,
+            input.status()?,
+        )
+    }
+}
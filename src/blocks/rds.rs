@@ -0,0 +1,72 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::dsp::firdes;
+use crate::dsp::firdes::DesignMethod;
+use crate::dsp::firdes::FilterShape;
+use crate::dsp::firdes::FilterSpec;
+use crate::dsp::firdes::Taps;
+use crate::kernels::rds::RdsReceiver;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+/// highest frequency in the multiplex used by RDS
+const RDS_TOP: f64 = 59_400.0;
+const RDS_BANDWIDTH: f64 = 2_400.0;
+/// rate the subcarrier is decimated to, about 8 samples per symbol
+const SUBCARRIER_RATE: f64 = 19_000.0;
+
+#[derive(Clone, Copy, Default)]
+pub struct RdsDecoderBlock {}
+impl ESDRBlock for RdsDecoderBlock {
+    fn name(self) -> &'static str {
+        "RDS Decoder"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::scalar("sample rate")
+                .initial_value((consts::AUDIO_RATE * consts::AUDIO_MULT) as f64)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let sample_rate = input.scalar("sample rate")?;
+        if sample_rate < 2.0 * RDS_TOP {
+            bail!(
+                "The multiplex must be sampled at {} S/s or more to carry RDS",
+                2.0 * RDS_TOP
+            );
+        }
+        let taps = firdes::design(&FilterSpec {
+            shape: FilterShape::Lowpass,
+            method: DesignMethod::Kaiser,
+            sample_rate,
+            cutoff: RDS_BANDWIDTH,
+            cutoff_high: 0.0,
+            transition: RDS_BANDWIDTH / 2.0,
+            attenuation: 60.0,
+            symbol_rate: 0.0,
+            rolloff: 0.0,
+            num_taps: 0,
+            complex: false,
+        })?;
+        let taps = match taps {
+            Taps::Real(taps) => taps,
+            Taps::Complex(_) => bail!("The subcarrier filter must be real"),
+        };
+        let decimation = (sample_rate / SUBCARRIER_RATE).floor().max(1.0) as usize;
+        Ok(RdsReceiver::new(
+            sample_rate,
+            taps,
+            decimation,
+            input.status()?,
+        ))
+    }
+}
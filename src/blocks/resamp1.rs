@@ -3,6 +3,7 @@ use crate::blocks::ESDRBlockInput;
 use crate::consts;
 use crate::params::Param;
 
+use anyhow::Result;
 use futuresdr::blocks::FirBuilder;
 use futuresdr::num_complex::Complex32;
 use futuresdr::runtime::Block;
@@ -21,9 +22,9 @@ impl ESDRBlock for Resamp1Block {
         ]
     }
 
-    fn block(self, _input: ESDRBlockInput) -> Block {
+    fn block(self, _input: ESDRBlockInput) -> Result<Block> {
         let interp = (consts::AUDIO_RATE * consts::AUDIO_MULT) as usize;
         let decim = consts::RATE as usize;
-        FirBuilder::new_resampling::<Complex32>(interp, decim)
+        Ok(FirBuilder::new_resampling::<Complex32>(interp, decim))
     }
 }
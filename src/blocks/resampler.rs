@@ -0,0 +1,217 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::blocks::NodeCache;
+use crate::consts;
+use crate::dsp::firdes;
+use crate::kernels::arbitrary_resampler::ArbitraryResampler;
+use crate::params::Param;
+
+use anyhow::bail;
+use anyhow::Result;
+use eframe::egui;
+use futuresdr::blocks::FirBuilder;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+
+/// branches of the arbitrary resampler filterbank
+const ARBITRARY_PHASES: usize = 32;
+
+/// Anti-aliasing params, shared by the resamplers.
+fn filter_params() -> Vec<Param> {
+    vec![
+        Param::scalar("passband").initial_value(0.8).build(),
+        Param::scalar("attenuation").initial_value(60.0).build(),
+    ]
+}
+
+/// Designs the filterbank of a resampler by `rate` with `phases` branches.
+/// The passband is given as a fraction of the lower of the two Nyquist
+/// frequencies, the stopband starts right at it.
+fn design(input: &ESDRBlockInput, phases: usize, rate: f64) -> Result<Vec<f32>> {
+    let passband = input.scalar("passband")?;
+    if passband <= 0.0 || passband >= 1.0 {
+        bail!("The passband must be between 0 and 1");
+    }
+    let band = rate.min(1.0);
+    firdes::polyphase_lowpass(
+        phases,
+        (1.0 + passband) / 4.0 * band,
+        (1.0 - passband) / 2.0 * band,
+        input.scalar("attenuation")?,
+    )
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Interpolation and decimation reduced to their lowest terms.
+fn ratio(interp: f64, decim: f64) -> Result<(usize, usize)> {
+    let (interp, decim) = (interp.round(), decim.round());
+    if interp < 1.0 || decim < 1.0 {
+        bail!("The interpolation and decimation must be at least 1");
+    }
+    let (interp, decim) = (interp as usize, decim as usize);
+    let divisor = gcd(interp, decim);
+    Ok((interp / divisor, decim / divisor))
+}
+
+fn rational_block(input: &ESDRBlockInput, (interp, decim): (usize, usize)) -> Result<Block> {
+    let taps = design(input, interp, interp as f64 / decim as f64)?;
+    Ok(FirBuilder::new_resampling_with_taps::<Complex32, f32, _>(
+        interp, decim, taps,
+    ))
+}
+
+/// Shows the reduced ratio and the size of the filter, or why it can't be
+/// designed.
+fn rational_ui(
+    ui: &mut egui::Ui,
+    input: &ESDRBlockInput,
+    cache: &NodeCache,
+    ratio: Result<(usize, usize)>,
+) {
+    let key = format!(
+        "{:?} {:?}",
+        ratio.as_ref().ok(),
+        ["passband", "attenuation"].map(|name| input.scalar(name).ok())
+    );
+    let summary = cache.get(key, || {
+        ratio
+            .and_then(|(interp, decim)| {
+                let taps = design(input, interp, interp as f64 / decim as f64)?;
+                Ok(format!("{}/{}, {} taps", interp, decim, taps.len()))
+            })
+            .map_err(|e| format!("{:#}", e))
+    });
+    match summary {
+        Ok(summary) => {
+            ui.label(summary);
+        }
+        Err(e) => {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct RationalResamplerBlock {}
+impl ESDRBlock for RationalResamplerBlock {
+    fn name(self) -> &'static str {
+        "Rational Resampler"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("interpolation").initial_value(1.0).build(),
+            Param::scalar("decimation").initial_value(1.0).build(),
+        ];
+        params.extend(filter_params());
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let ratio = ratio(input.scalar("interpolation")?, input.scalar("decimation")?)?;
+        rational_block(&input, ratio)
+    }
+
+    fn node_ui(self, ui: &mut egui::Ui, input: ESDRBlockInput, cache: &NodeCache) {
+        let ratio = input
+            .scalar("interpolation")
+            .and_then(|interp| ratio(interp, input.scalar("decimation")?));
+        rational_ui(ui, &input, cache, ratio);
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct DecimatorBlock {}
+impl ESDRBlock for DecimatorBlock {
+    fn name(self) -> &'static str {
+        "Decimating FIR"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("decimation").initial_value(2.0).build(),
+        ];
+        params.extend(filter_params());
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let ratio = ratio(1.0, input.scalar("decimation")?)?;
+        rational_block(&input, ratio)
+    }
+
+    fn node_ui(self, ui: &mut egui::Ui, input: ESDRBlockInput, cache: &NodeCache) {
+        let ratio = input
+            .scalar("decimation")
+            .and_then(|decim| ratio(1.0, decim));
+        rational_ui(ui, &input, cache, ratio);
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ArbitraryResamplerBlock {}
+impl ESDRBlock for ArbitraryResamplerBlock {
+    fn name(self) -> &'static str {
+        "Arbitrary Resampler"
+    }
+
+    fn params(self) -> Vec<Param> {
+        let mut params = vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("input rate")
+                .initial_value(consts::RATE)
+                .build(),
+            Param::scalar("output rate")
+                .initial_value(consts::AUDIO_RATE as f64)
+                .build(),
+        ];
+        params.extend(filter_params());
+        params
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let input_rate = input.scalar("input rate")?;
+        let output_rate = input.scalar("output rate")?;
+        if input_rate <= 0.0 || output_rate <= 0.0 {
+            bail!("The sample rates must be positive");
+        }
+        let rate = output_rate / input_rate;
+        let taps = design(&input, ARBITRARY_PHASES, rate)?;
+        Ok(ArbitraryResampler::<Complex32>::new(
+            rate,
+            ARBITRARY_PHASES,
+            taps,
+            input.work_timer(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduces_the_ratio() {
+        assert_eq!(ratio(4.0, 6.0).unwrap(), (2, 3));
+        assert_eq!(ratio(1.0, 10.0).unwrap(), (1, 10));
+        assert_eq!(ratio(48.0, 48.0).unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn rejects_ratios_below_one() {
+        assert!(ratio(0.0, 2.0).is_err());
+        assert!(ratio(3.0, 0.4).is_err());
+    }
+}
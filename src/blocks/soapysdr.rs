@@ -1,11 +1,147 @@
 use crate::blocks::ESDRBlock;
 use crate::blocks::ESDRBlockInput;
 use crate::consts;
+use crate::kernels::soapy_source::SoapySource;
+use crate::kernels::soapy_source::SoapySourceConfig;
+use crate::kernels::soapy_source::GAIN_ELEMENT_SUFFIX;
+use crate::params::choice::Choice;
 use crate::params::Param;
+use crate::soapy;
+use crate::soapy::Capabilities;
+use crate::soapy::ChannelConfig;
+use crate::soapy::Direction;
+use crate::soapy::SoapyDriver;
+use crate::soapy::SoapySDRDriver;
 
-use futuresdr::blocks::SoapySourceBuilder;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Result;
 use futuresdr::runtime::Block;
 
+/// how long enumerated devices are listed before enumerating them again
+const DEVICES_MAX_AGE: Duration = Duration::from_secs(5);
+
+/// Devices found by the last enumeration. Some drivers take seconds to
+/// enumerate, so it runs in the background instead of on the UI thread.
+struct DeviceCache {
+    devices: Vec<Choice>,
+    enumerated: Option<Instant>,
+    enumerating: bool,
+}
+
+static DEVICES: Mutex<DeviceCache> = Mutex::new(DeviceCache {
+    devices: Vec::new(),
+    enumerated: None,
+    enumerating: false,
+});
+
+/// Lists the devices found so far, and enumerates them again in the
+/// background if the list is out of date.
+pub fn devices() -> Vec<Choice> {
+    let mut cache = DEVICES.lock().unwrap();
+    let outdated = cache
+        .enumerated
+        .map_or(true, |enumerated| enumerated.elapsed() > DEVICES_MAX_AGE);
+    if outdated && !cache.enumerating {
+        cache.enumerating = true;
+        thread::spawn(enumerate_devices);
+    }
+    cache.devices.clone()
+}
+
+fn enumerate_devices() {
+    let devices = match SoapySDRDriver.enumerate("") {
+        Ok(devices) => devices
+            .iter()
+            .map(|device| Choice::new(&device.label, &device.args))
+            .collect(),
+        Err(e) => {
+            log::warn!("{:#}", e);
+            vec![]
+        }
+    };
+    let mut cache = DEVICES.lock().unwrap();
+    cache.devices = devices;
+    cache.enumerated = Some(Instant::now());
+    cache.enumerating = false;
+}
+
+/// A device being read in the background, along with what each of its
+/// channels supports once known.
+struct Probe {
+    tx: bool,
+    args: String,
+    result: Option<Result<Vec<Capabilities>>>,
+}
+
+static PROBES: Mutex<Vec<Probe>> = Mutex::new(Vec::new());
+
+/// Reads what each channel of a device supports in the background, as
+/// opening some devices takes seconds. Returns None until it is known, after
+/// which the next call reads the device again.
+pub fn capabilities(args: &str, direction: Direction) -> Option<Result<Vec<Capabilities>>> {
+    let tx = matches!(direction, Direction::Tx);
+    let mut probes = PROBES.lock().unwrap();
+    match probes
+        .iter()
+        .position(|probe| probe.tx == tx && probe.args == args)
+    {
+        Some(i) if probes[i].result.is_none() => None,
+        Some(i) => probes.remove(i).result,
+        None => {
+            probes.push(Probe {
+                tx,
+                args: args.to_string(),
+                result: None,
+            });
+            let args = args.to_string();
+            thread::spawn(move || {
+                let result = soapy::driver(&args)
+                    .open(&args, direction)
+                    .and_then(|device| {
+                        let num_channels = device.capabilities(0)?.num_channels;
+                        (0..num_channels)
+                            .map(|channel| device.capabilities(channel))
+                            .collect()
+                    });
+                let mut probes = PROBES.lock().unwrap();
+                if let Some(probe) = probes
+                    .iter_mut()
+                    .find(|probe| probe.tx == tx && probe.args == args)
+                {
+                    probe.result = Some(result);
+                }
+            });
+            None
+        }
+    }
+}
+
+/// Channel 0 goes to "out", the others to "out <channel>".
+fn output_name(channel: usize) -> String {
+    match channel {
+        0 => "out".to_string(),
+        _ => format!("out {}", channel),
+    }
+}
+
+fn output_channel(name: &str) -> Option<usize> {
+    match name {
+        "out" => Some(0),
+        _ => name.strip_prefix("out ")?.parse().ok(),
+    }
+}
+
+pub fn device_args(input: &ESDRBlockInput) -> Result<String> {
+    Ok(soapy::join_args(
+        &input.choice("device")?,
+        &input.text("device args")?,
+    ))
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct SoapySDRBlock {}
 impl ESDRBlock for SoapySDRBlock {
@@ -16,20 +152,128 @@ impl ESDRBlock for SoapySDRBlock {
     fn params(self) -> Vec<Param> {
         vec![
             Param::output_stream("out").build(),
+            Param::choice("device")
+                .choices(vec![Choice::new("First available", "")])
+                .refresh(devices)
+                .build(),
+            Param::text("device args").build(),
             Param::scalar("freq")
                 .initial_value(90900000.0)
                 .allow_updates(true)
                 .build(),
-            Param::scalar("gain").initial_value(30.0).build(),
+            Param::choice("antenna")
+                .choices(vec![Choice::new("Default", "")])
+                .allow_updates(true)
+                .build(),
+            Param::scalar("bandwidth").allow_updates(true).build(),
+            Param::toggle("agc").allow_updates(true).build(),
+            Param::scalar("gain")
+                .initial_value(30.0)
+                .allow_updates(true)
+                .build(),
+            Param::toggle("element gains").build(),
+            Param::scalar("ppm").allow_updates(true).build(),
+            Param::scalar("freq offset").build(),
+            Param::toggle("dc offset auto").allow_updates(true).build(),
+            Param::toggle("iq balance auto").allow_updates(true).build(),
+            Param::text("settings").allow_updates(true).build(),
+            Param::toggle("reconnect").build(),
+            Param::text("sync group").build(),
         ]
     }
 
-    fn block(self, input: ESDRBlockInput) -> Block {
-        SoapySourceBuilder::new()
-            .filter("")
-            .freq(input.scalar("freq") + consts::FREQ_OFFSET)
-            .sample_rate(consts::RATE)
-            .gain(input.scalar("gain"))
-            .build()
+    /// Lists the antennas of the selected device, and adds an output for each
+    /// of its channels and a parameter for each of its gain elements (e.g.
+    /// "LNA gain"), which are used instead of "gain" if "element gains" is set.
+    /// Antennas and gain elements apply to all the streamed channels, so only
+    /// those every channel has are listed.
+    fn refresh(self, input: ESDRBlockInput) -> Result<Option<Vec<Param>>> {
+        let channels = match capabilities(&device_args(&input)?, Direction::Rx) {
+            Some(channels) => channels?,
+            None => return Ok(None),
+        };
+        let on_every_channel = |name: &String, list: fn(&Capabilities) -> &Vec<String>| {
+            channels.iter().all(|channel| list(channel).contains(name))
+        };
+        let mut antennas = vec![Choice::new("Default", "")];
+        antennas.extend(
+            channels[0]
+                .antennas
+                .iter()
+                .filter(|antenna| on_every_channel(antenna, |channel| &channel.antennas))
+                .map(|antenna| Choice::new(antenna, antenna)),
+        );
+        let mut params = vec![Param::choice("antenna")
+            .choices(antennas)
+            .allow_updates(true)
+            .build()];
+        params.extend(
+            (1..channels.len()).map(|channel| Param::output_stream(&output_name(channel)).build()),
+        );
+        params.extend(
+            channels[0]
+                .gain_elements
+                .iter()
+                .filter(|element| on_every_channel(element, |channel| &channel.gain_elements))
+                .map(|element| {
+                    Param::scalar(&format!("{}{}", element, GAIN_ELEMENT_SUFFIX))
+                        .allow_updates(true)
+                        .build()
+                }),
+        );
+        Ok(Some(params))
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        // per-element gains only exist once the node has been refreshed
+        let mut gain_elements = vec![];
+        for name in input.param_names() {
+            if let Some(element) = name.strip_suffix(GAIN_ELEMENT_SUFFIX) {
+                gain_elements.push((element.to_string(), input.scalar(&name)?));
+            }
+        }
+        // only connected channels are streamed, so that a multi-channel device
+        // can also be used for a single one
+        let mut channels: Vec<(usize, String)> = input
+            .output_names()
+            .into_iter()
+            .filter(|name| input.output_connected(name))
+            .filter_map(|name| Some((output_channel(&name)?, name)))
+            .collect();
+        if channels.is_empty() {
+            channels.push((0, output_name(0)));
+        }
+        let freq_offset = input.scalar("freq offset")?;
+        let status = input.status()?;
+        let sync_group = input.text("sync group")?;
+        let config = SoapySourceConfig {
+            args: device_args(&input)?,
+            channels,
+            channel_config: ChannelConfig {
+                freq: input.scalar("freq")?,
+                sample_rate: consts::RATE,
+                gain: input.scalar("gain")?,
+                gain_elements,
+                element_gains: input.toggle("element gains")?,
+                antenna: input.choice("antenna")?,
+                bandwidth: input.scalar("bandwidth")?,
+                agc: input.toggle("agc")?,
+                ppm: input.scalar("ppm")?,
+                dc_offset_auto: input.toggle("dc offset auto")?,
+                iq_balance_auto: input.toggle("iq balance auto")?,
+            },
+            settings: soapy::parse_settings(&input.text("settings")?)?,
+            freq_offset,
+            reconnect: input.toggle("reconnect")?,
+            sync: match sync_group.trim() {
+                "" => None,
+                name => Some(status.sync_group(name)),
+            },
+        };
+        Ok(SoapySource::new(
+            soapy::driver(&config.args),
+            config,
+            status,
+        ))
     }
 }
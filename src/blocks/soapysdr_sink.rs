@@ -0,0 +1,87 @@
+use crate::blocks::soapysdr;
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::soapy_sink::SoapySink;
+use crate::kernels::soapy_sink::SoapySinkConfig;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::soapy;
+use crate::soapy::ChannelConfig;
+use crate::soapy::Direction;
+use crate::soapy::NullDriver;
+
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct SoapySDRSinkBlock {}
+impl ESDRBlock for SoapySDRSinkBlock {
+    fn name(self) -> &'static str {
+        "Soapy SDR Sink"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::choice("device")
+                .choices(vec![
+                    Choice::new("First available", ""),
+                    Choice::new("Null sink (no hardware)", NullDriver::ARGS),
+                ])
+                .refresh(soapysdr::devices)
+                .build(),
+            Param::text("device args").build(),
+            Param::scalar("freq")
+                .initial_value(433920000.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::RATE)
+                .build(),
+            Param::scalar("gain").allow_updates(true).build(),
+            Param::choice("antenna")
+                .choices(vec![Choice::new("Default", "")])
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn refresh(self, input: ESDRBlockInput) -> Result<Option<Vec<Param>>> {
+        let args = soapysdr::device_args(&input)?;
+        // only the first channel is used
+        let channels = match soapysdr::capabilities(&args, Direction::Tx) {
+            Some(channels) => channels?,
+            None => return Ok(None),
+        };
+        let mut antennas = vec![Choice::new("Default", "")];
+        antennas.extend(
+            channels[0]
+                .antennas
+                .iter()
+                .map(|antenna| Choice::new(antenna, antenna)),
+        );
+        Ok(Some(vec![Param::choice("antenna")
+            .choices(antennas)
+            .allow_updates(true)
+            .build()]))
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let config = SoapySinkConfig {
+            args: soapysdr::device_args(&input)?,
+            channel_config: ChannelConfig {
+                freq: input.scalar("freq")?,
+                sample_rate: input.scalar("sample rate")?,
+                gain: input.scalar("gain")?,
+                antenna: input.choice("antenna")?,
+                ..ChannelConfig::default()
+            },
+        };
+        Ok(SoapySink::new(
+            soapy::driver(&config.args),
+            config,
+            input.status()?,
+        ))
+    }
+}
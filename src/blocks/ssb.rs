@@ -0,0 +1,68 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::ssb::SsbReceiver;
+use crate::kernels::ssb::SSB_MODES;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct SsbDemodulatorBlock {}
+impl ESDRBlock for SsbDemodulatorBlock {
+    fn name(self) -> &'static str {
+        "SSB/CW Demodulator"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::AUDIO_RATE as f64)
+                .build(),
+            Param::choice("mode")
+                .choices(
+                    SSB_MODES
+                        .iter()
+                        .map(|name| Choice::new(name, name))
+                        .collect::<Vec<_>>(),
+                )
+                .allow_updates(true)
+                .build(),
+            Param::scalar("bandwidth")
+                .initial_value(2_700.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("shift")
+                .initial_value(0.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("pitch")
+                .initial_value(700.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("cw bandwidth")
+                .initial_value(500.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        SsbReceiver::new(
+            input.scalar("sample rate")?,
+            &input.choice("mode")?,
+            input.scalar("bandwidth")?,
+            input.scalar("shift")?,
+            input.scalar("pitch")?,
+            input.scalar("cw bandwidth")?,
+            input.status()?,
+        )
+    }
+}
@@ -0,0 +1,46 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::kernels::tone_filter::ToneFilter;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct ToneFilterBlock {}
+impl ESDRBlock for ToneFilterBlock {
+    fn name(self) -> &'static str {
+        "Tone Filter"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::AUDIO_RATE as f64)
+                .build(),
+            Param::scalar("low cut")
+                .initial_value(300.0)
+                .allow_updates(true)
+                .build(),
+            Param::scalar("high cut")
+                .initial_value(3_000.0)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        ToneFilter::new(
+            input.scalar("sample rate")?,
+            input.scalar("low cut")?,
+            input.scalar("high cut")?,
+            input.status()?,
+        )
+    }
+}
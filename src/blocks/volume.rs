@@ -0,0 +1,38 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::kernels::volume::Volume;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+#[derive(Clone, Copy, Default)]
+pub struct VolumeBlock {}
+impl ESDRBlock for VolumeBlock {
+    fn name(self) -> &'static str {
+        "Volume"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").item_type(StreamType::F32).build(),
+            Param::output_stream("out")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("volume")
+                .initial_value(0.0)
+                .allow_updates(true)
+                .build(),
+            Param::toggle("mute").allow_updates(true).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        Ok(Volume::new(
+            input.scalar("volume")?,
+            input.toggle("mute")?,
+            input.work_timer(),
+        ))
+    }
+}
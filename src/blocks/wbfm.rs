@@ -0,0 +1,98 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::consts;
+use crate::dsp::firdes;
+use crate::dsp::firdes::DesignMethod;
+use crate::dsp::firdes::FilterShape;
+use crate::dsp::firdes::FilterSpec;
+use crate::dsp::firdes::Taps;
+use crate::dsp::fm;
+use crate::kernels::wbfm::WbfmReceiver;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::params::StreamType;
+
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::runtime::Block;
+
+const AUDIO_BANDWIDTH: f64 = 15_000.0;
+const AUDIO_TRANSITION: f64 = 4_000.0;
+
+#[derive(Clone, Copy, Default)]
+pub struct WbfmReceiverBlock {}
+impl ESDRBlock for WbfmReceiverBlock {
+    fn name(self) -> &'static str {
+        "WBFM Receiver"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("left")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::output_stream("right")
+                .item_type(StreamType::F32)
+                .build(),
+            Param::scalar("sample rate")
+                .initial_value((consts::AUDIO_RATE * consts::AUDIO_MULT) as f64)
+                .build(),
+            Param::scalar("decimation")
+                .initial_value(consts::AUDIO_MULT as f64)
+                .build(),
+            Param::choice("de-emphasis")
+                .choices(
+                    fm::DEEMPHASIS
+                        .iter()
+                        .map(|(name, _)| Choice::new(name, name))
+                        .collect::<Vec<_>>(),
+                )
+                .allow_updates(true)
+                .build(),
+            Param::toggle("stereo")
+                .initial_value(true)
+                .allow_updates(true)
+                .build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let sample_rate = input.scalar("sample rate")?;
+        let decimation = input.scalar("decimation")?.round();
+        if decimation < 1.0 {
+            bail!("The decimation must be at least 1");
+        }
+        if sample_rate / decimation < 2.0 * AUDIO_BANDWIDTH {
+            bail!(
+                "The audio rate must be at least {} S/s",
+                2.0 * AUDIO_BANDWIDTH
+            );
+        }
+        let taps = firdes::design(&FilterSpec {
+            shape: FilterShape::Lowpass,
+            method: DesignMethod::Kaiser,
+            sample_rate,
+            cutoff: AUDIO_BANDWIDTH,
+            cutoff_high: 0.0,
+            transition: AUDIO_TRANSITION,
+            attenuation: 60.0,
+            symbol_rate: 0.0,
+            rolloff: 0.0,
+            num_taps: 0,
+            complex: false,
+        })?;
+        let taps = match taps {
+            Taps::Real(taps) => taps,
+            Taps::Complex(_) => bail!("The audio filter must be real"),
+        };
+        Ok(WbfmReceiver::new(
+            sample_rate,
+            taps,
+            decimation as usize,
+            fm::deemphasis_tau(&input.choice("de-emphasis")?)?,
+            input.toggle("stereo")?,
+            input.status()?,
+        ))
+    }
+}
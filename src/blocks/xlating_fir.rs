@@ -0,0 +1,106 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
+use crate::blocks::NodeCache;
+use crate::consts;
+use crate::dsp::firdes;
+use crate::dsp::firdes::DesignMethod;
+use crate::dsp::firdes::FilterShape;
+use crate::dsp::firdes::FilterSpec;
+use crate::dsp::firdes::Taps;
+use crate::kernels::xlating_fir::XlatingFir;
+use crate::params::Param;
+
+use anyhow::bail;
+use anyhow::Result;
+use eframe::egui;
+use futuresdr::runtime::Block;
+
+/// Designs the channel filter, and checks the decimation.
+fn design(input: &ESDRBlockInput) -> Result<(Vec<f32>, usize)> {
+    let decimation = input.scalar("decimation")?.round();
+    if decimation < 1.0 {
+        bail!("The decimation must be at least 1");
+    }
+    let taps = firdes::design(&FilterSpec {
+        shape: FilterShape::Lowpass,
+        method: DesignMethod::Kaiser,
+        sample_rate: input.scalar("sample rate")?,
+        cutoff: input.scalar("cutoff")?,
+        cutoff_high: 0.0,
+        transition: input.scalar("transition")?,
+        attenuation: input.scalar("attenuation")?,
+        symbol_rate: 0.0,
+        rolloff: 0.0,
+        num_taps: 0,
+        complex: false,
+    })?;
+    match taps {
+        Taps::Real(taps) => Ok((taps, decimation as usize)),
+        Taps::Complex(_) => bail!("The channel filter must be real"),
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct XlatingFirBlock {}
+impl ESDRBlock for XlatingFirBlock {
+    fn name(self) -> &'static str {
+        "Frequency Xlating FIR"
+    }
+
+    fn params(self) -> Vec<Param> {
+        vec![
+            Param::input_stream("in").build(),
+            Param::output_stream("out").build(),
+            Param::scalar("offset").allow_updates(true).build(),
+            Param::scalar("sample rate")
+                .initial_value(consts::RATE)
+                .build(),
+            Param::scalar("decimation").initial_value(4.0).build(),
+            Param::scalar("cutoff").initial_value(100_000.0).build(),
+            Param::scalar("transition").initial_value(25_000.0).build(),
+            Param::scalar("attenuation").initial_value(60.0).build(),
+        ]
+    }
+
+    fn block(self, input: ESDRBlockInput) -> Result<Block> {
+        let (taps, decimation) = design(&input)?;
+        Ok(XlatingFir::new(
+            input.scalar("offset")?,
+            input.scalar("sample rate")?,
+            taps,
+            decimation,
+            input.work_timer(),
+        ))
+    }
+
+    fn node_ui(self, ui: &mut egui::Ui, input: ESDRBlockInput, cache: &NodeCache) {
+        let key = format!(
+            "{:?}",
+            [
+                "sample rate",
+                "decimation",
+                "cutoff",
+                "transition",
+                "attenuation"
+            ]
+            .map(|name| input.scalar(name).ok())
+        );
+        let summary = cache.get(key, || {
+            let (taps, decimation) = design(&input).map_err(|e| format!("{:#}", e))?;
+            let sample_rate = input.scalar("sample rate").map_err(|e| e.to_string())?;
+            Ok::<_, String>(format!(
+                "{} taps, {} S/s out",
+                taps.len(),
+                sample_rate / decimation as f64
+            ))
+        });
+        match summary {
+            Ok(summary) => {
+                ui.label(summary);
+            }
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+    }
+}
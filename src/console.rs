@@ -0,0 +1,215 @@
+use crate::ui::ESDRGraph;
+use crate::ui::ESDRResponse;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use chrono::DateTime;
+use chrono::Local;
+use eframe::egui;
+use egui_node_graph::NodeId;
+
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Debug,
+        Severity::Info,
+        Severity::Warn,
+        Severity::Error,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn color(self) -> egui::Color32 {
+        match self {
+            Severity::Debug => egui::Color32::GRAY,
+            Severity::Info => egui::Color32::LIGHT_BLUE,
+            Severity::Warn => egui::Color32::YELLOW,
+            Severity::Error => egui::Color32::RED,
+        }
+    }
+}
+
+impl From<log::Level> for Severity {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Severity::Error,
+            log::Level::Warn => Severity::Warn,
+            log::Level::Info => Severity::Info,
+            log::Level::Debug | log::Level::Trace => Severity::Debug,
+        }
+    }
+}
+
+/// Context attached to errors coming from a specific node, so that the
+/// console can link back to it.
+#[derive(Debug)]
+pub struct NodeContext {
+    pub node_id: NodeId,
+    pub label: String,
+}
+
+impl NodeContext {
+    pub fn new(graph: &ESDRGraph, node_id: NodeId) -> NodeContext {
+        NodeContext {
+            node_id,
+            label: graph[node_id].label.clone(),
+        }
+    }
+}
+
+impl fmt::Display for NodeContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Node \"{}\"", self.label)
+    }
+}
+
+#[derive(Clone)]
+pub struct LogEntry {
+    time: DateTime<Local>,
+    severity: Severity,
+    node_id: Option<NodeId>,
+    message: String,
+}
+
+impl LogEntry {
+    fn timestamp(&self) -> String {
+        self.time.format("%H:%M:%S%.3f").to_string()
+    }
+}
+
+type Entries = Arc<Mutex<VecDeque<LogEntry>>>;
+
+fn push(entries: &Entries, entry: LogEntry) {
+    let mut entries = entries.lock().unwrap();
+    if entries.len() == MAX_ENTRIES {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+/// Forwards everything logged through the `log` crate (including the FutureSDR
+/// runtime and its blocks) to the console, while still printing to stderr.
+struct ConsoleLogger {
+    entries: Entries,
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        eprintln!(
+            "[{}] {}: {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        push(
+            &self.entries,
+            LogEntry {
+                time: Local::now(),
+                severity: record.level().into(),
+                node_id: None,
+                message: format!("{}: {}", record.target(), record.args()),
+            },
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+pub struct Console {
+    entries: Entries,
+    min_severity: Severity,
+    pub open: bool,
+}
+
+impl Console {
+    pub fn init() -> Console {
+        let entries = Entries::default();
+        let logger = ConsoleLogger {
+            entries: entries.clone(),
+        };
+        if log::set_boxed_logger(Box::new(logger)).is_ok() {
+            log::set_max_level(log::LevelFilter::Info);
+        }
+        Console {
+            entries,
+            min_severity: Severity::Info,
+            open: false,
+        }
+    }
+
+    pub fn log(&self, severity: Severity, node_id: Option<NodeId>, message: String) {
+        push(
+            &self.entries,
+            LogEntry {
+                time: Local::now(),
+                severity,
+                node_id,
+                message,
+            },
+        );
+    }
+
+    pub fn error(&mut self, error: &anyhow::Error) {
+        let node_id = error.downcast_ref::<NodeContext>().map(|c| c.node_id);
+        self.log(Severity::Error, node_id, format!("{:#}", error));
+        self.open = true;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Vec<ESDRResponse> {
+        let mut responses = vec![];
+        ui.horizontal(|ui| {
+            ui.label("Severity");
+            for severity in Severity::ALL {
+                ui.selectable_value(&mut self.min_severity, severity, severity.name());
+            }
+            if ui.button("Clear").clicked() {
+                self.entries.lock().unwrap().clear();
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .stick_to_bottom()
+            .show(ui, |ui| {
+                let entries = self.entries.lock().unwrap();
+                for entry in entries.iter().filter(|e| e.severity >= self.min_severity) {
+                    ui.horizontal(|ui| {
+                        ui.monospace(entry.timestamp());
+                        ui.colored_label(entry.severity.color(), entry.severity.name());
+                        if let Some(node_id) = entry.node_id {
+                            if ui.small_button("⮩").on_hover_text("Jump to node").clicked() {
+                                responses.push(ESDRResponse::JumpToNode(node_id));
+                            }
+                        }
+                        ui.label(&entry.message);
+                    });
+                }
+            });
+        responses
+    }
+}
@@ -1,5 +1,4 @@
 // TODO: get rid of these constants as we move towards more reusable blocks
 pub const RATE: f64 = 1000000.0;
-pub const FREQ_OFFSET: f64 = 250000.0;
 pub const AUDIO_RATE: u32 = 48000;
 pub const AUDIO_MULT: u32 = 5;
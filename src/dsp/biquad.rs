@@ -0,0 +1,66 @@
+use std::f64::consts::PI;
+
+/// Second order IIR section, with coefficients from the RBJ audio EQ
+/// cookbook.
+#[derive(Clone, Debug)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Biquad {
+        Biquad {
+            b0: (b[0] / a[0]) as f32,
+            b1: (b[1] / a[0]) as f32,
+            b2: (b[2] / a[0]) as f32,
+            a1: (a[1] / a[0]) as f32,
+            a2: (a[2] / a[0]) as f32,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    fn omega(freq: f64, q: f64, sample_rate: f64) -> (f64, f64) {
+        let w = 2.0 * PI * freq / sample_rate;
+        (w.cos(), w.sin() / (2.0 * q))
+    }
+
+    pub fn lowpass(freq: f64, q: f64, sample_rate: f64) -> Biquad {
+        let (cos, alpha) = Biquad::omega(freq, q, sample_rate);
+        Biquad::new(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    pub fn highpass(freq: f64, q: f64, sample_rate: f64) -> Biquad {
+        let (cos, alpha) = Biquad::omega(freq, q, sample_rate);
+        Biquad::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Bandpass with a peak gain of 1.
+    pub fn bandpass(freq: f64, q: f64, sample_rate: f64) -> Biquad {
+        let (cos, alpha) = Biquad::omega(freq, q, sample_rate);
+        Biquad::new([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    pub fn filter(&mut self, x: f32) -> f32 {
+        // transposed direct form II
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// Q of the two sections of a 4th order Butterworth filter.
+pub const BUTTERWORTH_Q: [f64; 2] = [0.541_196_1, 1.306_563];
@@ -0,0 +1,851 @@
+use std::f64::consts::PI;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use futuresdr::num_complex::Complex64;
+
+const MAX_TAPS: usize = 4095;
+const REMEZ_GRID_DENSITY: usize = 16;
+const REMEZ_MAX_ITERATIONS: usize = 40;
+/// span of the pulse shaping filters when the number of taps isn't given
+const RRC_SPAN_SYMBOLS: f64 = 11.0;
+const GAUSSIAN_SPAN_SYMBOLS: f64 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterShape {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Bandstop,
+    RootRaisedCosine,
+    Gaussian,
+    Hilbert,
+}
+
+impl FilterShape {
+    pub const ALL: [FilterShape; 7] = [
+        FilterShape::Lowpass,
+        FilterShape::Highpass,
+        FilterShape::Bandpass,
+        FilterShape::Bandstop,
+        FilterShape::RootRaisedCosine,
+        FilterShape::Gaussian,
+        FilterShape::Hilbert,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FilterShape::Lowpass => "lowpass",
+            FilterShape::Highpass => "highpass",
+            FilterShape::Bandpass => "bandpass",
+            FilterShape::Bandstop => "bandstop",
+            FilterShape::RootRaisedCosine => "root raised cosine",
+            FilterShape::Gaussian => "gaussian",
+            FilterShape::Hilbert => "hilbert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<FilterShape> {
+        FilterShape::ALL
+            .into_iter()
+            .find(|shape| shape.name() == name)
+            .ok_or_else(|| anyhow!("Unknown filter shape \"{}\"", name))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DesignMethod {
+    Kaiser,
+    Hamming,
+    Hann,
+    Blackman,
+    Rectangular,
+    Equiripple,
+}
+
+impl DesignMethod {
+    pub const ALL: [DesignMethod; 6] = [
+        DesignMethod::Kaiser,
+        DesignMethod::Hamming,
+        DesignMethod::Hann,
+        DesignMethod::Blackman,
+        DesignMethod::Rectangular,
+        DesignMethod::Equiripple,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DesignMethod::Kaiser => "kaiser window",
+            DesignMethod::Hamming => "hamming window",
+            DesignMethod::Hann => "hann window",
+            DesignMethod::Blackman => "blackman window",
+            DesignMethod::Rectangular => "rectangular window",
+            DesignMethod::Equiripple => "equiripple",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<DesignMethod> {
+        DesignMethod::ALL
+            .into_iter()
+            .find(|method| method.name() == name)
+            .ok_or_else(|| anyhow!("Unknown design method \"{}\"", name))
+    }
+
+    /// Estimates how many taps are needed for a given transition width (in
+    /// cycles per sample) and stopband attenuation.
+    fn estimate_taps(self, transition: f64, attenuation: f64) -> f64 {
+        match self {
+            DesignMethod::Kaiser => (attenuation - 7.95) / (14.36 * transition) + 1.0,
+            DesignMethod::Hamming => 3.3 / transition,
+            DesignMethod::Hann => 3.1 / transition,
+            DesignMethod::Blackman => 5.5 / transition,
+            DesignMethod::Rectangular => 0.9 / transition,
+            DesignMethod::Equiripple => (attenuation - 13.0) / (14.6 * transition) + 1.0,
+        }
+    }
+}
+
+/// Everything needed to design a filter. Frequencies are in Hz, and the
+/// fields which don't apply to the shape are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterSpec {
+    pub shape: FilterShape,
+    pub method: DesignMethod,
+    pub sample_rate: f64,
+    /// cutoff of lowpass and highpass filters, lower edge of band filters
+    pub cutoff: f64,
+    /// upper edge of band filters
+    pub cutoff_high: f64,
+    pub transition: f64,
+    /// stopband attenuation in dB, used to size the filter
+    pub attenuation: f64,
+    pub symbol_rate: f64,
+    /// excess bandwidth of root raised cosine filters, BT of gaussian ones
+    pub rolloff: f64,
+    /// 0 to estimate it from the other parameters
+    pub num_taps: usize,
+    /// complex taps allow band filters which aren't symmetric around DC
+    pub complex: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum Taps {
+    Real(Vec<f32>),
+    Complex(Vec<Complex32>),
+}
+
+impl Taps {
+    pub fn len(&self) -> usize {
+        match self {
+            Taps::Real(taps) => taps.len(),
+            Taps::Complex(taps) => taps.len(),
+        }
+    }
+
+    /// Magnitude response as (frequency in Hz, gain in dB) pairs. Real taps
+    /// have a symmetric response, so only positive frequencies are returned.
+    pub fn response(&self, sample_rate: f64, points: usize) -> Vec<(f64, f64)> {
+        let (taps, start): (Vec<Complex64>, f64) = match self {
+            Taps::Real(taps) => (
+                taps.iter()
+                    .map(|tap| Complex64::new(*tap as f64, 0.0))
+                    .collect(),
+                0.0,
+            ),
+            Taps::Complex(taps) => (
+                taps.iter()
+                    .map(|tap| Complex64::new(tap.re as f64, tap.im as f64))
+                    .collect(),
+                -0.5,
+            ),
+        };
+        (0..points)
+            .map(|point| {
+                let f = start + (0.5 - start) * point as f64 / (points - 1) as f64;
+                let h: Complex64 = taps
+                    .iter()
+                    .enumerate()
+                    .map(|(n, tap)| tap * Complex64::from_polar(1.0, -2.0 * PI * f * n as f64))
+                    .sum();
+                (f * sample_rate, 20.0 * (h.norm() + 1e-12).log10())
+            })
+            .collect()
+    }
+}
+
+pub fn design(spec: &FilterSpec) -> Result<Taps> {
+    if spec.sample_rate <= 0.0 {
+        bail!("The sample rate must be positive");
+    }
+    let fc = spec.cutoff / spec.sample_rate;
+    let fh = spec.cutoff_high / spec.sample_rate;
+    let transition = spec.transition / spec.sample_rate;
+
+    let taps = match spec.shape {
+        FilterShape::Lowpass => {
+            check_edges(&[fc])?;
+            real(spec, &[(0.0, fc, 1.0), (fc, 0.5, 0.0)], transition)?
+        }
+        FilterShape::Highpass => {
+            check_edges(&[fc])?;
+            real(spec, &[(0.0, fc, 0.0), (fc, 0.5, 1.0)], transition)?
+        }
+        FilterShape::Bandpass | FilterShape::Bandstop if spec.complex => {
+            if fc <= -0.5 || fh >= 0.5 || fh <= fc {
+                bail!("The band must be within the sample rate, with the low cutoff first");
+            }
+            // a lowpass prototype shifted to the center of the band, which
+            // doesn't have to be symmetric around DC
+            let half_width = (fh - fc) / 2.0;
+            let prototype = real(
+                spec,
+                &[(0.0, half_width, 1.0), (half_width, 0.5, 0.0)],
+                transition,
+            )?;
+            let mut taps = shift(&prototype, (fc + fh) / 2.0);
+            if spec.shape == FilterShape::Bandstop {
+                let center = (taps.len() - 1) / 2;
+                for (n, tap) in taps.iter_mut().enumerate() {
+                    *tap = if n == center { 1.0 - *tap } else { -*tap };
+                }
+            }
+            return Ok(Taps::Complex(to_complex32(&taps)));
+        }
+        FilterShape::Bandpass => {
+            check_edges(&[fc, fh])?;
+            real(
+                spec,
+                &[(0.0, fc, 0.0), (fc, fh, 1.0), (fh, 0.5, 0.0)],
+                transition,
+            )?
+        }
+        FilterShape::Bandstop => {
+            check_edges(&[fc, fh])?;
+            real(
+                spec,
+                &[(0.0, fc, 1.0), (fc, fh, 0.0), (fh, 0.5, 1.0)],
+                transition,
+            )?
+        }
+        FilterShape::RootRaisedCosine => {
+            let sps = samples_per_symbol(spec)?;
+            if spec.rolloff <= 0.0 || spec.rolloff > 1.0 {
+                bail!("The rolloff must be between 0 and 1");
+            }
+            let n = taps_or(spec, RRC_SPAN_SYMBOLS * sps)?;
+            normalize(root_raised_cosine(sps, spec.rolloff, n))
+        }
+        FilterShape::Gaussian => {
+            let sps = samples_per_symbol(spec)?;
+            if spec.rolloff <= 0.0 {
+                bail!("The BT product must be positive");
+            }
+            let n = taps_or(spec, GAUSSIAN_SPAN_SYMBOLS * sps)?;
+            normalize(gaussian(sps, spec.rolloff, n))
+        }
+        FilterShape::Hilbert => {
+            if spec.method == DesignMethod::Equiripple {
+                bail!("Hilbert filters can only be designed with a window");
+            }
+            let n = num_taps(spec, transition)?;
+            let window = window(spec.method, n, spec.attenuation);
+            let center = (n - 1) as f64 / 2.0;
+            let hilbert: Vec<f64> = (0..n)
+                .map(|i| {
+                    let m = (i as f64 - center).round() as i64;
+                    if m % 2 == 0 {
+                        0.0
+                    } else {
+                        2.0 / (PI * m as f64) * window[i]
+                    }
+                })
+                .collect();
+            if spec.complex {
+                // passes positive frequencies only, i.e. outputs the analytic
+                // signal of a real input
+                let taps = hilbert
+                    .iter()
+                    .enumerate()
+                    .map(|(i, h)| {
+                        let re = if i == (n - 1) / 2 { 1.0 } else { 0.0 };
+                        Complex64::new(re, *h)
+                    })
+                    .collect::<Vec<_>>();
+                return Ok(Taps::Complex(to_complex32(&taps)));
+            }
+            hilbert
+        }
+    };
+
+    let taps: Vec<f32> = taps.iter().map(|tap| *tap as f32).collect();
+    Ok(if spec.complex {
+        Taps::Complex(taps.iter().map(|tap| Complex32::new(*tap, 0.0)).collect())
+    } else {
+        Taps::Real(taps)
+    })
+}
+
+fn check_edges(edges: &[f64]) -> Result<()> {
+    let mut previous = 0.0;
+    for edge in edges {
+        if *edge <= previous || *edge >= 0.5 {
+            bail!("Cutoffs must be increasing and below half the sample rate");
+        }
+        previous = *edge;
+    }
+    Ok(())
+}
+
+fn samples_per_symbol(spec: &FilterSpec) -> Result<f64> {
+    if spec.symbol_rate <= 0.0 || spec.symbol_rate > spec.sample_rate {
+        bail!("The symbol rate must be positive and at most the sample rate");
+    }
+    Ok(spec.sample_rate / spec.symbol_rate)
+}
+
+/// Number of taps given by the spec, or the default. Always odd, so that the
+/// filter has a whole sample of delay.
+fn taps_or(spec: &FilterSpec, default: f64) -> Result<usize> {
+    let n = match spec.num_taps {
+        0 => default.ceil() as usize,
+        n => n,
+    };
+    if n > MAX_TAPS {
+        bail!(
+            "The filter would need {} taps, at most {} are supported",
+            n,
+            MAX_TAPS
+        );
+    }
+    Ok((n.max(3) - 1) / 2 * 2 + 1)
+}
+
+fn num_taps(spec: &FilterSpec, transition: f64) -> Result<usize> {
+    if spec.num_taps == 0 && transition <= 0.0 {
+        bail!("The transition width must be positive");
+    }
+    taps_or(
+        spec,
+        spec.method.estimate_taps(transition, spec.attenuation),
+    )
+}
+
+/// Designs a real filter from its ideal response, given as (start, end, gain)
+/// bands covering 0 to half the sample rate, in cycles per sample.
+fn real(spec: &FilterSpec, bands: &[(f64, f64, f64)], transition: f64) -> Result<Vec<f64>> {
+    match spec.method {
+        DesignMethod::Equiripple => {
+            let n = num_taps(spec, transition)?;
+            // leave room for the transitions between the bands
+            let bands: Vec<(f64, f64, f64, f64)> = bands
+                .iter()
+                .map(|&(start, end, gain)| {
+                    let start = if start > 0.0 {
+                        start + transition / 2.0
+                    } else {
+                        start
+                    };
+                    let end = if end < 0.5 {
+                        end - transition / 2.0
+                    } else {
+                        end
+                    };
+                    (start, end, gain, 1.0)
+                })
+                .collect();
+            if bands.iter().any(|(start, end, _, _)| start >= end) {
+                bail!("The transition width is too large for the bands");
+            }
+            remez(n, &bands)
+        }
+        _ => {
+            let n = num_taps(spec, transition)?;
+            Ok(window_design(spec.method, n, spec.attenuation, bands))
+        }
+    }
+}
+
+fn window_design(
+    method: DesignMethod,
+    n: usize,
+    attenuation: f64,
+    bands: &[(f64, f64, f64)],
+) -> Vec<f64> {
+    let window = window(method, n, attenuation);
+    let center = (n - 1) as f64 / 2.0;
+    // the ideal response of each band is the difference of two lowpasses
+    let lowpass = |cutoff: f64, m: f64| 2.0 * cutoff * sinc(2.0 * cutoff * m);
+    (0..n)
+        .map(|i| {
+            let m = i as f64 - center;
+            let ideal: f64 = bands
+                .iter()
+                .map(|&(start, end, gain)| gain * (lowpass(end, m) - lowpass(start, m)))
+                .sum();
+            ideal * window[i]
+        })
+        .collect()
+}
+
+/// Kaiser lowpass prototype of a polyphase filterbank with `phases` branches,
+/// i.e. designed at `phases` times the input rate. The cutoff and transition
+/// are in cycles per input sample, and the gain makes up for the zeros
+/// inserted between input samples. The number of taps is a multiple of
+/// `phases`.
+pub fn polyphase_lowpass(
+    phases: usize,
+    cutoff: f64,
+    transition: f64,
+    attenuation: f64,
+) -> Result<Vec<f32>> {
+    if phases == 0 {
+        bail!("The filterbank needs at least one branch");
+    }
+    if cutoff <= 0.0 || cutoff >= 0.5 {
+        bail!("The cutoff must be below half the sample rate");
+    }
+    if transition <= 0.0 {
+        bail!("The transition width must be positive");
+    }
+    let (cutoff, transition) = (cutoff / phases as f64, transition / phases as f64);
+    let per_phase = (DesignMethod::Kaiser.estimate_taps(transition, attenuation) / phases as f64)
+        .ceil() as usize;
+    if per_phase > MAX_TAPS {
+        bail!(
+            "Each branch would need {} taps, at most {} are supported",
+            per_phase,
+            MAX_TAPS
+        );
+    }
+    let n = per_phase.max(3) * phases;
+    // windows are symmetric over an odd number of taps, the last one is left
+    // at zero to fill the last branch
+    let mut taps = window_design(
+        DesignMethod::Kaiser,
+        n - 1,
+        attenuation,
+        &[(0.0, cutoff, 1.0), (cutoff, 0.5, 0.0)],
+    );
+    taps.push(0.0);
+    Ok(taps
+        .iter()
+        .map(|tap| (tap * phases as f64) as f32)
+        .collect())
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn window(method: DesignMethod, n: usize, attenuation: f64) -> Vec<f64> {
+    let m = (n - 1) as f64;
+    let beta = kaiser_beta(attenuation);
+    (0..n)
+        .map(|i| {
+            let x = i as f64;
+            match method {
+                DesignMethod::Kaiser => {
+                    let r = 2.0 * x / m - 1.0;
+                    bessel_i0(beta * (1.0 - r * r).sqrt()) / bessel_i0(beta)
+                }
+                DesignMethod::Hamming => 0.54 - 0.46 * (2.0 * PI * x / m).cos(),
+                DesignMethod::Hann => 0.5 - 0.5 * (2.0 * PI * x / m).cos(),
+                DesignMethod::Blackman => {
+                    0.42 - 0.5 * (2.0 * PI * x / m).cos() + 0.08 * (4.0 * PI * x / m).cos()
+                }
+                DesignMethod::Rectangular | DesignMethod::Equiripple => 1.0,
+            }
+        })
+        .collect()
+}
+
+fn kaiser_beta(attenuation: f64) -> f64 {
+    if attenuation > 50.0 {
+        0.1102 * (attenuation - 8.7)
+    } else if attenuation >= 21.0 {
+        0.5842 * (attenuation - 21.0).powf(0.4) + 0.07886 * (attenuation - 21.0)
+    } else {
+        0.0
+    }
+}
+
+/// Modified Bessel function of the first kind, of order 0.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > 1e-12 * sum {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+fn shift(taps: &[f64], freq: f64) -> Vec<Complex64> {
+    let center = (taps.len() - 1) as f64 / 2.0;
+    taps.iter()
+        .enumerate()
+        .map(|(n, tap)| tap * Complex64::from_polar(1.0, 2.0 * PI * freq * (n as f64 - center)))
+        .collect()
+}
+
+fn to_complex32(taps: &[Complex64]) -> Vec<Complex32> {
+    taps.iter()
+        .map(|tap| Complex32::new(tap.re as f32, tap.im as f32))
+        .collect()
+}
+
+/// Scales the taps to a gain of 1 at DC.
+fn normalize(taps: Vec<f64>) -> Vec<f64> {
+    let sum: f64 = taps.iter().sum();
+    taps.iter().map(|tap| tap / sum).collect()
+}
+
+fn root_raised_cosine(sps: f64, alpha: f64, n: usize) -> Vec<f64> {
+    let center = (n - 1) as f64 / 2.0;
+    (0..n)
+        .map(|i| {
+            let t = (i as f64 - center) / sps;
+            if t.abs() < 1e-9 {
+                1.0 - alpha + 4.0 * alpha / PI
+            } else if ((4.0 * alpha * t).abs() - 1.0).abs() < 1e-9 {
+                alpha / 2f64.sqrt()
+                    * ((1.0 + 2.0 / PI) * (PI / (4.0 * alpha)).sin()
+                        + (1.0 - 2.0 / PI) * (PI / (4.0 * alpha)).cos())
+            } else {
+                ((PI * t * (1.0 - alpha)).sin() + 4.0 * alpha * t * (PI * t * (1.0 + alpha)).cos())
+                    / (PI * t * (1.0 - (4.0 * alpha * t).powi(2)))
+            }
+        })
+        .collect()
+}
+
+fn gaussian(sps: f64, bt: f64, n: usize) -> Vec<f64> {
+    let center = (n - 1) as f64 / 2.0;
+    (0..n)
+        .map(|i| {
+            let t = (i as f64 - center) / sps;
+            (-2.0 * (PI * bt * t).powi(2) / 2f64.ln()).exp()
+        })
+        .collect()
+}
+
+/// Lagrange interpolation in barycentric form, through the points (x, y).
+struct Interpolator {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+impl Interpolator {
+    fn eval(&self, x: f64) -> f64 {
+        let mut numerator = 0.0;
+        let mut denominator = 0.0;
+        for k in 0..self.x.len() {
+            let diff = x - self.x[k];
+            if diff.abs() < 1e-14 {
+                return self.y[k];
+            }
+            let t = self.weights[k] / diff;
+            numerator += t * self.y[k];
+            denominator += t;
+        }
+        numerator / denominator
+    }
+}
+
+/// Barycentric weights of the points, scaled so that the largest is 1. The
+/// products over- or underflow with thousands of points, so they are summed
+/// as logarithms instead, which doesn't matter since only their ratios are
+/// used.
+fn barycentric_weights(x: &[f64]) -> Vec<f64> {
+    let (logs, signs): (Vec<f64>, Vec<f64>) = (0..x.len())
+        .map(|k| {
+            (0..x.len())
+                .filter(|i| *i != k)
+                .fold((0.0, 1.0), |(log, sign), i| {
+                    let diff = x[k] - x[i];
+                    (log - diff.abs().ln(), sign * diff.signum())
+                })
+        })
+        .unzip();
+    let max = logs
+        .iter()
+        .fold(f64::NEG_INFINITY, |max, log| max.max(*log));
+    logs.iter()
+        .zip(signs)
+        .map(|(log, sign)| sign * (log - max).exp())
+        .collect()
+}
+
+/// Parks-McClellan design of a linear phase filter with an odd number of
+/// taps. Bands are (start, end, gain, weight), in cycles per sample.
+fn remez(n: usize, bands: &[(f64, f64, f64, f64)]) -> Result<Vec<f64>> {
+    // the response is a polynomial of degree l in cos(w), and the optimal
+    // one has r alternating extremal points of the error
+    let l = (n - 1) / 2;
+    let r = l + 2;
+
+    let total: f64 = bands.iter().map(|(start, end, _, _)| end - start).sum();
+    let mut grid = vec![];
+    let mut edges = vec![];
+    for &(start, end, gain, weight) in bands {
+        let count = ((end - start) / total * (REMEZ_GRID_DENSITY * r) as f64).round() as usize;
+        let count = count.max(2);
+        for i in 0..count {
+            let f = start + (end - start) * i as f64 / (count - 1) as f64;
+            grid.push(((2.0 * PI * f).cos(), gain, weight));
+            edges.push(i == 0 || i == count - 1);
+        }
+    }
+
+    let mut extremals: Vec<usize> = (0..r).map(|i| i * (grid.len() - 1) / (r - 1)).collect();
+    let mut interpolator = None;
+    for _ in 0..REMEZ_MAX_ITERATIONS {
+        let x: Vec<f64> = extremals.iter().map(|i| grid[*i].0).collect();
+        let sign = |k: usize| if k % 2 == 0 { 1.0 } else { -1.0 };
+
+        let b = barycentric_weights(&x);
+        let numerator: f64 = (0..r).map(|k| b[k] * grid[extremals[k]].1).sum();
+        let denominator: f64 = (0..r).map(|k| b[k] * sign(k) / grid[extremals[k]].2).sum();
+        let delta = numerator / denominator;
+
+        let current = Interpolator {
+            x: x[..r - 1].to_vec(),
+            y: (0..r - 1)
+                .map(|k| grid[extremals[k]].1 - sign(k) * delta / grid[extremals[k]].2)
+                .collect(),
+            weights: barycentric_weights(&x[..r - 1]),
+        };
+        let error: Vec<f64> = grid
+            .iter()
+            .map(|(x, gain, weight)| weight * (gain - current.eval(*x)))
+            .collect();
+        interpolator = Some(current);
+
+        let max_error = error.iter().fold(0.0f64, |max, e| max.max(e.abs()));
+        match find_extremals(&error, &edges, r) {
+            Some(next) if next != extremals && max_error - delta.abs() > 1e-6 * max_error => {
+                extremals = next;
+            }
+            _ => break,
+        }
+    }
+    let interpolator = interpolator.ok_or_else(|| anyhow!("The filter has no taps"))?;
+
+    // sample the response at n equally spaced frequencies, and get the taps
+    // back with an inverse DFT
+    let response: Vec<f64> = (0..=l)
+        .map(|k| interpolator.eval((2.0 * PI * k as f64 / n as f64).cos()))
+        .collect();
+    Ok((0..n)
+        .map(|i| {
+            let m = i as f64 - l as f64;
+            let sum: f64 = (1..=l)
+                .map(|k| response[k] * (2.0 * PI * k as f64 * m / n as f64).cos())
+                .sum();
+            (response[0] + 2.0 * sum) / n as f64
+        })
+        .collect())
+}
+
+fn find_extremals(error: &[f64], edges: &[bool], r: usize) -> Option<Vec<usize>> {
+    let mut candidates = vec![];
+    for j in 0..error.len() {
+        let e = error[j];
+        let before = if j > 0 { error[j - 1] } else { e };
+        let after = if j + 1 < error.len() { error[j + 1] } else { e };
+        let is_peak =
+            (e > 0.0 && e >= before && e >= after) || (e < 0.0 && e <= before && e <= after);
+        if edges[j] || is_peak {
+            candidates.push(j);
+        }
+    }
+
+    // keep the largest of consecutive extremals with the same sign
+    let mut alternating: Vec<usize> = vec![];
+    for j in candidates {
+        if let Some(last) = alternating.last_mut() {
+            if error[*last].signum() == error[j].signum() {
+                if error[j].abs() > error[*last].abs() {
+                    *last = j;
+                }
+                continue;
+            }
+        }
+        alternating.push(j);
+    }
+
+    // dropping the smallest one at either end keeps the signs alternating
+    while alternating.len() > r {
+        if error[alternating[0]].abs() < error[alternating[alternating.len() - 1]].abs() {
+            alternating.remove(0);
+        } else {
+            alternating.pop();
+        }
+    }
+    if alternating.len() < r {
+        return None;
+    }
+    Some(alternating)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(shape: FilterShape, method: DesignMethod) -> FilterSpec {
+        FilterSpec {
+            shape,
+            method,
+            sample_rate: 1.0,
+            cutoff: 0.25,
+            cutoff_high: 0.35,
+            transition: 0.05,
+            attenuation: 60.0,
+            symbol_rate: 0.25,
+            rolloff: 0.35,
+            num_taps: 0,
+            complex: false,
+        }
+    }
+
+    fn real_taps(spec: &FilterSpec) -> Vec<f32> {
+        match design(spec).unwrap() {
+            Taps::Real(taps) => taps,
+            Taps::Complex(_) => panic!("expected real taps"),
+        }
+    }
+
+    /// Largest gain in dB between the given frequencies.
+    fn peak(response: &[(f64, f64)], from: f64, to: f64) -> f64 {
+        response
+            .iter()
+            .filter(|(f, _)| *f >= from && *f <= to)
+            .fold(f64::NEG_INFINITY, |max, (_, gain)| max.max(*gain))
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (*a as f64 - e).abs() < tolerance,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn hamming_halfband_matches_the_textbook_design() {
+        let mut spec = spec(FilterShape::Lowpass, DesignMethod::Hamming);
+        spec.num_taps = 5;
+        // the ideal response 0.5 sinc(m / 2), times the hamming window
+        let side = 0.54 / PI;
+        assert_close(&real_taps(&spec), &[0.0, side, 0.5, side, 0.0], 1e-6);
+    }
+
+    #[test]
+    fn kaiser_window_parameters() {
+        assert!((kaiser_beta(60.0) - 5.65326).abs() < 1e-5);
+        assert_eq!(kaiser_beta(20.0), 0.0);
+        assert!((bessel_i0(1.0) - 1.2660658777520082).abs() < 1e-12);
+    }
+
+    #[test]
+    fn kaiser_lowpass_meets_the_attenuation() {
+        let spec = spec(FilterShape::Lowpass, DesignMethod::Kaiser);
+        let response = Taps::Real(real_taps(&spec)).response(1.0, 1024);
+        assert!(peak(&response, 0.0, 0.2).abs() < 0.01);
+        assert!(peak(&response, 0.3, 0.5) < -59.0);
+    }
+
+    #[test]
+    fn equiripple_halfband_has_the_known_structure() {
+        // with equal weights and bands symmetric around a quarter of the
+        // sample rate, the optimal filter has every other tap at zero and
+        // the same ripple in both bands
+        let mut spec = spec(FilterShape::Lowpass, DesignMethod::Equiripple);
+        spec.num_taps = 11;
+        spec.transition = 0.1;
+        let taps = real_taps(&spec);
+        let center = taps.len() / 2;
+        assert!((taps[center] - 0.5).abs() < 1e-3);
+        for m in (2..=center).step_by(2) {
+            assert!(taps[center + m].abs() < 1e-3, "{:?}", taps);
+        }
+        for m in 1..=center {
+            assert_eq!(taps[center - m], taps[center + m]);
+        }
+        let response = Taps::Real(taps).response(1.0, 1024);
+        let passband_ripple = 1.0 - 10f64.powf(-peak(&response, 0.0, 0.2).abs() / 20.0);
+        let stopband_ripple = 10f64.powf(peak(&response, 0.3, 0.5) / 20.0);
+        assert!((passband_ripple - stopband_ripple).abs() < 0.1 * stopband_ripple);
+    }
+
+    #[test]
+    fn root_raised_cosine_pair_has_no_intersymbol_interference() {
+        let spec = spec(FilterShape::RootRaisedCosine, DesignMethod::Kaiser);
+        let taps = real_taps(&spec);
+        let n = taps.len();
+        let pulse: Vec<f64> = (0..2 * n - 1)
+            .map(|i| {
+                (0..n)
+                    .filter(|k| i >= *k && i - k < n)
+                    .map(|k| taps[k] as f64 * taps[i - k] as f64)
+                    .sum()
+            })
+            .collect();
+        let center = n - 1;
+        let sps = 4;
+        for symbol in 1..5 {
+            assert!(pulse[center + symbol * sps].abs() < 0.01 * pulse[center]);
+        }
+    }
+
+    #[test]
+    fn pulse_shapes_have_unity_gain() {
+        for shape in [FilterShape::RootRaisedCosine, FilterShape::Gaussian] {
+            let sum: f32 = real_taps(&spec(shape, DesignMethod::Kaiser)).iter().sum();
+            assert!((sum - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn barycentric_weights_stay_finite_for_many_points() {
+        let x: Vec<f64> = (0..MAX_TAPS)
+            .map(|k| (PI * (k as f64 + 0.5) / MAX_TAPS as f64).cos())
+            .collect();
+        let weights = barycentric_weights(&x);
+        assert!(weights.iter().all(|w| w.is_finite() && *w != 0.0));
+        // weights of Chebyshev points alternate in sign
+        assert!(weights.windows(2).all(|w| w[0].signum() != w[1].signum()));
+    }
+
+    #[test]
+    fn barycentric_interpolation_is_exact_for_polynomials() {
+        let x = vec![-1.0, -0.3, 0.2, 0.9];
+        let cubic = |x: f64| 2.0 * x * x * x - x + 0.5;
+        let interpolator = Interpolator {
+            y: x.iter().map(|x| cubic(*x)).collect(),
+            weights: barycentric_weights(&x),
+            x,
+        };
+        assert!((interpolator.eval(0.5) - cubic(0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rejects_too_many_taps() {
+        let mut spec = spec(FilterShape::Lowpass, DesignMethod::Kaiser);
+        spec.num_taps = MAX_TAPS + 1;
+        assert!(design(&spec).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::f64::consts::PI;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+
+/// De-emphasis time constants, by name.
+pub const DEEMPHASIS: [(&str, f64); 3] = [("50 µs", 50e-6), ("75 µs", 75e-6), ("none", 0.0)];
+
+pub fn deemphasis_tau(name: &str) -> Result<f64> {
+    DEEMPHASIS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, tau)| *tau)
+        .ok_or_else(|| anyhow!("Unknown de-emphasis \"{}\"", name))
+}
+
+/// Phase-difference FM discriminator.
+#[derive(Clone, Debug)]
+pub struct Discriminator {
+    last: Complex32,
+    gain: f32,
+}
+
+impl Discriminator {
+    /// `deviation` is the frequency deviation, in Hz, demodulated to 1.
+    pub fn new(deviation: f64, sample_rate: f64) -> Discriminator {
+        Discriminator {
+            last: Complex32::new(0.0, 0.0),
+            gain: (sample_rate / (2.0 * PI * deviation)) as f32,
+        }
+    }
+
+    pub fn set_deviation(&mut self, deviation: f64, sample_rate: f64) {
+        self.gain = (sample_rate / (2.0 * PI * deviation)) as f32;
+    }
+
+    pub fn demodulate(&mut self, sample: Complex32) -> f32 {
+        let out = (sample * self.last.conj()).arg() * self.gain;
+        self.last = sample;
+        out
+    }
+}
+
+/// Single pole lowpass undoing the pre-emphasis of FM broadcasts. A time
+/// constant of 0 disables it.
+#[derive(Clone, Debug)]
+pub struct Deemphasis {
+    alpha: f32,
+    state: f32,
+}
+
+impl Deemphasis {
+    pub fn new(tau: f64, sample_rate: f64) -> Deemphasis {
+        let mut deemphasis = Deemphasis {
+            alpha: 1.0,
+            state: 0.0,
+        };
+        deemphasis.set_tau(tau, sample_rate);
+        deemphasis
+    }
+
+    pub fn set_tau(&mut self, tau: f64, sample_rate: f64) {
+        self.alpha = if tau > 0.0 {
+            (1.0 - (-1.0 / (sample_rate * tau)).exp()) as f32
+        } else {
+            1.0
+        };
+    }
+
+    pub fn filter(&mut self, sample: f32) -> f32 {
+        self.state += self.alpha * (sample - self.state);
+        self.state
+    }
+}
+
+/// The 38 kHz stereo subcarrier, given the pilot phase tracked by
+/// [`Pll::track`]. Pilot and subcarrier are both sines, while the PLL locks
+/// its cosine onto the pilot.
+pub fn stereo_subcarrier(pilot_phase: f64) -> f32 {
+    -(2.0 * pilot_phase).sin() as f32
+}
+
+/// PLL locking onto a pilot tone. The tone is mixed down to DC and averaged,
+/// and the phase of the average drives the loop.
+#[derive(Clone, Debug)]
+pub struct Pll {
+    phase: f64,
+    /// in radians per sample
+    freq: f64,
+    center: f64,
+    max_offset: f64,
+    alpha: f64,
+    beta: f64,
+    average: Complex32,
+    smoothing: f32,
+}
+
+impl Pll {
+    /// `range` is how far from `freq` the tone may be, and `bandwidth` the
+    /// loop bandwidth, all in Hz.
+    pub fn new(freq: f64, range: f64, bandwidth: f64, sample_rate: f64) -> Pll {
+        let omega = 2.0 * PI * bandwidth / sample_rate;
+        let damping = std::f64::consts::FRAC_1_SQRT_2;
+        Pll {
+            phase: 0.0,
+            freq: 2.0 * PI * freq / sample_rate,
+            center: 2.0 * PI * freq / sample_rate,
+            max_offset: 2.0 * PI * range / sample_rate,
+            alpha: 2.0 * damping * omega,
+            beta: omega * omega,
+            average: Complex32::new(0.0, 0.0),
+            // averages well below the loop bandwidth would make it unstable
+            smoothing: (1.0 - (-5.0 * omega).exp()) as f32,
+        }
+    }
+
+    /// Tracks the tone in `sample`, and returns its phase at that sample.
+    pub fn track(&mut self, sample: f32) -> f64 {
+        let phase = self.phase;
+        let mixed = Complex32::from_polar(sample, -phase as f32);
+        self.average += (mixed - self.average) * self.smoothing;
+        let error = self.average.arg() as f64;
+        self.freq = (self.freq + self.beta * error)
+            .clamp(self.center - self.max_offset, self.center + self.max_offset);
+        self.phase = (self.phase + self.freq + self.alpha * error) % (2.0 * PI);
+        phase
+    }
+
+    /// Amplitude of the tracked tone.
+    pub fn level(&self) -> f32 {
+        2.0 * self.average.norm()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_left_and_right() {
+        let sample_rate = 250_000.0;
+        let (left, right) = (0.3, -0.1);
+        let mut pll = Pll::new(19_000.0, 20.0, 10.0, sample_rate);
+        let (mut sum, mut difference) = (0.0, 0.0);
+        let settled = 400_000;
+        let total = 500_000;
+        for n in 0..total {
+            let pilot = 2.0 * PI * 19_000.0 * n as f64 / sample_rate;
+            let mpx = (left + right) / 2.0
+                + 0.1 * pilot.sin()
+                + (left - right) / 2.0 * (2.0 * pilot).sin();
+            let phase = pll.track(mpx as f32);
+            // averaging stands in for the audio lowpass
+            if n >= settled {
+                sum += mpx;
+                difference += mpx * 2.0 * stereo_subcarrier(phase) as f64;
+            }
+        }
+        let (sum, difference) = (sum / 100_000.0, difference / 100_000.0);
+        assert!(
+            (sum + difference - left).abs() < 1e-2,
+            "left {}",
+            sum + difference
+        );
+        assert!(
+            (sum - difference - right).abs() < 1e-2,
+            "right {}",
+            sum - difference
+        );
+    }
+
+    #[test]
+    fn pll_measures_the_pilot_level() {
+        let sample_rate = 250_000.0;
+        let mut pll = Pll::new(19_000.0, 20.0, 10.0, sample_rate);
+        for n in 0..250_000 {
+            let pilot = 2.0 * PI * 19_005.0 * n as f64 / sample_rate;
+            pll.track(0.1 * pilot.sin() as f32);
+        }
+        assert!((pll.level() - 0.1).abs() < 1e-2, "level {}", pll.level());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod biquad;
+pub mod firdes;
+pub mod fm;
+pub mod nco;
+pub mod rds;
+pub mod tones;
@@ -0,0 +1,97 @@
+use futuresdr::num_complex::Complex32;
+
+/// how many samples to generate between renormalizations of the phasor
+const RENORMALIZE_INTERVAL: usize = 512;
+
+/// Numerically controlled oscillator, producing `e^(j*2*pi*freq/sample_rate*n)`
+/// by rotating a phasor. Repeated multiplications make the amplitude of the
+/// phasor drift, so it is renormalized every now and then.
+#[derive(Clone, Debug)]
+pub struct Nco {
+    phasor: Complex32,
+    step: Complex32,
+    since_renormalization: usize,
+}
+
+impl Nco {
+    pub fn new(freq: f64, sample_rate: f64) -> Nco {
+        let mut nco = Nco {
+            phasor: Complex32::new(1.0, 0.0),
+            step: Complex32::new(1.0, 0.0),
+            since_renormalization: 0,
+        };
+        nco.set_freq(freq, sample_rate);
+        nco
+    }
+
+    /// Retunes the oscillator. The phase carries on from where it was, so that
+    /// retuning doesn't cause a discontinuity.
+    pub fn set_freq(&mut self, freq: f64, sample_rate: f64) {
+        let phase_increment = 2.0 * std::f64::consts::PI * freq / sample_rate;
+        self.step = Complex32::from_polar(1.0, phase_increment as f32);
+    }
+
+    pub fn next(&mut self) -> Complex32 {
+        let current = self.phasor;
+        self.phasor *= self.step;
+        self.since_renormalization += 1;
+        if self.since_renormalization == RENORMALIZE_INTERVAL {
+            self.phasor /= self.phasor.norm();
+            self.since_renormalization = 0;
+        }
+        current
+    }
+
+    /// Multiplies each sample by the oscillator, shifting it by its frequency.
+    pub fn mix(&mut self, input: &[Complex32], output: &mut [Complex32]) {
+        for (o, i) in output.iter_mut().zip(input) {
+            *o = i * self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_a_unit_amplitude() {
+        let mut nco = Nco::new(1234.5, 48000.0);
+        for n in 0..1_000_000 {
+            let amplitude = nco.next().norm();
+            assert!((amplitude - 1.0).abs() < 1e-3, "{} at {}", amplitude, n);
+        }
+    }
+
+    #[test]
+    fn has_the_frequency() {
+        let mut nco = Nco::new(1000.0, 48000.0);
+        let step = 2.0 * std::f32::consts::PI * 1000.0 / 48000.0;
+        let mut previous = nco.next();
+        for _ in 0..10_000 {
+            let current = nco.next();
+            assert!(((current * previous.conj()).arg() - step).abs() < 1e-4);
+            previous = current;
+        }
+    }
+
+    #[test]
+    fn doesnt_jump_when_retuned() {
+        let mut nco = Nco::new(1000.0, 48000.0);
+        let mut samples: Vec<Complex32> = (0..777).map(|_| nco.next()).collect();
+        nco.set_freq(-3000.0, 48000.0);
+        samples.extend((0..777).map(|_| nco.next()));
+        // no step between two samples is larger than the larger phase
+        // increment of both frequencies
+        let max_step = 2.0 * std::f32::consts::PI * 3000.0 / 48000.0;
+        for pair in samples.windows(2) {
+            let step = (pair[1] * pair[0].conj()).arg().abs();
+            assert!(step < max_step + 1e-4, "step of {}", step);
+        }
+        // the retuning shows up right after the sample where it happened
+        let before = (samples[777] * samples[776].conj()).arg();
+        let after = (samples[778] * samples[777].conj()).arg();
+        assert!((before - 2.0 * std::f32::consts::PI * 1000.0 / 48000.0).abs() < 1e-4);
+        assert!((after + max_step).abs() < 1e-4);
+    }
+}
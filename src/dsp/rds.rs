@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+
+/// generator polynomial of the RDS block check, x^10+x^8+x^7+x^5+x^4+x^3+1
+const GENERATOR: u32 = 0x5b9;
+const BLOCK_BITS: u32 = 26;
+/// offset words of blocks A, B, C, C' and D
+const OFFSETS: [(BlockType, u16); 5] = [
+    (BlockType::A, 0x0fc),
+    (BlockType::B, 0x198),
+    (BlockType::C, 0x168),
+    (BlockType::CPrime, 0x350),
+    (BlockType::D, 0x1b4),
+];
+/// error-free blocks in a row needed to confirm a sync, before which no
+/// errors are corrected
+const CONFIRM_BLOCKS: usize = 4;
+/// blocks over which errors are counted while synced
+const LOSS_WINDOW: usize = 50;
+/// blocks with errors, corrected or not, within the window after which the
+/// sync is considered lost
+const MAX_BAD_BLOCKS: usize = 35;
+/// longest burst of errors corrected
+const MAX_BURST: u32 = 5;
+
+const PROGRAM_TYPES: [&str; 32] = [
+    "None",
+    "News",
+    "Current affairs",
+    "Information",
+    "Sport",
+    "Education",
+    "Drama",
+    "Culture",
+    "Science",
+    "Varied",
+    "Pop music",
+    "Rock music",
+    "Easy listening",
+    "Light classical",
+    "Serious classical",
+    "Other music",
+    "Weather",
+    "Finance",
+    "Children's programmes",
+    "Social affairs",
+    "Religion",
+    "Phone-in",
+    "Travel",
+    "Leisure",
+    "Jazz music",
+    "Country music",
+    "National music",
+    "Oldies music",
+    "Folk music",
+    "Documentary",
+    "Alarm test",
+    "Alarm",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BlockType {
+    A,
+    B,
+    C,
+    CPrime,
+    D,
+}
+
+impl BlockType {
+    fn index(self) -> usize {
+        match self {
+            BlockType::A => 0,
+            BlockType::B => 1,
+            BlockType::C | BlockType::CPrime => 2,
+            BlockType::D => 3,
+        }
+    }
+
+    fn offset(self) -> u16 {
+        OFFSETS.iter().find(|(t, _)| *t == self).unwrap().1
+    }
+}
+
+/// Remainder of the division of `word` by the generator polynomial.
+fn syndrome(word: u32) -> u16 {
+    let mut remainder = word;
+    for bit in (10..BLOCK_BITS).rev() {
+        if remainder & (1 << bit) != 0 {
+            remainder ^= GENERATOR << (bit - 10);
+        }
+    }
+    remainder as u16
+}
+
+/// Decodes RDS groups from the demodulated bit stream, keeping track of the
+/// station information they carry.
+pub struct RdsDecoder {
+    /// the last 26 bits received
+    register: u32,
+    synced: bool,
+    /// bits received since the last block while synced, or since the last
+    /// valid block while searching
+    bits: u32,
+    /// type of the last block found while searching
+    last_found: Option<BlockType>,
+    /// next block expected while synced
+    expected: usize,
+    /// error-free blocks in a row since the sync, until it is confirmed
+    good_blocks: usize,
+    confirmed: bool,
+    /// blocks received and those with errors in the current loss window
+    window_blocks: usize,
+    bad_blocks: usize,
+    /// error patterns by syndrome
+    bursts: HashMap<u16, u32>,
+    group: [Option<u16>; 4],
+    pi: Option<u16>,
+    pty: Option<u8>,
+    ps: [u8; 8],
+    radio_text: [u8; 64],
+    text_ab: Option<bool>,
+    clock: Option<String>,
+}
+
+impl Default for RdsDecoder {
+    fn default() -> RdsDecoder {
+        let mut bursts = HashMap::new();
+        for pattern in (1u32..1 << MAX_BURST).filter(|pattern| pattern & 1 != 0) {
+            let len = 32 - pattern.leading_zeros();
+            for shift in 0..=BLOCK_BITS - len {
+                let error = pattern << shift;
+                bursts.entry(syndrome(error)).or_insert(error);
+            }
+        }
+        RdsDecoder {
+            register: 0,
+            synced: false,
+            bits: 0,
+            last_found: None,
+            expected: 0,
+            good_blocks: 0,
+            confirmed: false,
+            window_blocks: 0,
+            bad_blocks: 0,
+            bursts,
+            group: [None; 4],
+            pi: None,
+            pty: None,
+            ps: [b' '; 8],
+            radio_text: [b' '; 64],
+            text_ab: None,
+            clock: None,
+        }
+    }
+}
+
+impl RdsDecoder {
+    /// Takes the next bit, and returns the fields of the station information
+    /// which changed, as (name, value).
+    pub fn push_bit(&mut self, bit: bool) -> Vec<(&'static str, String)> {
+        self.register = ((self.register << 1) | bit as u32) & ((1 << BLOCK_BITS) - 1);
+        self.bits += 1;
+        if !self.synced {
+            self.search();
+            return vec![];
+        }
+        if self.bits < BLOCK_BITS {
+            return vec![];
+        }
+        self.bits = 0;
+
+        let index = self.expected;
+        self.expected = (self.expected + 1) % 4;
+        let block = self.correct(index);
+        let clean = matches!(block, Some((_, false)));
+        if !self.confirmed {
+            // a single bad block means the sync was found by chance
+            if !clean {
+                self.lose_sync();
+                return vec![];
+            }
+            self.good_blocks += 1;
+            if self.good_blocks >= CONFIRM_BLOCKS {
+                log::debug!("Confirmed RDS sync");
+                self.confirmed = true;
+            }
+        }
+        self.window_blocks += 1;
+        if !clean {
+            self.bad_blocks += 1;
+        }
+        if self.bad_blocks >= MAX_BAD_BLOCKS {
+            self.lose_sync();
+            return vec![];
+        }
+        if self.window_blocks >= LOSS_WINDOW {
+            self.window_blocks = 0;
+            self.bad_blocks = 0;
+        }
+        self.group[index] = block.map(|(block, _)| block);
+        if index == 3 {
+            let group = std::mem::take(&mut self.group);
+            return self.decode_group(group);
+        }
+        vec![]
+    }
+
+    /// Looks for two valid blocks in sequence, a block apart.
+    fn search(&mut self) {
+        let found = OFFSETS
+            .iter()
+            .find(|(_, offset)| syndrome(self.register) == *offset)
+            .map(|(block_type, _)| *block_type);
+        let block_type = match found {
+            Some(block_type) => block_type,
+            None => return,
+        };
+        if let Some(last) = self.last_found {
+            if self.bits == BLOCK_BITS && (last.index() + 1) % 4 == block_type.index() {
+                log::debug!("Got RDS sync");
+                self.synced = true;
+                self.good_blocks = 0;
+                self.confirmed = false;
+                self.window_blocks = 0;
+                self.bad_blocks = 0;
+                self.expected = (block_type.index() + 1) % 4;
+                self.group = [None; 4];
+                if block_type.index() < 3 {
+                    self.group[block_type.index()] = Some((self.register >> 10) as u16);
+                }
+                self.bits = 0;
+                return;
+            }
+        }
+        self.last_found = Some(block_type);
+        self.bits = 0;
+    }
+
+    fn lose_sync(&mut self) {
+        log::debug!("Lost RDS sync");
+        self.synced = false;
+        self.last_found = None;
+        self.bits = 0;
+    }
+
+    /// Checks the block in the register against the expected offset, fixing
+    /// a burst of errors if there is one and the sync is confirmed. Returns
+    /// the block and whether it was corrected.
+    fn correct(&self, index: usize) -> Option<(u16, bool)> {
+        let offsets: &[BlockType] = match index {
+            0 => &[BlockType::A],
+            1 => &[BlockType::B],
+            2 => &[BlockType::C, BlockType::CPrime],
+            _ => &[BlockType::D],
+        };
+        let syndrome = syndrome(self.register);
+        for block_type in offsets {
+            let error = syndrome ^ block_type.offset();
+            if error == 0 {
+                return Some(((self.register >> 10) as u16, false));
+            }
+            if !self.confirmed {
+                continue;
+            }
+            if let Some(pattern) = self.bursts.get(&error) {
+                return Some((((self.register ^ pattern) >> 10) as u16, true));
+            }
+        }
+        None
+    }
+
+    fn decode_group(&mut self, group: [Option<u16>; 4]) -> Vec<(&'static str, String)> {
+        let mut changes = vec![];
+        if let Some(pi) = group[0] {
+            if self.pi != Some(pi) {
+                self.pi = Some(pi);
+                changes.push(("PI", format!("{:04X}", pi)));
+            }
+        }
+        let b = match group[1] {
+            Some(b) => b,
+            None => return changes,
+        };
+        let pty = ((b >> 5) & 0x1f) as u8;
+        if self.pty != Some(pty) {
+            self.pty = Some(pty);
+            changes.push(("PTY", PROGRAM_TYPES[pty as usize].to_string()));
+        }
+        let group_type = b >> 12;
+        let version_b = b & 0x800 != 0;
+        match (group_type, version_b) {
+            (0, _) => {
+                if let Some(d) = group[3] {
+                    let address = (b & 0x3) as usize * 2;
+                    if self.update(Text::Ps, address, &d.to_be_bytes()) {
+                        changes.push(("PS", text(&self.ps)));
+                    }
+                }
+            }
+            (2, _) => {
+                let text_ab = b & 0x10 != 0;
+                if self.text_ab.is_some() && self.text_ab != Some(text_ab) {
+                    self.radio_text = [b' '; 64];
+                }
+                self.text_ab = Some(text_ab);
+                let segment = (b & 0xf) as usize;
+                let changed = if version_b {
+                    group[3].map_or(false, |d| {
+                        self.update(Text::RadioText, segment * 2, &d.to_be_bytes())
+                    })
+                } else {
+                    let c = group[2].map_or(false, |c| {
+                        self.update(Text::RadioText, segment * 4, &c.to_be_bytes())
+                    });
+                    let d = group[3].map_or(false, |d| {
+                        self.update(Text::RadioText, segment * 4 + 2, &d.to_be_bytes())
+                    });
+                    c || d
+                };
+                if changed {
+                    changes.push(("RadioText", text(&self.radio_text)));
+                }
+            }
+            (4, false) => {
+                if let (Some(c), Some(d)) = (group[2], group[3]) {
+                    let clock = clock_time(b, c, d);
+                    if self.clock.as_ref() != Some(&clock) {
+                        self.clock = Some(clock.clone());
+                        changes.push(("Clock", clock));
+                    }
+                }
+            }
+            _ => {}
+        }
+        changes
+    }
+
+    /// Writes characters to the PS name or RadioText, and returns whether it
+    /// changed. A carriage return ends the RadioText.
+    fn update(&mut self, which: Text, address: usize, chars: &[u8]) -> bool {
+        let buffer: &mut [u8] = match which {
+            Text::Ps => &mut self.ps,
+            Text::RadioText => &mut self.radio_text,
+        };
+        let mut changed = false;
+        for (i, c) in chars.iter().enumerate() {
+            if let Some(slot) = buffer.get_mut(address + i) {
+                let c = match c {
+                    b'\r' => {
+                        for rest in buffer[address + i..].iter_mut() {
+                            *rest = b' ';
+                        }
+                        return true;
+                    }
+                    0x20..=0x7e => *c,
+                    _ => b'?',
+                };
+                changed |= *slot != c;
+                *slot = c;
+            }
+        }
+        changed
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Text {
+    Ps,
+    RadioText,
+}
+
+fn text(chars: &[u8]) -> String {
+    String::from_utf8_lossy(chars).trim_end().to_string()
+}
+
+/// Formats the clock time and date of a 4A group, with the local offset.
+fn clock_time(b: u16, c: u16, d: u16) -> String {
+    let mjd = (((b & 0x3) as u32) << 15 | (c >> 1) as u32) as f64;
+    let hour = ((c & 0x1) << 4) | (d >> 12);
+    let minute = (d >> 6) & 0x3f;
+    let half_hours = d & 0x1f;
+    let sign = if d & 0x20 != 0 { '-' } else { '+' };
+
+    let y = ((mjd - 15078.2) / 365.25).floor();
+    let m = ((mjd - 14956.1 - (y * 365.25).floor()) / 30.6001).floor();
+    let day = mjd - 14956.0 - (y * 365.25).floor() - (m * 30.6001).floor();
+    let k = if m == 14.0 || m == 15.0 { 1.0 } else { 0.0 };
+    let year = y + k + 1900.0;
+    let month = m - 1.0 - k * 12.0;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC (local {}{}:{:02})",
+        year as i32,
+        month as i32,
+        day as i32,
+        hour,
+        minute,
+        sign,
+        half_hours / 2,
+        half_hours % 2 * 30
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PI: u16 = 0xabcd;
+    const PTY: u16 = 10;
+
+    /// `data` with the check word of a block of `block_type`.
+    fn block(data: u16, block_type: BlockType) -> u32 {
+        let word = (data as u32) << 10;
+        word | (syndrome(word) ^ block_type.offset()) as u32
+    }
+
+    fn push_word(decoder: &mut RdsDecoder, word: u32) -> Vec<(&'static str, String)> {
+        (0..BLOCK_BITS)
+            .rev()
+            .flat_map(|bit| decoder.push_bit(word & (1 << bit) != 0))
+            .collect()
+    }
+
+    fn push_group(decoder: &mut RdsDecoder, group: [u16; 4]) -> Vec<(&'static str, String)> {
+        [BlockType::A, BlockType::B, BlockType::C, BlockType::D]
+            .iter()
+            .zip(group)
+            .flat_map(|(block_type, data)| push_word(decoder, block(data, *block_type)))
+            .collect()
+    }
+
+    fn ps_group(address: u16, chars: &[u8]) -> [u16; 4] {
+        [
+            PI,
+            PTY << 5 | address,
+            0xe0cd,
+            u16::from_be_bytes([chars[0], chars[1]]),
+        ]
+    }
+
+    fn synced_decoder() -> RdsDecoder {
+        let mut decoder = RdsDecoder::default();
+        for _ in 0..2 {
+            push_group(&mut decoder, ps_group(0, b"AB"));
+        }
+        assert!(decoder.synced && decoder.confirmed);
+        decoder
+    }
+
+    /// The last value reported for `name`.
+    fn last<'a>(changes: &'a [(&'static str, String)], name: &str) -> Option<&'a str> {
+        changes
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn check_words_carry_the_offsets() {
+        assert_eq!(syndrome(GENERATOR), 0);
+        assert_eq!(block(0, BlockType::A), 0x0fc);
+        for (block_type, offset) in OFFSETS {
+            assert_eq!(syndrome(block(0x1234, block_type)), offset);
+            assert_eq!(syndrome(block(0xffff, block_type)), offset);
+        }
+    }
+
+    #[test]
+    fn corrects_bursts_only_once_the_sync_is_confirmed() {
+        let burst = 0b10111 << 12;
+
+        let mut decoder = RdsDecoder::default();
+        push_group(&mut decoder, ps_group(0, b"AB"));
+        assert!(decoder.synced && !decoder.confirmed);
+        push_word(&mut decoder, block(0x1234, BlockType::A) ^ burst);
+        assert!(!decoder.synced);
+
+        let mut decoder = synced_decoder();
+        let mut group = ps_group(0, b"AB");
+        group[0] = 0x1234;
+        let mut changes = push_word(&mut decoder, block(group[0], BlockType::A) ^ burst);
+        for (block_type, data) in [BlockType::B, BlockType::C, BlockType::D]
+            .iter()
+            .zip(&group[1..])
+        {
+            changes.extend(push_word(&mut decoder, block(*data, *block_type)));
+        }
+        assert_eq!(last(&changes, "PI"), Some("1234"));
+        assert!(decoder.synced);
+    }
+
+    #[test]
+    fn loses_the_sync_on_noise() {
+        let mut decoder = synced_decoder();
+        let mut state = 12345u32;
+        for _ in 0..(MAX_BAD_BLOCKS + 1) as u32 * BLOCK_BITS {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            decoder.push_bit(state & 0x10000 != 0);
+            if !decoder.synced {
+                return;
+            }
+        }
+        panic!("Still synced after {} noisy blocks", MAX_BAD_BLOCKS + 1);
+    }
+
+    #[test]
+    fn decodes_groups() {
+        let mut decoder = RdsDecoder::default();
+        let mut changes = vec![];
+        for _ in 0..2 {
+            for (address, chars) in b"TEST FM ".chunks(2).enumerate() {
+                changes.extend(push_group(&mut decoder, ps_group(address as u16, chars)));
+            }
+        }
+        for (segment, chars) in b"Hello\r  ".chunks(4).enumerate() {
+            changes.extend(push_group(
+                &mut decoder,
+                [
+                    PI,
+                    2 << 12 | PTY << 5 | segment as u16,
+                    u16::from_be_bytes([chars[0], chars[1]]),
+                    u16::from_be_bytes([chars[2], chars[3]]),
+                ],
+            ));
+        }
+        // 2000-01-01, MJD 51544, 12:34 UTC, two hours ahead locally
+        let mjd = 51544;
+        changes.extend(push_group(
+            &mut decoder,
+            [
+                PI,
+                4 << 12 | PTY << 5 | mjd >> 15,
+                (mjd & 0x7fff) << 1,
+                12 << 12 | 34 << 6 | 4,
+            ],
+        ));
+
+        assert_eq!(last(&changes, "PI"), Some("ABCD"));
+        assert_eq!(last(&changes, "PTY"), Some("Pop music"));
+        assert_eq!(last(&changes, "PS"), Some("TEST FM"));
+        assert_eq!(last(&changes, "RadioText"), Some("Hello"));
+        assert_eq!(
+            last(&changes, "Clock"),
+            Some("2000-01-01 12:34 UTC (local +2:00)")
+        );
+    }
+
+    #[test]
+    fn formats_the_clock_time() {
+        // MJD 60000 is 2023-02-25
+        let mjd: u32 = 60000;
+        let b = (mjd >> 15) as u16;
+        let c = ((mjd & 0x7fff) << 1) as u16 | 23 >> 4;
+        let d = (23 & 0xf) << 12 | 5 << 6 | 0x20 | 3;
+        assert_eq!(clock_time(b, c, d), "2023-02-25 23:05 UTC (local -1:30)");
+    }
+}
@@ -0,0 +1,406 @@
+use crate::dsp::biquad;
+use crate::dsp::biquad::Biquad;
+
+use std::f64::consts::PI;
+use std::fmt;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Result;
+
+pub const CTCSS_TONES: [f64; 50] = [
+    67.0, 69.3, 71.9, 74.4, 77.0, 79.7, 82.5, 85.4, 88.5, 91.5, 94.8, 97.4, 100.0, 103.5, 107.2,
+    110.9, 114.8, 118.8, 123.0, 127.3, 131.8, 136.5, 141.3, 146.2, 150.0, 151.4, 156.7, 159.8,
+    162.2, 165.5, 167.9, 171.3, 173.8, 177.3, 179.9, 183.5, 186.2, 189.9, 192.8, 196.6, 199.5,
+    203.5, 206.5, 210.7, 218.1, 225.7, 229.1, 233.6, 241.8, 250.3,
+];
+/// CTCSS tones are measured over this long, to tell the closest ones apart
+const CTCSS_WINDOW: f64 = 0.5;
+/// share of the sub-audio power the strongest tone must have
+const CTCSS_THRESHOLD: f64 = 0.5;
+/// highest sub-audio frequency
+const SUBAUDIO_CUTOFF: f64 = 300.0;
+
+const DCS_BIT_RATE: f64 = 134.4;
+const DCS_BITS: u32 = 23;
+/// Golay (23, 12) generator, x^11+x^10+x^6+x^5+x^4+x^2+1
+const GOLAY_GENERATOR: u32 = 0xc75;
+/// how long a DCS code stays detected without being seen again, in seconds
+const DCS_HOLD: f64 = 0.5;
+
+/// Sub-audio squelch tone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tone {
+    Ctcss(f64),
+    /// code, and whether it is inverted
+    Dcs(u16, bool),
+}
+
+impl Tone {
+    /// Parses a CTCSS frequency like "88.5", or a DCS code like "D023" or
+    /// "D023I". Empty means no tone.
+    pub fn parse(text: &str) -> Result<Option<Tone>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        if let Some(code) = text.strip_prefix(&['D', 'd'][..]) {
+            let (code, inverted) = match code.strip_suffix(&['I', 'i'][..]) {
+                Some(code) => (code, true),
+                None => (code.strip_suffix(&['N', 'n'][..]).unwrap_or(code), false),
+            };
+            let code = u16::from_str_radix(code, 8)
+                .map_err(|_| anyhow!("Invalid DCS code \"{}\"", text))?;
+            if code > 0o777 {
+                bail!("Invalid DCS code \"{}\"", text);
+            }
+            return Ok(Some(Tone::Dcs(code, inverted)));
+        }
+        let freq: f64 = text
+            .trim_end_matches("Hz")
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Invalid tone \"{}\", expected e.g. 88.5 or D023", text))?;
+        match CTCSS_TONES.iter().find(|tone| (**tone - freq).abs() < 0.05) {
+            Some(tone) => Ok(Some(Tone::Ctcss(*tone))),
+            None => bail!("{} Hz is not a CTCSS tone", freq),
+        }
+    }
+}
+
+impl fmt::Display for Tone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tone::Ctcss(freq) => write!(f, "{:.1} Hz", freq),
+            Tone::Dcs(code, false) => write!(f, "D{:03o}N", code),
+            Tone::Dcs(code, true) => write!(f, "D{:03o}I", code),
+        }
+    }
+}
+
+/// Goertzel filter, measuring the power at one frequency over a window.
+struct Goertzel {
+    coefficient: f64,
+    s1: f64,
+    s2: f64,
+}
+
+impl Goertzel {
+    fn new(freq: f64, sample_rate: f64) -> Goertzel {
+        Goertzel {
+            coefficient: 2.0 * (2.0 * PI * freq / sample_rate).cos(),
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+
+    fn push(&mut self, x: f64) {
+        let s = x + self.coefficient * self.s1 - self.s2;
+        self.s2 = self.s1;
+        self.s1 = s;
+    }
+
+    /// Squared magnitude over the window, and starts the next one.
+    fn take(&mut self) -> f64 {
+        let power = self.s1 * self.s1 + self.s2 * self.s2 - self.coefficient * self.s1 * self.s2;
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+        power
+    }
+}
+
+/// Detects CTCSS tones and DCS codes in demodulated audio.
+pub struct ToneDetector {
+    lowpass: [Biquad; 2],
+    goertzels: Vec<Goertzel>,
+    window: usize,
+    count: usize,
+    power: f64,
+    ctcss: Option<f64>,
+    dcs_samples_per_bit: f64,
+    /// samples until the middle of the next DCS bit
+    dcs_countdown: f64,
+    dcs_level: f32,
+    dcs_last: bool,
+    dcs_register: u32,
+    /// last DCS codeword received, as sent without inversion
+    dcs: Option<u32>,
+    dcs_age: usize,
+    dcs_hold: usize,
+}
+
+impl ToneDetector {
+    pub fn new(sample_rate: f64) -> ToneDetector {
+        ToneDetector {
+            lowpass: [0, 1].map(|section| {
+                Biquad::lowpass(SUBAUDIO_CUTOFF, biquad::BUTTERWORTH_Q[section], sample_rate)
+            }),
+            goertzels: CTCSS_TONES
+                .iter()
+                .map(|tone| Goertzel::new(*tone, sample_rate))
+                .collect(),
+            window: (CTCSS_WINDOW * sample_rate) as usize,
+            count: 0,
+            power: 0.0,
+            ctcss: None,
+            dcs_samples_per_bit: sample_rate / DCS_BIT_RATE,
+            dcs_countdown: 0.0,
+            dcs_level: 0.0,
+            dcs_last: false,
+            dcs_register: 0,
+            dcs: None,
+            dcs_age: 0,
+            dcs_hold: (DCS_HOLD * sample_rate) as usize,
+        }
+    }
+
+    /// The tone currently detected, if any. A DCS codeword inverted is
+    /// another valid one, so it is reported as the lowest code sent without
+    /// inversion, if there is one.
+    pub fn detected(&self) -> Option<Tone> {
+        match (self.dcs, self.ctcss) {
+            (Some(word), _) => match dcs_codes(word).min() {
+                Some(code) => Some(Tone::Dcs(code, false)),
+                None => dcs_codes(invert(word))
+                    .min()
+                    .map(|code| Tone::Dcs(code, true)),
+            },
+            (None, Some(freq)) => Some(Tone::Ctcss(freq)),
+            (None, None) => None,
+        }
+    }
+
+    /// Whether the tone is received, DCS codes being received along with
+    /// the codes they can be taken for.
+    pub fn receives(&self, tone: Tone) -> bool {
+        match (tone, self.dcs) {
+            (Tone::Ctcss(freq), _) => self.ctcss == Some(freq),
+            (Tone::Dcs(code, false), Some(word)) => dcs_codes(word).any(|c| c == code),
+            (Tone::Dcs(code, true), Some(word)) => dcs_codes(invert(word)).any(|c| c == code),
+            (Tone::Dcs(..), None) => false,
+        }
+    }
+
+    pub fn push(&mut self, x: f32) {
+        let subaudio = self
+            .lowpass
+            .iter_mut()
+            .fold(x, |x, section| section.filter(x));
+        self.push_ctcss(subaudio as f64);
+        self.push_dcs(subaudio);
+    }
+
+    fn push_ctcss(&mut self, x: f64) {
+        for goertzel in &mut self.goertzels {
+            goertzel.push(x);
+        }
+        self.power += x * x;
+        self.count += 1;
+        if self.count < self.window {
+            return;
+        }
+        // a tone of amplitude a gives a squared magnitude of (a * n / 2)^2,
+        // and its power is a^2 / 2
+        let n = self.count as f64;
+        let total = self.power / n;
+        let (index, power) = self
+            .goertzels
+            .iter_mut()
+            .map(|goertzel| 2.0 * goertzel.take() / (n * n))
+            .enumerate()
+            .fold(
+                (0, 0.0),
+                |best, (i, p)| if p > best.1 { (i, p) } else { best },
+            );
+        self.ctcss = if total > 0.0 && power / total > CTCSS_THRESHOLD {
+            Some(CTCSS_TONES[index])
+        } else {
+            None
+        };
+        self.count = 0;
+        self.power = 0.0;
+    }
+
+    fn push_dcs(&mut self, x: f32) {
+        self.dcs_age += 1;
+        if self.dcs_age > self.dcs_hold {
+            self.dcs = None;
+        }
+        // slice around the average, and sample in the middle of the bits,
+        // resyncing on every transition
+        self.dcs_level += (x - self.dcs_level) * 0.001;
+        let high = x > self.dcs_level;
+        if high != self.dcs_last {
+            self.dcs_last = high;
+            self.dcs_countdown = self.dcs_samples_per_bit / 2.0;
+        }
+        self.dcs_countdown -= 1.0;
+        if self.dcs_countdown > 0.0 {
+            return;
+        }
+        self.dcs_countdown += self.dcs_samples_per_bit;
+        self.dcs_register = ((self.dcs_register << 1) | high as u32) & ((1 << DCS_BITS) - 1);
+        let word = self.dcs_register;
+        if dcs_codes(word).next().is_some() || dcs_codes(invert(word)).next().is_some() {
+            self.dcs = Some(word);
+            self.dcs_age = 0;
+        }
+    }
+}
+
+/// Remainder of the division of a 23 bit word by the Golay generator.
+fn golay_syndrome(word: u32) -> u32 {
+    let mut remainder = word;
+    for bit in (11..DCS_BITS).rev() {
+        if remainder & (1 << bit) != 0 {
+            remainder ^= GOLAY_GENERATOR << (bit - 11);
+        }
+    }
+    remainder
+}
+
+fn invert(word: u32) -> u32 {
+    !word & ((1 << DCS_BITS) - 1)
+}
+
+/// DCS words are sent continuously, so the last 23 bits are a rotation of
+/// the codeword. Codes are found in the rotations where the 12 data bits,
+/// sent first, end with the fixed bits 100 after the 9 bits of the code, all
+/// least significant bit first. Several rotations may do, which is why some
+/// codes can't be told apart.
+fn dcs_codes(word: u32) -> impl Iterator<Item = u16> {
+    let mask = (1 << DCS_BITS) - 1;
+    let valid = golay_syndrome(word) == 0;
+    (0..DCS_BITS)
+        .filter(move |_| valid)
+        .map(move |rotation| ((word << rotation) | (word >> (DCS_BITS - rotation))) & mask)
+        .filter_map(|rotated| {
+            // the first bit received is the highest one
+            let data: u32 = (0..12).fold(0, |data, i| {
+                data | (((rotated >> (DCS_BITS - 1 - i)) & 1) << i)
+            });
+            match data >> 9 {
+                0b001 => Some((data & 0x1ff) as u16),
+                _ => None,
+            }
+        })
+        .filter(|code| *code != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 8000.0;
+
+    /// Runs `seconds` of the signal through a detector.
+    fn run(seconds: f64, signal: impl Fn(usize) -> f32) -> ToneDetector {
+        let mut detector = ToneDetector::new(SAMPLE_RATE);
+        for n in 0..(seconds * SAMPLE_RATE) as usize {
+            detector.push(signal(n));
+        }
+        detector
+    }
+
+    fn sine(freq: f64, amplitude: f32, n: usize) -> f32 {
+        amplitude * (2.0 * PI * freq * n as f64 / SAMPLE_RATE).sin() as f32
+    }
+
+    /// The codeword of a DCS code, with the first bit sent the highest one.
+    fn dcs_word(code: u16) -> u32 {
+        let data = code as u32 | 1 << 9;
+        let message = (0..12).fold(0, |message, i| {
+            message | ((data >> i) & 1) << (DCS_BITS - 1 - i)
+        });
+        message | golay_syndrome(message)
+    }
+
+    fn dcs(code: u16, inverted: bool, n: usize) -> f32 {
+        let bit = (n as f64 * DCS_BIT_RATE / SAMPLE_RATE) as u32 % DCS_BITS;
+        let high = (dcs_word(code) >> (DCS_BITS - 1 - bit)) & 1 == 1;
+        if high != inverted {
+            0.1
+        } else {
+            -0.1
+        }
+    }
+
+    /// Deterministic white noise between -1 and 1.
+    fn noise() -> impl FnMut() -> f32 {
+        let mut state: u32 = 12345;
+        move || {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+        }
+    }
+
+    #[test]
+    fn detects_ctcss_tones() {
+        for freq in [67.0, 100.0, 103.5, 250.3] {
+            // under voice
+            let detector = run(1.0, |n| sine(freq, 0.1, n) + sine(1000.0, 0.5, n));
+            assert_eq!(detector.detected(), Some(Tone::Ctcss(freq)));
+            assert!(detector.receives(Tone::Ctcss(freq)));
+        }
+    }
+
+    #[test]
+    fn detects_dcs_codes() {
+        for code in [0o023, 0o754] {
+            let detector = run(1.0, |n| dcs(code, false, n));
+            assert_eq!(detector.detected(), Some(Tone::Dcs(code, false)));
+            assert!(detector.receives(Tone::Dcs(code, false)));
+            assert!(!detector.receives(Tone::Dcs(code, true)));
+            assert!(!detector.receives(Tone::Dcs(0o025, false)));
+        }
+    }
+
+    #[test]
+    fn detects_inverted_dcs_codes() {
+        let detector = run(1.0, |n| dcs(0o023, true, n));
+        assert!(detector.receives(Tone::Dcs(0o023, true)));
+        assert!(!detector.receives(Tone::Dcs(0o023, false)));
+    }
+
+    #[test]
+    fn detects_nothing_in_silence() {
+        let detector = run(2.0, |_| 0.0);
+        assert_eq!(detector.detected(), None);
+        assert!(!detector.receives(Tone::Ctcss(100.0)));
+        assert!(!detector.receives(Tone::Dcs(0o023, false)));
+    }
+
+    #[test]
+    fn detects_nothing_in_noise() {
+        let mut noise = noise();
+        let mut detector = ToneDetector::new(SAMPLE_RATE);
+        for _ in 0..(10.0 * SAMPLE_RATE) as usize {
+            detector.push(noise());
+            assert_eq!(detector.detected(), None);
+        }
+    }
+
+    #[test]
+    fn parses_tones() {
+        assert_eq!(Tone::parse("").unwrap(), None);
+        assert_eq!(Tone::parse("88.5").unwrap(), Some(Tone::Ctcss(88.5)));
+        assert_eq!(Tone::parse("88.5 Hz").unwrap(), Some(Tone::Ctcss(88.5)));
+        assert_eq!(Tone::parse("D023").unwrap(), Some(Tone::Dcs(0o23, false)));
+        assert_eq!(Tone::parse("d023n").unwrap(), Some(Tone::Dcs(0o23, false)));
+        assert_eq!(Tone::parse("D754I").unwrap(), Some(Tone::Dcs(0o754, true)));
+    }
+
+    #[test]
+    fn rejects_invalid_tones() {
+        for text in ["88", "abc", "D", "D089", "D1000", "DXYZ", "-88.5"] {
+            assert!(Tone::parse(text).is_err(), "{} was accepted", text);
+        }
+    }
+
+    #[test]
+    fn formats_tones() {
+        assert_eq!(Tone::Ctcss(88.5).to_string(), "88.5 Hz");
+        assert_eq!(Tone::Dcs(0o23, false).to_string(), "D023N");
+        assert_eq!(Tone::Dcs(0o23, true).to_string(), "D023I");
+    }
+}
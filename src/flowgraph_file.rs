@@ -0,0 +1,407 @@
+use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockType;
+use crate::panels::PanelPlacement;
+use crate::params::choice::Choice;
+use crate::params::Param;
+use crate::params::StreamType;
+use crate::ui::ESDRDataType;
+use crate::ui::ESDREditorState;
+use crate::ui::ESDRGraphState;
+use crate::ui::ESDRValueType;
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use anyhow::Result;
+use eframe::egui;
+use egui_node_graph::GraphEditorState;
+use egui_node_graph::NodeId;
+use egui_node_graph::NodeTemplateTrait;
+use serde::Deserialize;
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+/// A flowgraph as saved to a file: its nodes with the values of their
+/// params, how they are connected, and the panels they are shown in.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FlowgraphFile {
+    nodes: Vec<SavedNode>,
+    /// (node, output) to (node, input), nodes being indices into `nodes`
+    connections: Vec<((usize, String), (usize, String))>,
+    panels: Vec<SavedPanel>,
+    run_view: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedNode {
+    block: String,
+    label: String,
+    position: (f32, f32),
+    inputs: Vec<(String, SavedValue)>,
+    outputs: Vec<(String, StreamType)>,
+    /// params added by the last refresh, see `ESDRNodeData::refreshed`
+    refreshed: Vec<String>,
+}
+
+/// Params are saved whole, so that the ones added by a refresh (e.g. the
+/// channels of a device) are there again before the next refresh.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum SavedValue {
+    Stream(StreamType),
+    Scalar { value: f64, allow_updates: bool },
+    Toggle { value: bool, allow_updates: bool },
+    Choice { value: String, allow_updates: bool },
+    Text { value: String, allow_updates: bool },
+}
+
+impl SavedValue {
+    fn new(typ: ESDRDataType, value: &ESDRValueType) -> SavedValue {
+        match value {
+            ESDRValueType::InputStream { .. } => match typ {
+                ESDRDataType::Stream(item_type) => SavedValue::Stream(item_type),
+                ESDRDataType::Scalar => SavedValue::Stream(StreamType::default()),
+            },
+            ESDRValueType::Scalar { value, config, .. } => SavedValue::Scalar {
+                value: *value,
+                allow_updates: config.allow_updates,
+            },
+            ESDRValueType::Toggle { value, config, .. } => SavedValue::Toggle {
+                value: *value,
+                allow_updates: config.allow_updates,
+            },
+            ESDRValueType::Choice { value, config, .. } => SavedValue::Choice {
+                value: value.clone(),
+                allow_updates: config.allow_updates,
+            },
+            ESDRValueType::Text { value, config, .. } => SavedValue::Text {
+                value: value.clone(),
+                allow_updates: config.allow_updates,
+            },
+        }
+    }
+
+    /// A param with this value, for params the block didn't add itself.
+    fn param(&self, name: &str) -> Param {
+        match self {
+            SavedValue::Stream(item_type) => {
+                Param::input_stream(name).item_type(*item_type).build()
+            }
+            SavedValue::Scalar {
+                value,
+                allow_updates,
+            } => Param::scalar(name)
+                .initial_value(*value)
+                .allow_updates(*allow_updates)
+                .build(),
+            SavedValue::Toggle {
+                value,
+                allow_updates,
+            } => Param::toggle(name)
+                .initial_value(*value)
+                .allow_updates(*allow_updates)
+                .build(),
+            // the choices are listed again on the next refresh
+            SavedValue::Choice {
+                value,
+                allow_updates,
+            } => Param::choice(name)
+                .choices(vec![Choice::new(value, value)])
+                .allow_updates(*allow_updates)
+                .build(),
+            SavedValue::Text {
+                value,
+                allow_updates,
+            } => Param::text(name)
+                .initial_value(value.clone())
+                .allow_updates(*allow_updates)
+                .build(),
+        }
+    }
+
+    /// Sets the value of a param, if it is of the same kind.
+    fn restore(&self, value: &mut ESDRValueType) {
+        match (self, value) {
+            (SavedValue::Scalar { value: saved, .. }, ESDRValueType::Scalar { value, .. }) => {
+                *value = *saved
+            }
+            (SavedValue::Toggle { value: saved, .. }, ESDRValueType::Toggle { value, .. }) => {
+                *value = *saved
+            }
+            (SavedValue::Choice { value: saved, .. }, ESDRValueType::Choice { value, .. }) => {
+                *value = saved.clone()
+            }
+            (SavedValue::Text { value: saved, .. }, ESDRValueType::Text { value, .. }) => {
+                *value = saved.clone()
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedPanel {
+    node: usize,
+    floating: bool,
+}
+
+fn port_name<Id: PartialEq>(ports: &[(String, Id)], id: Id) -> Option<String> {
+    ports
+        .iter()
+        .find(|(_, port_id)| *port_id == id)
+        .map(|(name, _)| name.clone())
+}
+
+/// Saves the flowgraph of the editor, along with its panel layout.
+pub fn save(state: &ESDREditorState) -> Result<String> {
+    let graph = &state.graph;
+    let node_ids: Vec<NodeId> = state.node_order.clone();
+    let index: HashMap<NodeId, usize> = node_ids
+        .iter()
+        .enumerate()
+        .map(|(index, node_id)| (*node_id, index))
+        .collect();
+
+    let nodes = node_ids
+        .iter()
+        .map(|node_id| {
+            let node = &graph[*node_id];
+            let position = state
+                .node_positions
+                .get(*node_id)
+                .copied()
+                .unwrap_or_default();
+            SavedNode {
+                block: node.user_data.block_type.name().to_string(),
+                label: node.label.clone(),
+                position: (position.x, position.y),
+                inputs: node
+                    .inputs
+                    .iter()
+                    .map(|(name, input_id)| {
+                        let input = graph.get_input(*input_id);
+                        (name.clone(), SavedValue::new(input.typ, &input.value))
+                    })
+                    .collect(),
+                outputs: node
+                    .outputs
+                    .iter()
+                    .map(|(name, output_id)| {
+                        let item_type = match graph.get_output(*output_id).typ {
+                            ESDRDataType::Stream(item_type) => item_type,
+                            ESDRDataType::Scalar => StreamType::default(),
+                        };
+                        (name.clone(), item_type)
+                    })
+                    .collect(),
+                refreshed: node.user_data.refreshed.clone(),
+            }
+        })
+        .collect();
+
+    let mut connections = vec![];
+    for (input_id, output_id) in &graph.connections {
+        let input_node = graph.get_input(input_id).node;
+        let output_node = graph.get_output(*output_id).node;
+        let input = port_name(&graph[input_node].inputs, input_id);
+        let output = port_name(&graph[output_node].outputs, *output_id);
+        if let (Some(input), Some(output)) = (input, output) {
+            connections.push(((index[&output_node], output), (index[&input_node], input)));
+        }
+    }
+    connections.sort();
+
+    let layout = &state.user_state.panels;
+    let file = FlowgraphFile {
+        nodes,
+        connections,
+        panels: layout
+            .panels()
+            .filter_map(|(node_id, placement)| {
+                Some(SavedPanel {
+                    node: *index.get(&node_id)?,
+                    floating: placement == PanelPlacement::Floating,
+                })
+            })
+            .collect(),
+        run_view: layout.run_view,
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+/// Loads a flowgraph saved by `save` into a new editor.
+pub fn load(text: &str) -> Result<ESDREditorState> {
+    let file: FlowgraphFile = serde_json::from_str(text).context("Invalid flowgraph file")?;
+    let mut state = GraphEditorState::new(1.0, ESDRGraphState::default());
+
+    let mut node_ids = vec![];
+    for saved in &file.nodes {
+        let block_type = ESDRBlockType::iter()
+            .find(|block_type| block_type.name() == saved.block)
+            .ok_or_else(|| anyhow!("Unknown block \"{}\"", saved.block))?;
+        let node_id = state.graph.add_node(
+            saved.label.clone(),
+            block_type.user_data(),
+            |graph, node_id| block_type.build_node(graph, node_id),
+        );
+        let graph = &mut state.graph;
+        for (name, value) in &saved.inputs {
+            let input_id = match graph[node_id].get_input(name) {
+                Ok(input_id) => input_id,
+                Err(_) => {
+                    value.param(name).add_param(graph, node_id);
+                    graph[node_id]
+                        .get_input(name)
+                        .map_err(|_| anyhow!("Failed to add \"{}\"", name))?
+                }
+            };
+            value.restore(&mut graph.inputs[input_id].value);
+        }
+        for (name, item_type) in &saved.outputs {
+            if graph[node_id].get_output(name).is_err() {
+                Param::output_stream(name)
+                    .item_type(*item_type)
+                    .build()
+                    .add_param(graph, node_id);
+            }
+        }
+        graph[node_id].user_data.refreshed = saved.refreshed.clone();
+        state
+            .node_positions
+            .insert(node_id, egui::pos2(saved.position.0, saved.position.1));
+        state.node_order.push(node_id);
+        node_ids.push(node_id);
+    }
+
+    let node_id = |index: usize| {
+        node_ids
+            .get(index)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown node {}", index))
+    };
+    for ((output_node, output), (input_node, input)) in &file.connections {
+        let graph = &mut state.graph;
+        let output_id = graph[node_id(*output_node)?]
+            .get_output(output)
+            .map_err(|_| anyhow!("Unknown output \"{}\"", output))?;
+        let input_id = graph[node_id(*input_node)?]
+            .get_input(input)
+            .map_err(|_| anyhow!("Unknown input \"{}\"", input))?;
+        graph.add_connection(output_id, input_id);
+    }
+
+    let layout = &mut state.user_state.panels;
+    for panel in &file.panels {
+        let placement = match panel.floating {
+            true => PanelPlacement::Floating,
+            false => PanelPlacement::Docked,
+        };
+        layout.open(node_id(panel.node)?, placement);
+    }
+    layout.run_view = file.run_view;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ui::ESDRGraph;
+
+    fn add_node(state: &mut ESDREditorState, block_type: ESDRBlockType, x: f32) -> NodeId {
+        let node_id = state.graph.add_node(
+            block_type.node_graph_label(),
+            block_type.user_data(),
+            |graph, node_id| block_type.build_node(graph, node_id),
+        );
+        state.node_positions.insert(node_id, egui::pos2(x, 10.0));
+        state.node_order.push(node_id);
+        node_id
+    }
+
+    fn connect(graph: &mut ESDRGraph, from: (NodeId, &str), to: (NodeId, &str)) {
+        let output_id = graph[from.0].get_output(from.1).unwrap();
+        let input_id = graph[to.0].get_input(to.1).unwrap();
+        graph.add_connection(output_id, input_id);
+    }
+
+    fn scalar(graph: &ESDRGraph, node_id: NodeId, name: &str) -> f64 {
+        let input_id = graph[node_id].get_input(name).unwrap();
+        match graph.get_input(input_id).value {
+            ESDRValueType::Scalar { value, .. } => value,
+            _ => panic!("{} is not a scalar", name),
+        }
+    }
+
+    fn flowgraph() -> ESDREditorState {
+        let mut state = GraphEditorState::new(1.0, ESDRGraphState::default());
+        let source = add_node(
+            &mut state,
+            ESDRBlockType::FloatNullSource(Default::default()),
+            0.0,
+        );
+        let volume = add_node(&mut state, ESDRBlockType::Volume(Default::default()), 100.0);
+        let mixer = add_node(
+            &mut state,
+            ESDRBlockType::AudioMixer(Default::default()),
+            200.0,
+        );
+        let graph = &mut state.graph;
+        // an input the block only adds on refresh
+        Param::scalar("gain 3")
+            .initial_value(0.5)
+            .allow_updates(true)
+            .build()
+            .add_param(graph, mixer);
+        graph[mixer].user_data.refreshed = vec!["gain 3".to_string()];
+        let input_id = graph[volume].get_input("volume").unwrap();
+        if let ESDRValueType::Scalar { value, .. } = &mut graph.inputs[input_id].value {
+            *value = 0.25;
+        }
+        connect(graph, (source, "out"), (volume, "in"));
+        connect(graph, (volume, "out"), (mixer, "in 1"));
+        let layout = &mut state.user_state.panels;
+        layout.open(volume, PanelPlacement::Floating);
+        layout.open(mixer, PanelPlacement::Docked);
+        layout.run_view = true;
+        state
+    }
+
+    #[test]
+    fn saves_and_loads_the_flowgraph_and_its_layout() {
+        let saved = save(&flowgraph()).unwrap();
+        let state = load(&saved).unwrap();
+        assert_eq!(save(&state).unwrap(), saved);
+
+        let graph = &state.graph;
+        let [_, volume, mixer] = [0, 1, 2].map(|index| state.node_order[index]);
+        assert_eq!(scalar(graph, volume, "volume"), 0.25);
+        assert_eq!(scalar(graph, mixer, "gain 3"), 0.5);
+        assert_eq!(graph[mixer].user_data.refreshed, vec!["gain 3".to_string()]);
+        assert_eq!(graph.connections.len(), 2);
+        assert_eq!(state.node_positions[mixer], egui::pos2(200.0, 10.0));
+        let layout = &state.user_state.panels;
+        assert_eq!(
+            layout.panels().collect::<Vec<_>>(),
+            vec![
+                (volume, PanelPlacement::Floating),
+                (mixer, PanelPlacement::Docked)
+            ]
+        );
+        assert!(layout.run_view);
+    }
+
+    #[test]
+    fn rejects_unknown_blocks_and_ports() {
+        let saved = save(&flowgraph()).unwrap();
+        let edited = |edit: fn(&mut FlowgraphFile)| {
+            let mut file = serde_json::from_str(&saved).unwrap();
+            edit(&mut file);
+            serde_json::to_string(&file).unwrap()
+        };
+        assert!(load(&edited(|file| file.nodes[1].block = "Nope".to_string())).is_err());
+        assert!(load(&edited(|file| file.connections[0].1 .1 = "in 9".to_string())).is_err());
+        assert!(load(&edited(|file| file.panels[0].node = 7)).is_err());
+        assert!(load("{").is_err());
+    }
+}
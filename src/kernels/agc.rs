@@ -0,0 +1,265 @@
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::ops::Mul;
+use std::pin::Pin;
+
+use futuresdr::anyhow::anyhow;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Presets with their attack, decay and hang times, in seconds. "custom" uses
+/// the times given separately.
+pub const AGC_PRESETS: [(&str, Option<(f64, f64, f64)>); 4] = [
+    ("custom", None),
+    ("slow", Some((0.01, 1.0, 0.5))),
+    ("medium", Some((0.005, 0.3, 0.2))),
+    ("fast", Some((0.002, 0.1, 0.05))),
+];
+
+/// Attack, decay and hang times of a preset, or `None` for "custom".
+pub fn agc_preset(name: &str) -> Result<Option<(f64, f64, f64)>> {
+    AGC_PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, times)| *times)
+        .ok_or_else(|| anyhow!("Unknown AGC preset \"{}\"", name))
+}
+
+pub trait AgcSample: Copy + Send + Mul<f32, Output = Self> + 'static {
+    fn magnitude(self) -> f32;
+}
+
+impl AgcSample for f32 {
+    fn magnitude(self) -> f32 {
+        self.abs()
+    }
+}
+
+impl AgcSample for Complex32 {
+    fn magnitude(self) -> f32 {
+        self.norm()
+    }
+}
+
+/// Times in seconds, levels as amplitudes and the maximum gain in dB.
+#[derive(Clone, Copy, Debug)]
+pub struct AgcSettings {
+    pub preset: Option<(f64, f64, f64)>,
+    pub attack: f64,
+    pub decay: f64,
+    pub hang: f64,
+    pub target: f64,
+    pub max_gain: f64,
+}
+
+/// Automatic gain control. The level of the signal follows its peaks with
+/// the attack time, holds for the hang time once they are gone, then decays,
+/// and the signal is scaled to bring that level to the target.
+pub struct Agc<T> {
+    sample_rate: f64,
+    settings: AgcSettings,
+    attack: f32,
+    decay: f32,
+    hang: usize,
+    target: f32,
+    max_gain: f32,
+    level: f32,
+    /// samples left before the level starts to decay
+    hang_left: usize,
+    status: NodeStatus,
+    _sample: std::marker::PhantomData<T>,
+}
+
+impl<T> Agc<T>
+where
+    T: AgcSample,
+{
+    pub fn new(sample_rate: f64, settings: AgcSettings, status: NodeStatus) -> Block {
+        let mut agc = Agc::<T> {
+            sample_rate,
+            settings,
+            attack: 0.0,
+            decay: 0.0,
+            hang: 0,
+            target: 0.0,
+            max_gain: 0.0,
+            level: 0.0,
+            hang_left: 0,
+            status,
+            _sample: std::marker::PhantomData,
+        };
+        agc.configure();
+        Block::new(
+            BlockMetaBuilder::new("Agc").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("preset", Agc::<T>::preset_handler)
+                .add_input("attack", Agc::<T>::attack_handler)
+                .add_input("decay", Agc::<T>::decay_handler)
+                .add_input("hang", Agc::<T>::hang_handler)
+                .add_input("target", Agc::<T>::target_handler)
+                .add_input("max gain", Agc::<T>::max_gain_handler)
+                .build(),
+            agc,
+        )
+    }
+
+    fn configure(&mut self) {
+        let (attack, decay, hang) = self.settings.preset.unwrap_or((
+            self.settings.attack,
+            self.settings.decay,
+            self.settings.hang,
+        ));
+        let alpha = |time: f64| (1.0 - (-1.0 / (time * self.sample_rate).max(1.0)).exp()) as f32;
+        self.attack = alpha(attack);
+        self.decay = alpha(decay);
+        self.hang = (hang.max(0.0) * self.sample_rate) as usize;
+        self.target = self.settings.target as f32;
+        self.max_gain = 10f64.powf(self.settings.max_gain / 20.0) as f32;
+    }
+
+    fn update(&mut self, p: Pmt, change: impl FnOnce(&mut AgcSettings, f64)) {
+        if let Pmt::Double(value) = p {
+            change(&mut self.settings, value);
+            self.configure();
+        }
+    }
+
+    fn preset_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Agc<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(name) = p {
+                match agc_preset(&name) {
+                    Ok(preset) => {
+                        self.settings.preset = preset;
+                        self.configure();
+                    }
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn attack_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Agc<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            self.update(p, |settings, attack| settings.attack = attack);
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn decay_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Agc<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            self.update(p, |settings, decay| settings.decay = decay);
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn hang_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Agc<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            self.update(p, |settings, hang| settings.hang = hang);
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn target_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Agc<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            self.update(p, |settings, target| settings.target = target);
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn max_gain_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Agc<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            self.update(p, |settings, max_gain| settings.max_gain = max_gain);
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for Agc<T>
+where
+    T: AgcSample,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            let magnitude = x.magnitude();
+            if magnitude > self.level {
+                self.level += (magnitude - self.level) * self.attack;
+                self.hang_left = self.hang;
+            } else if self.hang_left > 0 {
+                self.hang_left -= 1;
+            } else {
+                self.level += (magnitude - self.level) * self.decay;
+            }
+            let gain = (self.target / self.level.max(f32::MIN_POSITIVE)).min(self.max_gain);
+            *y = *x * gain;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use crate::dsp::biquad;
+use crate::dsp::biquad::Biquad;
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::f32::consts::PI;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::anyhow;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+pub const AM_MODES: [&str; 2] = ["envelope", "synchronous"];
+const CARRIER_LOOP_BANDWIDTH: f64 = 30.0;
+/// how far from the center the carrier is tracked, in Hz
+const CARRIER_RANGE: f64 = 500.0;
+/// time constant of the carrier level, in seconds
+const CARRIER_SMOOTHING: f64 = 0.2;
+
+/// Second order PLL locking on the carrier of an AM signal.
+struct CarrierPll {
+    phase: f32,
+    freq: f32,
+    max_freq: f32,
+    alpha: f32,
+    beta: f32,
+}
+
+impl CarrierPll {
+    fn new(sample_rate: f64) -> CarrierPll {
+        let omega = (2.0 * std::f64::consts::PI * CARRIER_LOOP_BANDWIDTH / sample_rate) as f32;
+        CarrierPll {
+            phase: 0.0,
+            freq: 0.0,
+            max_freq: (2.0 * std::f64::consts::PI * CARRIER_RANGE / sample_rate) as f32,
+            alpha: 2.0 * std::f32::consts::FRAC_1_SQRT_2 * omega,
+            beta: omega * omega,
+        }
+    }
+
+    /// Returns the sample rotated so that the carrier is on the real axis.
+    fn track(&mut self, sample: Complex32) -> Complex32 {
+        let out = sample * Complex32::from_polar(1.0, -self.phase);
+        let error = out.im.atan2(out.re);
+        self.freq = (self.freq + self.beta * error).clamp(-self.max_freq, self.max_freq);
+        self.phase = (self.phase + self.freq + self.alpha * error) % (2.0 * PI);
+        out
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Envelope,
+    Synchronous,
+}
+
+impl Mode {
+    fn parse(name: &str) -> Result<Mode> {
+        match name {
+            "envelope" => Ok(Mode::Envelope),
+            "synchronous" => Ok(Mode::Synchronous),
+            _ => Err(anyhow!("Unknown AM mode {}", name)),
+        }
+    }
+}
+
+/// AM receiver. The carrier is detected either from the envelope or with a
+/// PLL, its level is removed from the audio and, with AGC, the audio is
+/// normalized by it so that full modulation gives a level of `gain`.
+pub struct AmReceiver {
+    sample_rate: f64,
+    mode: Mode,
+    pll: CarrierPll,
+    carrier_alpha: f32,
+    carrier: f32,
+    agc: bool,
+    gain: f32,
+    lowpass: [Biquad; 2],
+    status: NodeStatus,
+}
+
+impl AmReceiver {
+    pub fn new(
+        sample_rate: f64,
+        mode: &str,
+        bandwidth: f64,
+        agc: bool,
+        gain: f64,
+        status: NodeStatus,
+    ) -> Result<Block> {
+        Ok(Block::new(
+            BlockMetaBuilder::new("AmReceiver").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("mode", AmReceiver::mode_handler)
+                .add_input("bandwidth", AmReceiver::bandwidth_handler)
+                .add_input("agc", AmReceiver::agc_handler)
+                .add_input("gain", AmReceiver::gain_handler)
+                .build(),
+            AmReceiver {
+                sample_rate,
+                mode: Mode::parse(mode)?,
+                pll: CarrierPll::new(sample_rate),
+                carrier_alpha: (1.0 - (-1.0 / (CARRIER_SMOOTHING * sample_rate)).exp()) as f32,
+                carrier: 0.0,
+                agc,
+                gain: gain as f32,
+                lowpass: AmReceiver::lowpass(bandwidth, sample_rate),
+                status,
+            },
+        ))
+    }
+
+    fn lowpass(bandwidth: f64, sample_rate: f64) -> [Biquad; 2] {
+        [0, 1]
+            .map(|section| Biquad::lowpass(bandwidth, biquad::BUTTERWORTH_Q[section], sample_rate))
+    }
+
+    fn mode_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<AmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(name) = p {
+                match Mode::parse(&name) {
+                    Ok(mode) => self.mode = mode,
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn bandwidth_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<AmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(bandwidth) = p {
+                if bandwidth > 0.0 && bandwidth < self.sample_rate / 2.0 {
+                    self.lowpass = AmReceiver::lowpass(bandwidth, self.sample_rate);
+                } else {
+                    self.status.warn(&anyhow!(
+                        "The bandwidth must be below {} Hz",
+                        self.sample_rate / 2.0
+                    ));
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn agc_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<AmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(agc) = p {
+                self.agc = agc;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn gain_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<AmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(gain) = p {
+                self.gain = gain as f32;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for AmReceiver {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<f32>();
+
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            // the PLL keeps tracking in envelope mode, so that switching is
+            // seamless
+            let locked = self.pll.track(*x);
+            let detected = match self.mode {
+                Mode::Envelope => x.norm(),
+                Mode::Synchronous => locked.re,
+            };
+            self.carrier += (detected - self.carrier) * self.carrier_alpha;
+            let audio = if self.agc {
+                detected / self.carrier.max(f32::EPSILON) - 1.0
+            } else {
+                detected - self.carrier
+            };
+            let audio = self
+                .lowpass
+                .iter_mut()
+                .fold(audio, |x, section| section.filter(x));
+            *y = audio * self.gain;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
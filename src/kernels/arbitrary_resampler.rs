@@ -0,0 +1,228 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::ops::Add;
+use std::ops::Mul;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Resampler by any ratio. The input is interpolated by a polyphase
+/// filterbank, and outputs falling between two branches are linearly
+/// interpolated from both.
+pub struct ArbitraryResampler<T> {
+    /// `phases + 1` filters over the newest `len + 1` samples, the last one
+    /// being the first branch one sample later
+    filters: Vec<Vec<f32>>,
+    /// input samples per output sample
+    step: f64,
+    /// position of the next output between the two newest samples used, in
+    /// input samples
+    position: f64,
+    /// the samples before the next one to use, followed by the ones not used
+    /// yet
+    history: Vec<T>,
+    timer: WorkTimer,
+}
+
+impl<T> ArbitraryResampler<T>
+where
+    T: Copy + Default + Send + 'static + Mul<f32, Output = T> + Add<Output = T>,
+{
+    /// `taps` is the prototype of the filterbank, see
+    /// [`polyphase_lowpass`](crate::dsp::firdes::polyphase_lowpass).
+    pub fn new(rate: f64, phases: usize, taps: Vec<f32>, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("ArbitraryResampler").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Self::with_taps(rate, phases, taps, timer),
+        )
+    }
+
+    fn with_taps(rate: f64, phases: usize, taps: Vec<f32>, timer: WorkTimer) -> Self {
+        let len = taps.len() / phases;
+        let mut filters = vec![vec![0.0; len + 1]; phases + 1];
+        for (k, filter) in filters.iter_mut().enumerate() {
+            for j in 0..len {
+                if k < phases {
+                    filter[j + 1] = taps[k + j * phases];
+                } else {
+                    filter[j] = taps[j * phases];
+                }
+            }
+        }
+        ArbitraryResampler {
+            timer,
+            filters,
+            step: 1.0 / rate,
+            position: 0.0,
+            history: vec![T::default(); len],
+        }
+    }
+
+    /// Output of a filter, with the newest sample of `samples` going with the
+    /// first tap.
+    fn filter(&self, k: usize, samples: &[T]) -> T {
+        samples
+            .iter()
+            .rev()
+            .zip(&self.filters[k])
+            .fold(T::default(), |acc, (x, tap)| acc + *x * *tap)
+    }
+
+    /// Resamples as much of `i` as fits into `o`. Returns the
+    /// number of samples consumed and produced.
+    fn resample(&mut self, i: &[T], o: &mut [T]) -> (usize, usize) {
+        let len = self.filters[0].len() - 1;
+        let phases = self.filters.len() - 1;
+        // only take what is needed to fill the output, so that the history
+        // doesn't grow
+        let pending = self.history.len() - len;
+        let needed = (o.len() as f64 * self.step).ceil() as usize + 1;
+        let n = cmp::min(i.len(), needed.saturating_sub(pending));
+        self.history.extend_from_slice(&i[..n]);
+
+        let mut next = len;
+        let mut produced = 0;
+        while produced < o.len() {
+            while self.position >= 1.0 && next < self.history.len() {
+                self.position -= 1.0;
+                next += 1;
+            }
+            if next >= self.history.len() {
+                break;
+            }
+            let branch = self.position * phases as f64;
+            let k = branch.floor() as usize;
+            let mu = (branch - k as f64) as f32;
+            let samples = &self.history[next - len..=next];
+            o[produced] = self.filter(k, samples) * (1.0 - mu) + self.filter(k + 1, samples) * mu;
+            produced += 1;
+            self.position += self.step;
+        }
+        self.history.drain(..next - len);
+
+        (n, produced)
+    }
+
+    /// Whether the history is used up, so that more input is needed for the
+    /// next output.
+    fn exhausted(&self) -> bool {
+        let len = self.filters[0].len() - 1;
+        len + self.position.floor() as usize >= self.history.len()
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for ArbitraryResampler<T>
+where
+    T: Copy + Default + Send + 'static + Mul<f32, Output = T> + Add<Output = T>,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let (n, produced) = self.resample(i, o);
+        sio.input(0).consume(n);
+        sio.output(0).produce(produced);
+
+        // done once all the input is in and no more output can be made of it,
+        // whether or not the output was filled
+        if sio.input(0).finished() && n == i.len() && self.exhausted() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dsp::firdes;
+
+    const PHASES: usize = 32;
+
+    fn resampler(rate: f64) -> ArbitraryResampler<f32> {
+        let taps = firdes::polyphase_lowpass(PHASES, 0.2 * rate.min(1.0), 0.1, 60.0).unwrap();
+        ArbitraryResampler::with_taps(rate, PHASES, taps, WorkTimer::default())
+    }
+
+    /// Feeds all of `input` through in chunks of `chunk` output samples.
+    fn run(resampler: &mut ArbitraryResampler<f32>, input: &[f32], chunk: usize) -> Vec<f32> {
+        let mut output = vec![];
+        let mut input = input;
+        loop {
+            let mut o = vec![0.0; chunk];
+            let (n, produced) = resampler.resample(input, &mut o);
+            output.extend_from_slice(&o[..produced]);
+            input = &input[n..];
+            if input.is_empty() && resampler.exhausted() {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn resamples_by_the_rate() {
+        for rate in [0.3, 1.0, 2.5] {
+            let output = run(&mut resampler(rate), &[1.0; 1000], 64);
+            let expected = 1000.0 * rate;
+            assert!(
+                (output.len() as f64 - expected).abs() <= 1.0,
+                "{} outputs at rate {}",
+                output.len(),
+                rate
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_gain() {
+        for rate in [0.3, 1.0, 2.5] {
+            let output = run(&mut resampler(rate), &[1.0; 1000], 64);
+            // past the filter's transient
+            for &y in &output[output.len() / 2..] {
+                assert!((y - 1.0).abs() < 1e-2, "{} at rate {}", y, rate);
+            }
+        }
+    }
+
+    #[test]
+    fn doesnt_depend_on_the_output_size() {
+        let input: Vec<f32> = (0..500).map(|i| (i as f32 * 0.05).sin()).collect();
+        let whole = run(&mut resampler(0.7), &input, 1000);
+        let chunked = run(&mut resampler(0.7), &input, 3);
+        assert_eq!(whole, chunked);
+    }
+
+    #[test]
+    fn is_exhausted_when_the_output_fills_up_exactly() {
+        let mut resampler = resampler(1.0);
+        let mut o = vec![0.0; 10];
+        let (n, produced) = resampler.resample(&[1.0; 10], &mut o);
+        assert_eq!((n, produced), (10, 10));
+        assert!(resampler.exhausted());
+    }
+}
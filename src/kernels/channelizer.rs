@@ -0,0 +1,126 @@
+use crate::stats::WorkTimer;
+
+use std::f64::consts::PI;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Critically sampled polyphase filterbank, splitting a stream into
+/// `num_channels` channels spaced by `sample_rate / num_channels`, each
+/// decimated by `num_channels`. Channel `k` is centered on
+/// `k * sample_rate / num_channels`, so the upper half of the channels are
+/// the negative frequencies. Only the given channels are computed.
+pub struct Channelizer {
+    /// `num_channels` branches of the prototype lowpass
+    branches: Vec<Vec<f32>>,
+    channels: Vec<usize>,
+    /// `twiddles[k * num_channels + p]` rotates branch `p` into channel `k`
+    twiddles: Vec<Complex32>,
+    /// the last `taps.len() - 1` input samples
+    history: Vec<Complex32>,
+    prototype_len: usize,
+    timer: WorkTimer,
+}
+
+impl Channelizer {
+    /// Outputs are added in the order of `channels`, with the given names.
+    pub fn new(
+        num_channels: usize,
+        taps: Vec<f32>,
+        channels: Vec<(usize, String)>,
+        timer: WorkTimer,
+    ) -> Block {
+        let branches = (0..num_channels)
+            .map(|p| taps.iter().skip(p).step_by(num_channels).copied().collect())
+            .collect();
+        let twiddles = (0..num_channels * num_channels)
+            .map(|i| {
+                let (k, p) = (i / num_channels, i % num_channels);
+                let phase = 2.0 * PI * ((k * p) % num_channels) as f64 / num_channels as f64;
+                Complex32::from_polar(1.0, phase as f32)
+            })
+            .collect();
+        let mut sio = StreamIoBuilder::new().add_input("in", std::mem::size_of::<Complex32>());
+        for (_, name) in &channels {
+            sio = sio.add_output(name, std::mem::size_of::<Complex32>());
+        }
+        Block::new(
+            BlockMetaBuilder::new("Channelizer").build(),
+            sio.build(),
+            MessageIoBuilder::new().build(),
+            Channelizer {
+                timer,
+                branches,
+                channels: channels.into_iter().map(|(channel, _)| channel).collect(),
+                twiddles,
+                history: vec![Complex32::default(); taps.len() - 1],
+                prototype_len: taps.len(),
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl Kernel for Channelizer {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let num_channels = self.branches.len();
+        let i = sio.input(0).slice::<Complex32>();
+        // without any channel connected, the input is simply dropped
+        let space = (0..self.channels.len())
+            .map(|output| sio.output(output).slice::<Complex32>().len())
+            .min()
+            .unwrap_or(usize::MAX);
+        let blocks = (i.len() / num_channels).min(space);
+        self.history.extend_from_slice(&i[..blocks * num_channels]);
+
+        let mut branch_outputs = vec![Complex32::default(); num_channels];
+        for m in 0..blocks {
+            // newest sample of this block
+            let t = self.prototype_len - 1 + m * num_channels + num_channels - 1;
+            for (p, branch) in self.branches.iter().enumerate() {
+                branch_outputs[p] = branch
+                    .iter()
+                    .enumerate()
+                    .map(|(q, tap)| self.history[t - p - q * num_channels] * tap)
+                    .sum();
+            }
+            for (output, channel) in self.channels.iter().enumerate() {
+                let twiddles = &self.twiddles[channel * num_channels..][..num_channels];
+                sio.output(output).slice::<Complex32>()[m] = branch_outputs
+                    .iter()
+                    .zip(twiddles)
+                    .map(|(v, twiddle)| v * twiddle)
+                    .sum();
+            }
+        }
+        self.history.drain(..blocks * num_channels);
+
+        sio.input(0).consume(blocks * num_channels);
+        for output in 0..self.channels.len() {
+            sio.output(output).produce(blocks);
+        }
+
+        if sio.input(0).finished() && i.len() - blocks * num_channels < num_channels {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
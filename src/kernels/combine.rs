@@ -0,0 +1,127 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::marker::PhantomData;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Applies a function to pairs of items from two inputs, e.g. to add or
+/// multiply two streams.
+pub struct Combine<A, B, O, F>
+where
+    F: FnMut(&A, &B) -> O + Send + 'static,
+{
+    f: F,
+    _types: PhantomData<fn(A, B) -> O>,
+    timer: WorkTimer,
+}
+
+impl<A, B, O, F> Combine<A, B, O, F>
+where
+    A: Copy + Send + 'static,
+    B: Copy + Send + 'static,
+    O: Copy + Send + 'static,
+    F: FnMut(&A, &B) -> O + Send + 'static,
+{
+    pub fn new(inputs: [&str; 2], output: &str, f: F, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Combine").build(),
+            StreamIoBuilder::new()
+                .add_input(inputs[0], std::mem::size_of::<A>())
+                .add_input(inputs[1], std::mem::size_of::<B>())
+                .add_output(output, std::mem::size_of::<O>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Combine::with_function(f, timer),
+        )
+    }
+
+    fn with_function(f: F, timer: WorkTimer) -> Self {
+        Combine {
+            timer,
+            f,
+            _types: PhantomData,
+        }
+    }
+
+    /// Combines as many pairs as there are on both inputs and fit into the
+    /// output, and returns how many.
+    fn combine(&mut self, a: &[A], b: &[B], o: &mut [O]) -> usize {
+        let n = cmp::min(cmp::min(a.len(), b.len()), o.len());
+        for ((o, a), b) in o[..n].iter_mut().zip(&a[..n]).zip(&b[..n]) {
+            *o = (self.f)(a, b);
+        }
+        n
+    }
+}
+
+#[async_trait]
+impl<A, B, O, F> Kernel for Combine<A, B, O, F>
+where
+    A: Copy + Send + 'static,
+    B: Copy + Send + 'static,
+    O: Copy + Send + 'static,
+    F: FnMut(&A, &B) -> O + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let a = sio.input(0).slice::<A>();
+        let b = sio.input(1).slice::<B>();
+        let o = sio.output(0).slice::<O>();
+
+        let n = self.combine(a, b, o);
+        sio.input(0).consume(n);
+        sio.input(1).consume(n);
+        sio.output(0).produce(n);
+
+        // once either side is done, nothing more can be combined
+        if (sio.input(0).finished() && n == a.len()) || (sio.input(1).finished() && n == b.len()) {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combines_pairs_of_items() {
+        let mut add = Combine::with_function(|a: &f32, b: &f32| a + b, WorkTimer::default());
+        let mut o = [0.0; 4];
+        assert_eq!(
+            add.combine(&[1.0, 2.0, 3.0], &[10.0, 20.0, 30.0], &mut o),
+            3
+        );
+        assert_eq!(o, [11.0, 22.0, 33.0, 0.0]);
+    }
+
+    #[test]
+    fn waits_for_both_inputs() {
+        let mut multiply =
+            Combine::with_function(|a: &f32, b: &i16| a * *b as f32, WorkTimer::default());
+        let mut o = [0.0; 4];
+        assert_eq!(multiply.combine(&[1.0, 2.0, 3.0], &[2], &mut o), 1);
+        assert_eq!(multiply.combine(&[1.0, 2.0, 3.0], &[], &mut o), 0);
+        assert_eq!(multiply.combine(&[1.0, 2.0], &[2, 3, 4], &mut o[..1]), 1);
+        assert_eq!(o[0], 2.0);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Removes the DC offset of a complex stream with a single pole highpass,
+/// `y[n] = x[n] - x[n-1] + r * y[n-1]`, the pole being placed for the given
+/// cutoff.
+pub struct DcBlocker {
+    sample_rate: f64,
+    pole: f32,
+    last_input: Complex32,
+    last_output: Complex32,
+    timer: WorkTimer,
+}
+
+impl DcBlocker {
+    pub fn new(sample_rate: f64, cutoff: f64, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("DcBlocker").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<Complex32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("cutoff", DcBlocker::cutoff_handler)
+                .build(),
+            DcBlocker {
+                timer,
+                sample_rate,
+                pole: DcBlocker::pole(cutoff, sample_rate),
+                last_input: Complex32::default(),
+                last_output: Complex32::default(),
+            },
+        )
+    }
+
+    fn pole(cutoff: f64, sample_rate: f64) -> f32 {
+        (-2.0 * std::f64::consts::PI * cutoff.max(0.0) / sample_rate).exp() as f32
+    }
+
+    fn cutoff_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<DcBlocker>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(cutoff) = p {
+                self.pole = DcBlocker::pole(cutoff, self.sample_rate);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for DcBlocker {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<Complex32>();
+
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            *y = x - self.last_input + self.last_output * self.pole;
+            self.last_input = *x;
+            self.last_output = *y;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::marker::PhantomData;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Delays a stream by a number of samples, by outputting that many zeros
+/// before it.
+pub struct Delay<T> {
+    /// zeros left to output
+    zeros: usize,
+    _type: PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> Delay<T>
+where
+    T: Copy + Default + Send + 'static,
+{
+    pub fn new(delay: usize, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Delay").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Delay::<T> {
+                timer,
+                zeros: delay,
+                _type: PhantomData,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for Delay<T>
+where
+    T: Copy + Default + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let zeros = cmp::min(self.zeros, o.len());
+        o[..zeros].fill(T::default());
+        self.zeros -= zeros;
+        let n = cmp::min(i.len(), o.len() - zeros);
+        o[zeros..zeros + n].copy_from_slice(&i[..n]);
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(zeros + n);
+
+        // zeros are only left when the output is full, and the block is woken
+        // up again once there is room
+        if self.zeros == 0 && sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
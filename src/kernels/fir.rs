@@ -0,0 +1,239 @@
+use crate::dsp::firdes;
+use crate::dsp::firdes::FilterSpec;
+use crate::dsp::firdes::Taps;
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::ops::AddAssign;
+use std::ops::Mul;
+use std::pin::Pin;
+
+use futuresdr::anyhow::bail;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+pub trait Tap: Copy + Send + 'static {
+    fn from_taps(taps: Taps) -> Result<Vec<Self>>;
+}
+
+impl Tap for f32 {
+    fn from_taps(taps: Taps) -> Result<Vec<f32>> {
+        match taps {
+            Taps::Real(taps) => Ok(taps),
+            Taps::Complex(_) => bail!("Complex taps can only filter complex streams"),
+        }
+    }
+}
+
+impl Tap for Complex32 {
+    fn from_taps(taps: Taps) -> Result<Vec<Complex32>> {
+        Ok(match taps {
+            Taps::Real(taps) => taps.iter().map(|tap| Complex32::new(*tap, 0.0)).collect(),
+            Taps::Complex(taps) => taps,
+        })
+    }
+}
+
+/// FIR filter designed from a [`FilterSpec`]. Changing the spec while
+/// running redesigns the filter, and the samples already seen are kept so that
+/// the output stays continuous.
+pub struct Fir<T, U> {
+    spec: FilterSpec,
+    taps: Vec<U>,
+    /// the last `taps.len() - 1` input samples
+    history: Vec<T>,
+    status: NodeStatus,
+}
+
+impl<T, U> Fir<T, U>
+where
+    T: Copy + Default + Send + 'static + Mul<U, Output = T> + AddAssign,
+    U: Tap,
+{
+    pub fn new(spec: FilterSpec, status: NodeStatus) -> Result<Block> {
+        let taps = U::from_taps(firdes::design(&spec)?)?;
+        Ok(Block::new(
+            BlockMetaBuilder::new("Fir").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("cutoff", Self::cutoff_handler)
+                .add_input("cutoff high", Self::cutoff_high_handler)
+                .add_input("transition", Self::transition_handler)
+                .add_input("attenuation", Self::attenuation_handler)
+                .add_input("symbol rate", Self::symbol_rate_handler)
+                .add_input("rolloff", Self::rolloff_handler)
+                .build(),
+            Fir {
+                spec,
+                history: vec![T::default(); taps.len() - 1],
+                taps,
+                status,
+            },
+        ))
+    }
+
+    /// Redesigns the filter with an updated spec. If the new spec is invalid
+    /// the previous filter is kept.
+    fn redesign(&mut self, update: impl FnOnce(&mut FilterSpec)) {
+        let mut spec = self.spec.clone();
+        update(&mut spec);
+        match firdes::design(&spec).and_then(U::from_taps) {
+            Ok(taps) => {
+                let len = taps.len() - 1;
+                if len > self.history.len() {
+                    let missing = len - self.history.len();
+                    self.history
+                        .splice(0..0, std::iter::repeat(T::default()).take(missing));
+                } else {
+                    self.history.drain(..self.history.len() - len);
+                }
+                self.taps = taps;
+                self.spec = spec;
+                self.status.clear();
+            }
+            Err(e) => self.status.warn(&e),
+        }
+    }
+
+    fn cutoff_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Self>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(cutoff) = p {
+                self.redesign(|spec| spec.cutoff = cutoff);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn cutoff_high_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Self>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(cutoff) = p {
+                self.redesign(|spec| spec.cutoff_high = cutoff);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn transition_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Self>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(transition) = p {
+                self.redesign(|spec| spec.transition = transition);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn attenuation_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Self>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(attenuation) = p {
+                self.redesign(|spec| spec.attenuation = attenuation);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn symbol_rate_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Self>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(symbol_rate) = p {
+                self.redesign(|spec| spec.symbol_rate = symbol_rate);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn rolloff_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Self>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(rolloff) = p {
+                self.redesign(|spec| spec.rolloff = rolloff);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl<T, U> Kernel for Fir<T, U>
+where
+    T: Copy + Default + Send + 'static + Mul<U, Output = T> + AddAssign,
+    U: Tap,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let n = cmp::min(i.len(), o.len());
+        if n > 0 {
+            let len = self.taps.len();
+            self.history.extend_from_slice(&i[..n]);
+            for (k, out) in o[..n].iter_mut().enumerate() {
+                let mut acc = T::default();
+                // the newest sample goes with the first tap
+                for (x, tap) in self.history[k..k + len].iter().rev().zip(&self.taps) {
+                    acc += *x * *tap;
+                }
+                *out = acc;
+            }
+            self.history.drain(..n);
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::dsp::nco::Nco;
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Shifts a complex stream in frequency by mixing it with an [`Nco`]. Both
+/// the shift and the sample rate can be changed while running.
+pub struct FrequencyTranslator {
+    nco: Nco,
+    shift: f64,
+    sample_rate: f64,
+    timer: WorkTimer,
+}
+
+impl FrequencyTranslator {
+    pub fn new(shift: f64, sample_rate: f64, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("FrequencyTranslator").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<Complex32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("shift", FrequencyTranslator::shift_handler)
+                .add_input("sample rate", FrequencyTranslator::sample_rate_handler)
+                .build(),
+            FrequencyTranslator {
+                timer,
+                nco: Nco::new(shift, sample_rate),
+                shift,
+                sample_rate,
+            },
+        )
+    }
+
+    fn shift_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<FrequencyTranslator>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(shift) = p {
+                self.shift = shift;
+                self.nco.set_freq(self.shift, self.sample_rate);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn sample_rate_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<FrequencyTranslator>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            match p {
+                Pmt::Double(sample_rate) if sample_rate > 0.0 => {
+                    self.sample_rate = sample_rate;
+                    self.nco.set_freq(self.shift, self.sample_rate);
+                }
+                Pmt::Double(sample_rate) => {
+                    log::warn!("Ignoring invalid sample rate {}", sample_rate);
+                }
+                _ => {}
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for FrequencyTranslator {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<Complex32>();
+
+        let n = cmp::min(i.len(), o.len());
+        self.nco.mix(&i[..n], &mut o[..n]);
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
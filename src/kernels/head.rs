@@ -0,0 +1,76 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::marker::PhantomData;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Passes the first samples of a stream, then finishes.
+pub struct Head<T> {
+    /// samples left to pass
+    left: usize,
+    _type: PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> Head<T>
+where
+    T: Copy + Send + 'static,
+{
+    pub fn new(count: usize, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Head").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Head::<T> {
+                timer,
+                left: count,
+                _type: PhantomData,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for Head<T>
+where
+    T: Copy + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let n = cmp::min(cmp::min(i.len(), o.len()), self.left);
+        o[..n].copy_from_slice(&i[..n]);
+        self.left -= n;
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if self.left == 0 || (sio.input(0).finished() && n == i.len()) {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use crate::radio::NodeStatus;
+
+use std::cmp;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Blind IQ imbalance correction. The power of I and Q and their correlation
+/// are averaged; Q is first made orthogonal to I, removing the phase error,
+/// then scaled to the power of I, removing the gain error. The input should
+/// be free of DC.
+pub struct IqCorrection {
+    alpha: f32,
+    /// averages of I*I, Q*Q and I*Q
+    ii: f32,
+    qq: f32,
+    iq: f32,
+    /// estimated imbalance, as last published
+    published: (String, String),
+    status: NodeStatus,
+}
+
+impl IqCorrection {
+    pub fn new(alpha: f64, status: NodeStatus) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("IqCorrection").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<Complex32>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            IqCorrection {
+                alpha: alpha as f32,
+                ii: 1.0,
+                qq: 1.0,
+                iq: 0.0,
+                published: (String::new(), String::new()),
+                status,
+            },
+        )
+    }
+
+    /// The gain error in dB and the phase error in degrees, as shown on the
+    /// node.
+    fn imbalance(&self) -> (String, String) {
+        let ii = self.ii.max(f32::MIN_POSITIVE);
+        let qq = self.qq.max(f32::MIN_POSITIVE);
+        let gain = 10.0 * (qq / ii).log10();
+        let phase = (self.iq / (ii * qq).sqrt())
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees();
+        (format!("{:.2} dB", gain), format!("{:.2}°", phase))
+    }
+}
+
+#[async_trait]
+impl Kernel for IqCorrection {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<Complex32>();
+
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            self.ii += (x.re * x.re - self.ii) * self.alpha;
+            self.qq += (x.im * x.im - self.qq) * self.alpha;
+            self.iq += (x.re * x.im - self.iq) * self.alpha;
+
+            let ii = self.ii.max(f32::MIN_POSITIVE);
+            let phase = self.iq / ii;
+            let orthogonal = x.im - phase * x.re;
+            let orthogonal_power = (self.qq - self.iq * phase).max(f32::MIN_POSITIVE);
+            let gain = (ii / orthogonal_power).sqrt();
+            *y = Complex32::new(x.re, orthogonal * gain);
+        }
+
+        let imbalance = self.imbalance();
+        if imbalance != self.published {
+            self.status.publish("Gain error", &imbalance.0);
+            self.status.publish("Phase error", &imbalance.1);
+            self.published = imbalance;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::bail;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Message handlers are plain functions, so there is one per input, up to
+/// this many inputs.
+pub const MAX_INPUTS: usize = 8;
+
+/// Sums several audio streams, each with its own gain. Inputs which ended
+/// are left out, until all of them did.
+pub struct Mixer {
+    gains: Vec<f32>,
+    timer: WorkTimer,
+}
+
+impl Mixer {
+    /// `inputs` are the names of the input streams and of the message input
+    /// setting their gain, along with the initial gain.
+    pub fn new(inputs: Vec<(String, String, f64)>, timer: WorkTimer) -> Result<Block> {
+        if inputs.is_empty() || inputs.len() > MAX_INPUTS {
+            bail!("The mixer takes 1 to {} inputs", MAX_INPUTS);
+        }
+        let mut stream_io = StreamIoBuilder::new();
+        let mut message_io = MessageIoBuilder::new();
+        for (index, (input, gain, _)) in inputs.iter().enumerate() {
+            stream_io = stream_io.add_input(input, std::mem::size_of::<f32>());
+            message_io = Mixer::add_gain_input(message_io, index, gain);
+        }
+        Ok(Block::new(
+            BlockMetaBuilder::new("Mixer").build(),
+            stream_io
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            message_io.build(),
+            Mixer {
+                timer,
+                gains: inputs.iter().map(|(_, _, gain)| *gain as f32).collect(),
+            },
+        ))
+    }
+
+    fn add_gain_input(
+        builder: MessageIoBuilder<Mixer>,
+        index: usize,
+        name: &str,
+    ) -> MessageIoBuilder<Mixer> {
+        match index {
+            0 => builder.add_input(name, Mixer::gain_handler::<0>),
+            1 => builder.add_input(name, Mixer::gain_handler::<1>),
+            2 => builder.add_input(name, Mixer::gain_handler::<2>),
+            3 => builder.add_input(name, Mixer::gain_handler::<3>),
+            4 => builder.add_input(name, Mixer::gain_handler::<4>),
+            5 => builder.add_input(name, Mixer::gain_handler::<5>),
+            6 => builder.add_input(name, Mixer::gain_handler::<6>),
+            _ => builder.add_input(name, Mixer::gain_handler::<7>),
+        }
+    }
+
+    fn gain_handler<'a, const INPUT: usize>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Mixer>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(gain) = p {
+                self.gains[INPUT] = gain as f32;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for Mixer {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        // checked first, so that the samples of an input which ended are all
+        // in its slice
+        let finished: Vec<bool> = (0..self.gains.len())
+            .map(|input| sio.input(input).finished())
+            .collect();
+        let inputs: Vec<&[f32]> = (0..self.gains.len())
+            .map(|input| sio.input(input).slice::<f32>())
+            .collect();
+        let o = sio.output(0).slice::<f32>();
+        // inputs which ended are silent past their last samples, unless they
+        // all did
+        let live = inputs
+            .iter()
+            .zip(&finished)
+            .filter(|(_, finished)| !**finished)
+            .map(|(i, _)| i.len())
+            .min();
+        let available = match live {
+            Some(len) => len,
+            None => inputs.iter().map(|i| i.len()).max().unwrap_or(0),
+        };
+        let n = cmp::min(available, o.len());
+
+        o[..n].fill(0.0);
+        for (i, gain) in inputs.iter().zip(&self.gains) {
+            for (y, x) in o[..n].iter_mut().zip(i.iter()) {
+                *y += x * gain;
+            }
+        }
+
+        let mut done = true;
+        for (input, i) in inputs.iter().enumerate() {
+            let consumed = cmp::min(n, i.len());
+            done &= finished[input] && consumed == i.len();
+            sio.input(input).consume(consumed);
+        }
+        sio.output(0).produce(n);
+        if done {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+pub mod agc;
+pub mod am;
+pub mod arbitrary_resampler;
+pub mod channelizer;
+pub mod combine;
+pub mod dc_blocker;
+pub mod delay;
+pub mod fir;
+pub mod frequency_translator;
+pub mod head;
+pub mod iq_correction;
+pub mod mixer;
+pub mod multiply_const;
+pub mod nbfm;
+pub mod noise_reduction;
+pub mod null;
+pub mod power_squelch;
+pub mod probe;
+pub mod rds;
+pub mod skip;
+pub mod soapy_sink;
+pub mod soapy_source;
+pub mod split;
+pub mod ssb;
+pub mod stereo_audio_sink;
+pub mod throttle;
+pub mod tone_filter;
+pub mod volume;
+pub mod wbfm;
+pub mod xlating_fir;
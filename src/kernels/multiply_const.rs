@@ -0,0 +1,127 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+pub trait Scale: Copy + Send + 'static {
+    fn scale(self, constant: Complex32) -> Self;
+}
+
+/// Real streams are only scaled by the real part of the constant.
+impl Scale for f32 {
+    fn scale(self, constant: Complex32) -> f32 {
+        self * constant.re
+    }
+}
+
+impl Scale for Complex32 {
+    fn scale(self, constant: Complex32) -> Complex32 {
+        self * constant
+    }
+}
+
+/// Multiplies a stream by a constant, which can be changed while running.
+pub struct MultiplyConst<T> {
+    constant: Complex32,
+    _type: std::marker::PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> MultiplyConst<T>
+where
+    T: Scale,
+{
+    pub fn new(constant: Complex32, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("MultiplyConst").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("constant", MultiplyConst::<T>::constant_handler)
+                .add_input("constant imag", MultiplyConst::<T>::constant_imag_handler)
+                .build(),
+            MultiplyConst::<T> {
+                timer,
+                constant,
+                _type: std::marker::PhantomData,
+            },
+        )
+    }
+
+    fn constant_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<MultiplyConst<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(re) = p {
+                self.constant.re = re as f32;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn constant_imag_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<MultiplyConst<T>>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(im) = p {
+                self.constant.im = im as f32;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for MultiplyConst<T>
+where
+    T: Scale,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            *y = x.scale(self.constant);
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
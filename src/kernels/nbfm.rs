@@ -0,0 +1,341 @@
+use crate::dsp::biquad;
+use crate::dsp::biquad::Biquad;
+use crate::dsp::fm::Discriminator;
+use crate::dsp::tones::Tone;
+use crate::dsp::tones::ToneDetector;
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// lowest frequency of the noise measured by the squelch, above the voice
+const NOISE_FREQ: f64 = 3_500.0;
+/// time constant of the noise level, in seconds
+const NOISE_SMOOTHING: f64 = 0.01;
+/// how much quieter the noise must get below the threshold to open the
+/// squelch than it must get above it to close it, in dB
+const HYSTERESIS: f64 = 3.0;
+/// the sub-audio tones are removed below this frequency
+const TONE_FILTER_FREQ: f64 = 300.0;
+
+/// Narrowband FM receiver for two-way radio. The audio is scaled so that
+/// the given deviation gives a level of `gain`, and is muted while the noise
+/// above the voice band is over the squelch threshold, or while the expected
+/// CTCSS tone or DCS code is not received.
+pub struct NbfmReceiver {
+    discriminator: Discriminator,
+    sample_rate: f64,
+    gain: f32,
+    noise_filter: Biquad,
+    noise_alpha: f32,
+    noise_power: f32,
+    /// squelch threshold, in dB relative to the deviation
+    squelch: f64,
+    open: bool,
+    tone: Option<Tone>,
+    tone_filter: bool,
+    tone_highpass: [Biquad; 2],
+    detector: ToneDetector,
+    detected: Option<Tone>,
+    status: NodeStatus,
+}
+
+impl NbfmReceiver {
+    pub fn new(
+        sample_rate: f64,
+        deviation: f64,
+        gain: f64,
+        squelch: f64,
+        tone: Option<Tone>,
+        tone_filter: bool,
+        status: NodeStatus,
+    ) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("NbfmReceiver").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("deviation", NbfmReceiver::deviation_handler)
+                .add_input("gain", NbfmReceiver::gain_handler)
+                .add_input("squelch", NbfmReceiver::squelch_handler)
+                .add_input("tone", NbfmReceiver::tone_handler)
+                .add_input("tone filter", NbfmReceiver::tone_filter_handler)
+                .build(),
+            NbfmReceiver::with_settings(
+                sample_rate,
+                deviation,
+                gain,
+                squelch,
+                tone,
+                tone_filter,
+                status,
+            ),
+        )
+    }
+
+    fn with_settings(
+        sample_rate: f64,
+        deviation: f64,
+        gain: f64,
+        squelch: f64,
+        tone: Option<Tone>,
+        tone_filter: bool,
+        status: NodeStatus,
+    ) -> NbfmReceiver {
+        let noise_freq = (0.3 * sample_rate).max(NOISE_FREQ).min(0.45 * sample_rate);
+        status.publish("Squelch", "closed");
+        status.publish("Tone", "none");
+        NbfmReceiver {
+            discriminator: Discriminator::new(deviation, sample_rate),
+            sample_rate,
+            gain: gain as f32,
+            noise_filter: Biquad::highpass(
+                noise_freq,
+                std::f64::consts::FRAC_1_SQRT_2,
+                sample_rate,
+            ),
+            noise_alpha: (1.0 - (-1.0 / (NOISE_SMOOTHING * sample_rate)).exp()) as f32,
+            noise_power: 1.0,
+            squelch,
+            open: false,
+            tone,
+            tone_filter,
+            tone_highpass: [0, 1].map(|section| {
+                Biquad::highpass(
+                    TONE_FILTER_FREQ,
+                    biquad::BUTTERWORTH_Q[section],
+                    sample_rate,
+                )
+            }),
+            detector: ToneDetector::new(sample_rate),
+            detected: None,
+            status,
+        }
+    }
+
+    fn deviation_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(deviation) = p {
+                if deviation > 0.0 {
+                    self.discriminator
+                        .set_deviation(deviation, self.sample_rate);
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn gain_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(gain) = p {
+                self.gain = gain as f32;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn squelch_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(squelch) = p {
+                self.squelch = squelch;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn tone_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(tone) = p {
+                match Tone::parse(&tone) {
+                    Ok(tone) => self.tone = tone,
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn tone_filter_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(tone_filter) = p {
+                self.tone_filter = tone_filter;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    /// Updates the squelch with the noise of the next sample, and returns
+    /// whether it is open.
+    fn squelch(&mut self, sample: f32) -> bool {
+        let noise = self.noise_filter.filter(sample);
+        self.noise_power += (noise * noise - self.noise_power) * self.noise_alpha;
+        let level = 10.0 * (self.noise_power.max(f32::MIN_POSITIVE) as f64).log10();
+        if self.open && level > self.squelch {
+            self.open = false;
+        } else if !self.open && level < self.squelch - HYSTERESIS {
+            self.open = true;
+        }
+        self.open
+    }
+}
+
+#[async_trait]
+impl Kernel for NbfmReceiver {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<f32>();
+
+        let was_open = self.open;
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            let sample = self.discriminator.demodulate(*x);
+            let open = self.squelch(sample);
+            self.detector.push(sample);
+            let audio = if self.tone_filter {
+                self.tone_highpass
+                    .iter_mut()
+                    .fold(sample, |x, section| section.filter(x))
+            } else {
+                sample
+            };
+            let tone_ok = match self.tone {
+                Some(tone) => self.detector.receives(tone),
+                None => true,
+            };
+            *y = if open && tone_ok {
+                audio * self.gain
+            } else {
+                0.0
+            };
+        }
+
+        if self.open != was_open {
+            self.status
+                .publish("Squelch", if self.open { "open" } else { "closed" });
+        }
+        let detected = self.detector.detected();
+        if detected != self.detected {
+            self.detected = detected;
+            let tone = detected.map_or_else(|| "none".to_string(), |tone| tone.to_string());
+            self.status.publish("Tone", &tone);
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio::TxArm;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    fn receiver(squelch: f64) -> NbfmReceiver {
+        NbfmReceiver::with_settings(
+            SAMPLE_RATE,
+            5000.0,
+            1.0,
+            squelch,
+            None,
+            false,
+            NodeStatus::detached(TxArm::default()),
+        )
+    }
+
+    /// Deterministic white noise of the given amplitude.
+    fn noise(amplitude: f32) -> impl FnMut() -> f32 {
+        let mut state: u32 = 12345;
+        move || {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            amplitude * ((state >> 8) as f32 / (1 << 23) as f32 - 1.0)
+        }
+    }
+
+    /// Feeds a second of the signal to the squelch, and returns whether it
+    /// stayed open or closed all along the last half.
+    fn settle(receiver: &mut NbfmReceiver, mut signal: impl FnMut() -> f32) -> bool {
+        let n = SAMPLE_RATE as usize;
+        let states: Vec<bool> = (0..n).map(|_| receiver.squelch(signal())).collect();
+        let last = states[n - 1];
+        assert!(states[n / 2..].iter().all(|open| *open == last));
+        last
+    }
+
+    /// Level of the noise measured by the squelch, in dB.
+    fn level(amplitude: f32) -> f64 {
+        let mut receiver = receiver(0.0);
+        settle(&mut receiver, noise(amplitude));
+        10.0 * (receiver.noise_power as f64).log10()
+    }
+
+    #[test]
+    fn squelch_has_hysteresis() {
+        // a noise level between the thresholds to open and to close
+        let amplitude = 0.1;
+        let mut receiver = receiver(level(amplitude) + HYSTERESIS / 2.0);
+        assert!(!settle(&mut receiver, noise(amplitude)));
+        // opens on a clear signal, and stays open at the same noise level
+        assert!(settle(&mut receiver, || 0.0));
+        assert!(settle(&mut receiver, noise(amplitude)));
+        // closes on more noise, and stays closed at the same noise level
+        assert!(!settle(&mut receiver, noise(1.0)));
+        assert!(!settle(&mut receiver, noise(amplitude)));
+    }
+}
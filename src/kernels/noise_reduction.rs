@@ -0,0 +1,188 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+use rustfft::num_complex::Complex32;
+use rustfft::Fft;
+use rustfft::FftPlanner;
+
+const FRAME: usize = 512;
+/// frames overlap by half, which the square root of a Hann window on both
+/// analysis and synthesis adds back up to 1 for
+const HOP: usize = FRAME / 2;
+/// how fast the noise estimate of a bin follows its power, when the power is
+/// below and above the estimate. Speech keeps the power of a bin up for much
+/// shorter than it takes the estimate to rise.
+const NOISE_FALL: f32 = 0.1;
+const NOISE_RISE: f32 = 0.002;
+
+/// Spectral subtraction noise reduction. The audio is cut into overlapping
+/// frames, the noise power of each frequency bin is estimated by following
+/// its lows, and each bin is attenuated by how much of its power is noise, but
+/// never below the floor.
+pub struct NoiseReduction {
+    strength: f32,
+    floor: f32,
+    window: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    noise: Vec<f32>,
+    /// the input not yet processed, starting with the overlap of the last frame
+    input: Vec<f32>,
+    /// frames being added up, the first `HOP` samples are complete after each
+    /// frame
+    overlap: Vec<f32>,
+    output: VecDeque<f32>,
+    timer: WorkTimer,
+}
+
+impl NoiseReduction {
+    /// `floor` is the lowest gain of a bin, in dB.
+    pub fn new(strength: f64, floor: f64, timer: WorkTimer) -> Block {
+        let mut planner = FftPlanner::new();
+        Block::new(
+            BlockMetaBuilder::new("NoiseReduction").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<f32>())
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("strength", NoiseReduction::strength_handler)
+                .add_input("floor", NoiseReduction::floor_handler)
+                .build(),
+            NoiseReduction {
+                timer,
+                strength: strength as f32,
+                floor: NoiseReduction::floor_gain(floor),
+                window: (0..FRAME)
+                    .map(|n| (PI * n as f32 / FRAME as f32).sin())
+                    .collect(),
+                forward: planner.plan_fft_forward(FRAME),
+                inverse: planner.plan_fft_inverse(FRAME),
+                noise: vec![0.0; FRAME],
+                input: vec![0.0; FRAME - HOP],
+                overlap: vec![0.0; FRAME],
+                output: VecDeque::new(),
+            },
+        )
+    }
+
+    fn floor_gain(floor: f64) -> f32 {
+        10f64.powf(floor.min(0.0) / 20.0) as f32
+    }
+
+    fn strength_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NoiseReduction>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(strength) = p {
+                self.strength = strength.max(0.0) as f32;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn floor_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<NoiseReduction>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(floor) = p {
+                self.floor = NoiseReduction::floor_gain(floor);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    /// Processes the frame at the start of the input, and moves on by a hop.
+    fn process_frame(&mut self) {
+        let mut spectrum: Vec<Complex32> = self.input[..FRAME]
+            .iter()
+            .zip(&self.window)
+            .map(|(x, w)| Complex32::new(x * w, 0.0))
+            .collect();
+        self.forward.process(&mut spectrum);
+
+        for (bin, noise) in spectrum.iter_mut().zip(self.noise.iter_mut()) {
+            let power = bin.norm_sqr();
+            let rate = if power < *noise {
+                NOISE_FALL
+            } else {
+                NOISE_RISE
+            };
+            *noise += (power - *noise) * rate;
+            let gain = if power > 0.0 {
+                (1.0 - self.strength * *noise / power).max(0.0).sqrt()
+            } else {
+                0.0
+            };
+            *bin *= gain.max(self.floor);
+        }
+
+        self.inverse.process(&mut spectrum);
+        for ((sum, y), w) in self.overlap.iter_mut().zip(&spectrum).zip(&self.window) {
+            *sum += y.re * w / FRAME as f32;
+        }
+        self.output.extend(self.overlap.drain(..HOP));
+        self.overlap.resize(FRAME, 0.0);
+        self.input.drain(..HOP);
+    }
+}
+
+#[async_trait]
+impl Kernel for NoiseReduction {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<f32>();
+        let o = sio.output(0).slice::<f32>();
+
+        // every input sample eventually gives one output sample, so taking no
+        // more than there is room for keeps the output queue short
+        let n = cmp::min(i.len(), o.len());
+        self.input.extend_from_slice(&i[..n]);
+        while self.input.len() >= FRAME {
+            self.process_frame();
+        }
+        let produced = cmp::min(o.len(), self.output.len());
+        for (y, x) in o[..produced].iter_mut().zip(self.output.drain(..produced)) {
+            *y = x;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(produced);
+
+        if sio.input(0).finished() && n == i.len() && self.output.is_empty() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
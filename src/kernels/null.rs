@@ -0,0 +1,108 @@
+use crate::stats::WorkTimer;
+
+use std::marker::PhantomData;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Outputs zeros, as fast as they are consumed.
+pub struct NullSource<T> {
+    _type: PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> NullSource<T>
+where
+    T: Copy + Default + Send + 'static,
+{
+    pub fn new(timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("NullSource").build(),
+            StreamIoBuilder::new()
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            NullSource::<T> {
+                timer,
+                _type: PhantomData,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for NullSource<T>
+where
+    T: Copy + Default + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        _io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let o = sio.output(0).slice::<T>();
+        o.fill(T::default());
+        let n = o.len();
+        sio.output(0).produce(n);
+        Ok(())
+    }
+}
+
+/// Consumes a stream and discards it.
+pub struct NullSink<T> {
+    _type: PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> NullSink<T>
+where
+    T: Copy + Send + 'static,
+{
+    pub fn new(timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("NullSink").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            NullSink::<T> {
+                timer,
+                _type: PhantomData,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for NullSink<T>
+where
+    T: Copy + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let n = sio.input(0).slice::<T>().len();
+        sio.input(0).consume(n);
+        if sio.input(0).finished() {
+            io.finished = true;
+        }
+        Ok(())
+    }
+}
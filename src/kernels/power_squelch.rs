@@ -0,0 +1,179 @@
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::anyhow;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+pub const SQUELCH_MODES: [&str; 2] = ["zero", "drop"];
+
+/// Closes when the average power of a complex stream falls below a threshold,
+/// and opens again once it is `hysteresis` dB above it. While closed, samples
+/// are either replaced by zeros or dropped. Changes of state are posted as
+/// booleans on the "squelch" message output.
+pub struct PowerSquelch {
+    alpha: f32,
+    power: f32,
+    /// threshold and hysteresis, in dB
+    threshold: f64,
+    hysteresis: f64,
+    drop: bool,
+    open: bool,
+    status: NodeStatus,
+}
+
+impl PowerSquelch {
+    pub fn new(
+        alpha: f64,
+        threshold: f64,
+        hysteresis: f64,
+        mode: &str,
+        status: NodeStatus,
+    ) -> Result<Block> {
+        status.publish("Squelch", "closed");
+        Ok(Block::new(
+            BlockMetaBuilder::new("PowerSquelch").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<Complex32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("threshold", PowerSquelch::threshold_handler)
+                .add_input("hysteresis", PowerSquelch::hysteresis_handler)
+                .add_input("mode", PowerSquelch::mode_handler)
+                .add_output("squelch")
+                .build(),
+            PowerSquelch {
+                alpha: alpha as f32,
+                power: 0.0,
+                threshold,
+                hysteresis,
+                drop: PowerSquelch::parse_mode(mode)?,
+                open: false,
+                status,
+            },
+        ))
+    }
+
+    /// Whether samples are dropped when closed.
+    fn parse_mode(name: &str) -> Result<bool> {
+        match name {
+            "zero" => Ok(false),
+            "drop" => Ok(true),
+            _ => Err(anyhow!("Unknown squelch mode {}", name)),
+        }
+    }
+
+    fn threshold_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<PowerSquelch>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(threshold) = p {
+                self.threshold = threshold;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn hysteresis_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<PowerSquelch>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(hysteresis) = p {
+                self.hysteresis = hysteresis.max(0.0);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn mode_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<PowerSquelch>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(name) = p {
+                match PowerSquelch::parse_mode(&name) {
+                    Ok(drop) => self.drop = drop,
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for PowerSquelch {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<Complex32>();
+
+        let n = cmp::min(i.len(), o.len());
+        let mut produced = 0;
+        let mut changes = vec![];
+        for x in &i[..n] {
+            self.power += (x.norm_sqr() - self.power) * self.alpha;
+            let level = 10.0 * (self.power.max(f32::MIN_POSITIVE) as f64).log10();
+            if self.open && level < self.threshold {
+                self.open = false;
+                changes.push(false);
+            } else if !self.open && level > self.threshold + self.hysteresis {
+                self.open = true;
+                changes.push(true);
+            }
+            if self.open {
+                o[produced] = *x;
+                produced += 1;
+            } else if !self.drop {
+                o[produced] = Complex32::default();
+                produced += 1;
+            }
+        }
+
+        if let Some(open) = changes.last() {
+            self.status
+                .publish("Squelch", if *open { "open" } else { "closed" });
+        }
+        for open in changes {
+            mio.post(0, Pmt::Bool(open)).await;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(produced);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
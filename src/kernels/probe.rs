@@ -0,0 +1,99 @@
+use crate::stats::EdgeCounters;
+
+use std::cmp;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Pass-through block that is inserted on every edge of the flowgraph to
+/// count the items going through it and to keep track of how full the buffer
+/// towards the downstream block is.
+pub struct Probe {
+    item_size: usize,
+    capacity: usize,
+    was_empty: bool,
+    was_full: bool,
+    counters: Arc<EdgeCounters>,
+}
+
+impl Probe {
+    pub fn new(item_size: usize, counters: Arc<EdgeCounters>) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Probe").build(),
+            StreamIoBuilder::new()
+                .add_input("in", item_size)
+                .add_output("out", item_size)
+                .build(),
+            MessageIoBuilder::<Probe>::new().build(),
+            Probe {
+                item_size,
+                capacity: 0,
+                was_empty: false,
+                was_full: false,
+                counters,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl Kernel for Probe {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let i = sio.input(0).slice::<u8>();
+        let o = sio.output(0).slice::<u8>();
+
+        // the free space on the output buffer is at its largest when the
+        // downstream block has consumed everything
+        self.capacity = cmp::max(self.capacity, o.len());
+        let queued = (self.capacity - o.len()) / self.item_size;
+
+        let n = cmp::min(i.len(), o.len()) / self.item_size;
+        let bytes = n * self.item_size;
+        o[..bytes].copy_from_slice(&i[..bytes]);
+
+        let is_empty = queued == 0 && n == 0;
+        let is_full = o.len() < self.item_size;
+        if is_empty && !self.was_empty {
+            self.counters.underflows.fetch_add(1, Ordering::Relaxed);
+        }
+        if is_full && !self.was_full {
+            self.counters.overflows.fetch_add(1, Ordering::Relaxed);
+        }
+        self.was_empty = is_empty;
+        self.was_full = is_full;
+
+        self.counters.items.fetch_add(n as u64, Ordering::Relaxed);
+        self.counters
+            .queued
+            .store((queued + n) as u64, Ordering::Relaxed);
+        self.counters
+            .capacity
+            .store((self.capacity / self.item_size) as u64, Ordering::Relaxed);
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n * self.item_size == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use crate::dsp::nco::Nco;
+use crate::dsp::rds::RdsDecoder;
+use crate::radio::NodeStatus;
+
+use std::f32::consts::PI;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+const SUBCARRIER: f64 = 57_000.0;
+/// biphase symbols per second, two per bit
+const SYMBOL_RATE: f64 = 2375.0;
+const COSTAS_BANDWIDTH: f64 = 20.0;
+const TIMING_GAIN: f32 = 0.05;
+/// how fast the biphase alignment metric follows the symbols
+const ALIGNMENT_SMOOTHING: f32 = 0.02;
+/// how fast the level used to normalize the subcarrier follows it
+const LEVEL_SMOOTHING: f32 = 0.001;
+
+/// Second order Costas loop for BPSK, bringing the signal onto the real axis.
+struct Costas {
+    phase: f32,
+    freq: f32,
+    alpha: f32,
+    beta: f32,
+}
+
+impl Costas {
+    fn new(bandwidth: f64, sample_rate: f64) -> Costas {
+        let omega = (2.0 * std::f64::consts::PI * bandwidth / sample_rate) as f32;
+        Costas {
+            phase: 0.0,
+            freq: 0.0,
+            alpha: 2.0 * std::f32::consts::FRAC_1_SQRT_2 * omega,
+            beta: omega * omega,
+        }
+    }
+
+    fn track(&mut self, sample: Complex32) -> Complex32 {
+        let out = sample * Complex32::from_polar(1.0, -self.phase);
+        let error = out.re.signum() * out.im / (out.norm() + 1e-9);
+        self.freq += self.beta * error;
+        self.phase = (self.phase + self.freq + self.alpha * error) % (2.0 * PI);
+        out
+    }
+}
+
+/// Gardner symbol timing recovery, on the real part of the signal.
+struct Gardner {
+    /// samples per half symbol
+    half_symbol: f32,
+    /// samples until the next strobe
+    countdown: f32,
+    previous: f32,
+    mid: f32,
+    last_symbol: f32,
+    at_symbol: bool,
+}
+
+impl Gardner {
+    fn new(samples_per_symbol: f64) -> Gardner {
+        let half_symbol = samples_per_symbol as f32 / 2.0;
+        Gardner {
+            half_symbol,
+            countdown: half_symbol,
+            previous: 0.0,
+            mid: 0.0,
+            last_symbol: 0.0,
+            at_symbol: false,
+        }
+    }
+
+    /// Takes the next sample, and returns a symbol when one is due.
+    fn push(&mut self, sample: f32) -> Option<f32> {
+        let previous = std::mem::replace(&mut self.previous, sample);
+        self.countdown -= 1.0;
+        if self.countdown > 0.0 {
+            return None;
+        }
+        // interpolate back to where the strobe was due
+        let strobe = sample + (previous - sample) * -self.countdown;
+        self.at_symbol = !self.at_symbol;
+        if !self.at_symbol {
+            self.mid = strobe;
+            self.countdown += self.half_symbol;
+            return None;
+        }
+        let error = self.mid * (self.last_symbol - strobe);
+        let error = error.clamp(-1.0, 1.0) * TIMING_GAIN * self.half_symbol;
+        self.countdown += self.half_symbol + error;
+        self.last_symbol = strobe;
+        Some(strobe)
+    }
+}
+
+/// Extracts the RDS data from a demodulated FM multiplex. The 57 kHz
+/// subcarrier is brought down to DC, filtered and decimated, then carrier and
+/// symbol timing are recovered, and the biphase and differential coding are
+/// undone to get the bits of the groups.
+pub struct RdsReceiver {
+    nco: Nco,
+    taps: Vec<f32>,
+    decimation: usize,
+    /// mixed samples, the last `taps.len() - 1` ones are kept between calls
+    history: Vec<Complex32>,
+    skip: usize,
+    level: f32,
+    costas: Costas,
+    gardner: Gardner,
+    last_symbol: f32,
+    /// how well symbols pair into bits, for both possible alignments
+    alignment: [f32; 2],
+    symbol_count: usize,
+    last_bit: bool,
+    decoder: RdsDecoder,
+    status: NodeStatus,
+}
+
+impl RdsReceiver {
+    pub fn new(sample_rate: f64, taps: Vec<f32>, decimation: usize, status: NodeStatus) -> Block {
+        let rate = sample_rate / decimation as f64;
+        Block::new(
+            BlockMetaBuilder::new("RdsReceiver").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new().add_output("info").build(),
+            RdsReceiver {
+                nco: Nco::new(-SUBCARRIER, sample_rate),
+                history: vec![Complex32::default(); taps.len() - 1],
+                taps,
+                decimation,
+                skip: 0,
+                level: 0.0,
+                costas: Costas::new(COSTAS_BANDWIDTH, rate),
+                gardner: Gardner::new(rate / SYMBOL_RATE),
+                last_symbol: 0.0,
+                alignment: [0.0; 2],
+                symbol_count: 0,
+                last_bit: false,
+                decoder: RdsDecoder::default(),
+                status,
+            },
+        )
+    }
+
+    /// Pairs biphase symbols into bits, and undoes the differential coding.
+    fn push_symbol(&mut self, symbol: f32) -> Option<bool> {
+        let difference = self.last_symbol - symbol;
+        self.last_symbol = symbol;
+        let parity = self.symbol_count % 2;
+        self.symbol_count += 1;
+        self.alignment[parity] += (difference.abs() - self.alignment[parity]) * ALIGNMENT_SMOOTHING;
+        if self.alignment[parity] < self.alignment[1 - parity] {
+            return None;
+        }
+        let bit = difference > 0.0;
+        let decoded = bit != self.last_bit;
+        self.last_bit = bit;
+        Some(decoded)
+    }
+}
+
+#[async_trait]
+impl Kernel for RdsReceiver {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<f32>();
+        let n = i.len();
+        self.history.extend(i.iter().map(|x| self.nco.next() * *x));
+
+        let len = self.taps.len();
+        let mut next = len - 1 + self.skip;
+        let mut changes = vec![];
+        while next < self.history.len() {
+            let sample: Complex32 = self.history[next + 1 - len..=next]
+                .iter()
+                .rev()
+                .zip(&self.taps)
+                .map(|(x, tap)| x * tap)
+                .sum();
+            next += self.decimation;
+            // the timing recovery expects symbols of about 1
+            self.level += (sample.norm() - self.level) * LEVEL_SMOOTHING;
+            let sample = self.costas.track(sample / self.level.max(f32::EPSILON));
+            let bit = match self.gardner.push(sample.re) {
+                Some(symbol) => self.push_symbol(symbol),
+                None => None,
+            };
+            if let Some(bit) = bit {
+                changes.extend(self.decoder.push_bit(bit));
+            }
+        }
+        self.skip = next - self.history.len();
+        self.history.drain(..self.history.len() - (len - 1));
+
+        for (name, value) in changes {
+            self.status.publish(name, &value);
+            mio.post(0, Pmt::String(format!("{}: {}", name, value)))
+                .await;
+        }
+
+        sio.input(0).consume(n);
+        if sio.input(0).finished() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::marker::PhantomData;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Drops the first samples of a stream, then passes the rest.
+pub struct Skip<T> {
+    /// samples left to drop
+    left: usize,
+    _type: PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> Skip<T>
+where
+    T: Copy + Send + 'static,
+{
+    pub fn new(count: usize, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Skip").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Skip::<T> {
+                timer,
+                left: count,
+                _type: PhantomData,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for Skip<T>
+where
+    T: Copy + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let skipped = cmp::min(i.len(), self.left);
+        self.left -= skipped;
+        let n = cmp::min(i.len() - skipped, o.len());
+        o[..n].copy_from_slice(&i[skipped..skipped + n]);
+
+        sio.input(0).consume(skipped + n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && skipped + n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
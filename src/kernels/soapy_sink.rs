@@ -0,0 +1,455 @@
+use crate::radio::NodeStatus;
+use crate::radio::TxArm;
+use crate::radio::TX_ARM_INPUT;
+use crate::soapy::ChannelConfig;
+use crate::soapy::Direction;
+use crate::soapy::SoapyDevice;
+use crate::soapy::SoapyDriver;
+use crate::soapy::SoapyTxStream;
+use crate::soapy::StreamError;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_io::Timer;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+const CHANNEL: usize = 0;
+/// how long to wait at most before dropping more samples while disarmed
+const MAX_WAIT: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Debug)]
+pub struct SoapySinkConfig {
+    pub args: String,
+    pub channel_config: ChannelConfig,
+}
+
+struct Connection {
+    device: Box<dyn SoapyDevice>,
+    stream: Box<dyn SoapyTxStream>,
+}
+
+/// Sink transmitting through a SoapySDR device. The stream is only active
+/// while TX is armed, samples received in the meantime are dropped at the
+/// sample rate, as if they had been transmitted.
+pub struct SoapySink {
+    driver: Arc<dyn SoapyDriver>,
+    config: SoapySinkConfig,
+    status: NodeStatus,
+    tx_arm: TxArm,
+    connection: Option<Connection>,
+    transmitting: bool,
+    /// when samples started being dropped, and how many were since
+    dropping: Option<(Instant, u64)>,
+}
+
+impl SoapySink {
+    pub fn new(driver: Arc<dyn SoapyDriver>, config: SoapySinkConfig, status: NodeStatus) -> Block {
+        let tx_arm = status.tx_arm();
+        {
+            let driver = driver.clone();
+            let config = config.clone();
+            status.check_before_start(move || SoapySink::check(driver.as_ref(), &config));
+        }
+        Block::new(
+            BlockMetaBuilder::new("SoapySink").blocking().build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("freq", SoapySink::freq_handler)
+                .add_input("gain", SoapySink::gain_handler)
+                .add_input("antenna", SoapySink::antenna_handler)
+                .add_input(TX_ARM_INPUT, SoapySink::tx_arm_handler)
+                .build(),
+            SoapySink {
+                driver,
+                config,
+                status,
+                tx_arm,
+                connection: None,
+                transmitting: false,
+                dropping: None,
+            },
+        )
+    }
+
+    /// Opens the device and configures the channel.
+    fn open_device(
+        driver: &dyn SoapyDriver,
+        config: &SoapySinkConfig,
+    ) -> Result<Box<dyn SoapyDevice>> {
+        let mut device = driver.open(&config.args, Direction::Tx)?;
+        device.configure(CHANNEL, &config.channel_config)?;
+        Ok(device)
+    }
+
+    /// Opens the device once before the flowgraph starts, so that a missing
+    /// device or settings it doesn't support are reported up front.
+    fn check(driver: &dyn SoapyDriver, config: &SoapySinkConfig) -> Result<()> {
+        SoapySink::open_device(driver, config).map(|_| ())
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        let mut device = SoapySink::open_device(self.driver.as_ref(), &self.config)?;
+        let stream = device.tx_stream(CHANNEL)?;
+        Ok(Connection { device, stream })
+    }
+
+    /// Applies a change to the device if it is connected.
+    fn apply(&mut self, f: impl FnOnce(&mut dyn SoapyDevice) -> Result<()>) {
+        if let Some(connection) = &mut self.connection {
+            if let Err(e) = f(connection.device.as_mut()) {
+                self.status.warn(&e);
+            }
+        }
+    }
+
+    fn freq_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySink>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(freq) = p {
+                self.config.channel_config.freq = freq;
+                self.apply(|device| device.set_frequency(CHANNEL, freq));
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn gain_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySink>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(gain) = p {
+                self.config.channel_config.gain = gain;
+                self.apply(|device| device.set_gain(CHANNEL, gain));
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn antenna_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySink>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(antenna) = p {
+                if !antenna.is_empty() {
+                    self.apply(|device| device.set_antenna(CHANNEL, &antenna));
+                }
+                self.config.channel_config.antenna = antenna;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    /// Lets the sink follow the TX interlock right away, instead of waiting
+    /// for its next samples.
+    fn tx_arm_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySink>,
+        _meta: &'a mut BlockMeta,
+        _p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            self.follow_tx_arm();
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn start(&mut self) {
+        match self.connect() {
+            Ok(connection) => self.connection = Some(connection),
+            Err(e) => self.status.fail(&e),
+        }
+    }
+
+    /// Activates or deactivates the stream to follow the TX interlock. The
+    /// device is dropped if that fails.
+    fn follow_tx_arm(&mut self) {
+        if let Err(e) = self.try_follow_tx_arm() {
+            self.status.fail(&e);
+            self.connection = None;
+            self.transmitting = false;
+        }
+    }
+
+    fn try_follow_tx_arm(&mut self) -> Result<()> {
+        let armed = self.tx_arm.is_armed();
+        if armed == self.transmitting {
+            return Ok(());
+        }
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => return Ok(()),
+        };
+        if armed {
+            connection.stream.activate()?;
+            log::info!("Transmitting on \"{}\"", self.config.args);
+        } else {
+            connection.stream.deactivate()?;
+            log::info!("Stopped transmitting on \"{}\"", self.config.args);
+        }
+        self.transmitting = armed;
+        self.dropping = None;
+        Ok(())
+    }
+
+    /// Writes samples to the device, returning how many it took. Fails the
+    /// node and drops the device if it goes away.
+    fn write(&mut self, samples: &[Complex32]) -> usize {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => return 0,
+        };
+        match connection.stream.write(samples) {
+            Ok(n) => n,
+            Err(StreamError::Timeout) => 0,
+            Err(StreamError::Underflow) => {
+                log::warn!("Device \"{}\" underflowed", self.config.args);
+                0
+            }
+            Err(StreamError::Overflow) => {
+                log::warn!("Device \"{}\" overflowed", self.config.args);
+                0
+            }
+            Err(StreamError::Fatal(e)) => {
+                self.status.fail(&e);
+                self.connection = None;
+                self.transmitting = false;
+                0
+            }
+        }
+    }
+
+    /// How many of the given samples to drop while disarmed, so that the
+    /// flowgraph keeps the pace it would have while transmitting. Returns the
+    /// time until the next sample is due if they can't all be dropped yet.
+    fn drop_paced(&mut self, available: usize) -> (usize, Option<Duration>) {
+        let sample_rate = self.config.channel_config.sample_rate;
+        if sample_rate <= 0.0 {
+            return (available, None);
+        }
+        let (start, dropped) = self.dropping.get_or_insert_with(|| (Instant::now(), 0));
+        let due = (start.elapsed().as_secs_f64() * sample_rate) as u64;
+        let n = cmp::min(available as u64, due.saturating_sub(*dropped)) as usize;
+        *dropped += n as u64;
+        if n == available {
+            return (n, None);
+        }
+        let next = Duration::from_secs_f64((*dropped + 1) as f64 / sample_rate);
+        (n, Some(next.saturating_sub(start.elapsed())))
+    }
+}
+
+#[async_trait]
+impl Kernel for SoapySink {
+    async fn init(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.start();
+        Ok(())
+    }
+
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.follow_tx_arm();
+        if self.connection.is_none() {
+            // stop cleanly, so that the rest of the flowgraph drains
+            io.finished = true;
+            return Ok(());
+        }
+
+        let i = sio.input(0).slice::<Complex32>();
+        let (n, wait) = if !self.transmitting {
+            self.drop_paced(i.len())
+        } else if i.is_empty() {
+            (0, None)
+        } else {
+            (self.write(i), None)
+        };
+        sio.input(0).consume(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        } else if let Some(wait) = wait {
+            Timer::after(cmp::min(wait, MAX_WAIT)).await;
+            io.call_again = true;
+        } else if n < i.len() {
+            io.call_again = true;
+        }
+        Ok(())
+    }
+
+    async fn deinit(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        if let (Some(connection), true) = (&mut self.connection, self.transmitting) {
+            connection.stream.deactivate()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::Severity;
+    use crate::soapy::fake;
+    use crate::soapy::fake::FakeDriver;
+    use crate::soapy::NullDriver;
+
+    fn sink(driver: Arc<dyn SoapyDriver>, args: &str, tx_arm: &TxArm) -> SoapySink {
+        SoapySink {
+            driver,
+            config: SoapySinkConfig {
+                args: args.to_string(),
+                channel_config: ChannelConfig {
+                    sample_rate: 1e6,
+                    ..ChannelConfig::default()
+                },
+            },
+            status: NodeStatus::detached(tx_arm.clone()),
+            tx_arm: tx_arm.clone(),
+            connection: None,
+            transmitting: false,
+            dropping: None,
+        }
+    }
+
+    fn fake_sink(driver: &FakeDriver, tx_arm: &TxArm) -> SoapySink {
+        let mut sink = sink(Arc::new(driver.clone()), fake::ARGS, tx_arm);
+        sink.start();
+        sink
+    }
+
+    #[test]
+    fn missing_device_fails_the_check() {
+        let driver = FakeDriver::default();
+        let sink = sink(Arc::new(driver.clone()), fake::ARGS, &TxArm::default());
+        assert!(SoapySink::check(&driver, &sink.config).is_err());
+    }
+
+    #[test]
+    fn unsupported_settings_fail_the_check() {
+        let driver = FakeDriver::plugged();
+        let sink = sink(Arc::new(driver.clone()), fake::ARGS, &TxArm::default());
+        driver.state().max_sample_rate = Some(0.5e6);
+        assert!(SoapySink::check(&driver, &sink.config).is_err());
+        driver.state().max_sample_rate = None;
+        assert!(SoapySink::check(&driver, &sink.config).is_ok());
+    }
+
+    #[test]
+    fn only_transmits_while_armed() {
+        let driver = FakeDriver::plugged();
+        let tx_arm = TxArm::default();
+        let mut sink = fake_sink(&driver, &tx_arm);
+        sink.follow_tx_arm();
+        assert!(!driver.state().transmitting);
+
+        tx_arm.set(true);
+        sink.follow_tx_arm();
+        assert!(driver.state().transmitting);
+        assert_eq!(sink.write(&[Complex32::new(1.0, 0.0); 4]), 4);
+        assert_eq!(driver.state().transmitted.len(), 4);
+
+        tx_arm.set(false);
+        sink.follow_tx_arm();
+        assert!(!driver.state().transmitting);
+    }
+
+    #[test]
+    fn paces_dropped_samples() {
+        let driver = FakeDriver::plugged();
+        let mut sink = fake_sink(&driver, &TxArm::default());
+        // nothing is due yet right after starting to drop
+        let (n, wait) = sink.drop_paced(1_000_000);
+        assert!(n < 1_000_000);
+        assert!(wait.is_some());
+        std::thread::sleep(Duration::from_millis(20));
+        let (n, _) = sink.drop_paced(1_000_000);
+        assert!(n >= 10_000);
+        assert!(driver.state().transmitted.is_empty());
+    }
+
+    #[test]
+    fn unplugging_fails_the_node() {
+        let driver = FakeDriver::plugged();
+        let tx_arm = TxArm::default();
+        tx_arm.set(true);
+        let mut sink = fake_sink(&driver, &tx_arm);
+        sink.follow_tx_arm();
+        driver.state().plugged = false;
+        assert_eq!(sink.write(&[Complex32::default(); 4]), 0);
+        assert!(sink.connection.is_none());
+        let severity = sink.status.reported().map(|(severity, _)| severity);
+        assert_eq!(severity, Some(Severity::Error));
+    }
+
+    #[test]
+    fn null_device_writes_to_its_file() {
+        let path = std::env::temp_dir().join(format!("esdr-null-{}.cf32", std::process::id()));
+        let args = format!("{},file={}", NullDriver::ARGS, path.display());
+        let tx_arm = TxArm::default();
+        tx_arm.set(true);
+        let mut sink = sink(crate::soapy::driver(&args), &args, &tx_arm);
+        sink.start();
+        sink.follow_tx_arm();
+        assert!(sink.transmitting);
+        assert_eq!(sink.write(&[Complex32::new(1.0, -2.0); 3]), 3);
+
+        tx_arm.set(false);
+        sink.follow_tx_arm();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let expected: Vec<u8> = [1.0f32, -2.0]
+            .repeat(3)
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn null_device_does_not_receive() {
+        let driver = crate::soapy::driver(NullDriver::ARGS);
+        assert!(driver.open(NullDriver::ARGS, Direction::Rx).is_err());
+    }
+}
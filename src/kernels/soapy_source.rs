@@ -0,0 +1,708 @@
+use crate::radio::NodeStatus;
+use crate::soapy;
+use crate::soapy::ChannelConfig;
+use crate::soapy::Direction;
+use crate::soapy::SoapyDevice;
+use crate::soapy::SoapyDriver;
+use crate::soapy::SoapyRxStream;
+use crate::soapy::StreamError;
+
+use std::future;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Duration;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_io::Timer;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// the inputs setting the gain of an element are named e.g. "LNA gain"
+pub const GAIN_ELEMENT_SUFFIX: &str = " gain";
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// how long to wait for the PPS edge resetting the clocks of synchronized
+/// devices to have passed
+const SYNC_PPS_WAIT: Duration = Duration::from_millis(1100);
+/// hardware time at which synchronized devices start streaming, which is
+/// still ahead of them once the PPS edge has passed
+const SYNC_START_NS: i64 = 1_500_000_000;
+
+#[derive(Default)]
+struct SyncState {
+    members: usize,
+    waiting: usize,
+    released: bool,
+    wakers: Vec<Waker>,
+}
+
+/// Lets the sources of several devices start streaming together. All members
+/// join the group while the flowgraph is built, and wait for each other once
+/// their device is ready.
+#[derive(Default)]
+pub struct SyncGroup {
+    state: Mutex<SyncState>,
+}
+
+impl SyncGroup {
+    pub fn join(&self) {
+        self.state.lock().unwrap().members += 1;
+    }
+
+    /// Waits until every other member is ready or has left the group.
+    async fn wait(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.waiting += 1;
+            SyncGroup::release_if_ready(&mut state);
+        }
+        future::poll_fn(|cx| {
+            let mut state = self.state.lock().unwrap();
+            if state.released {
+                Poll::Ready(())
+            } else {
+                state.wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Called by members which failed to open their device, so that the
+    /// others don't wait for them forever.
+    fn leave(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.members -= 1;
+        SyncGroup::release_if_ready(&mut state);
+    }
+
+    fn release_if_ready(state: &mut SyncState) {
+        if state.waiting >= state.members {
+            state.released = true;
+            for waker in state.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SoapySourceConfig {
+    pub args: String,
+    /// channels to stream, along with the name of the output they go to
+    pub channels: Vec<(usize, String)>,
+    /// applied to all the streamed channels
+    pub channel_config: ChannelConfig,
+    /// `writeSetting` key/value pairs, applied before the channel is configured
+    pub settings: Vec<(String, String)>,
+    /// added to the requested frequency when tuning, so that the signal of
+    /// interest doesn't end up on the DC spike
+    pub freq_offset: f64,
+    pub reconnect: bool,
+    pub sync: Option<Arc<SyncGroup>>,
+}
+
+struct Connection {
+    device: Box<dyn SoapyDevice>,
+    stream: Box<dyn SoapyRxStream>,
+}
+
+/// Source reading from a SoapySDR device. Unlike the FutureSDR one, errors
+/// are reported on the node instead of taking the process down, and the
+/// device can optionally be reopened if it goes away while running.
+pub struct SoapySource {
+    driver: Arc<dyn SoapyDriver>,
+    config: SoapySourceConfig,
+    status: NodeStatus,
+    connection: Option<Connection>,
+}
+
+impl SoapySource {
+    pub fn new(
+        driver: Arc<dyn SoapyDriver>,
+        config: SoapySourceConfig,
+        status: NodeStatus,
+    ) -> Block {
+        let mut sio = StreamIoBuilder::new();
+        for (_, output) in &config.channels {
+            sio = sio.add_output(output, std::mem::size_of::<Complex32>());
+        }
+        if let Some(sync) = &config.sync {
+            sync.join();
+        }
+        {
+            let driver = driver.clone();
+            let config = config.clone();
+            status.check_before_start(move || SoapySource::check(driver.as_ref(), &config));
+        }
+        let mut mio = MessageIoBuilder::new()
+            .add_input("freq", SoapySource::freq_handler)
+            .add_input("gain", SoapySource::gain_handler)
+            .add_input("antenna", SoapySource::antenna_handler)
+            .add_input("bandwidth", SoapySource::bandwidth_handler)
+            .add_input("agc", SoapySource::agc_handler)
+            .add_input("ppm", SoapySource::ppm_handler)
+            .add_input("dc offset auto", SoapySource::dc_offset_handler)
+            .add_input("iq balance auto", SoapySource::iq_balance_handler)
+            .add_input("settings", SoapySource::settings_handler);
+        for (element, _) in &config.channel_config.gain_elements {
+            let input = format!("{}{}", element, GAIN_ELEMENT_SUFFIX);
+            let element = element.clone();
+            mio = mio.add_input(&input, move |block: &mut SoapySource, _mio, _meta, p| {
+                block.gain_element_handler(&element, p)
+            });
+        }
+        Block::new(
+            BlockMetaBuilder::new("SoapySource").blocking().build(),
+            sio.build(),
+            mio.build(),
+            SoapySource {
+                driver,
+                config,
+                status,
+                connection: None,
+            },
+        )
+    }
+
+    fn channel_indices(&self) -> Vec<usize> {
+        self.config
+            .channels
+            .iter()
+            .map(|(channel, _)| *channel)
+            .collect()
+    }
+
+    /// Opens the device and configures the streamed channels.
+    fn open_device(
+        driver: &dyn SoapyDriver,
+        config: &SoapySourceConfig,
+    ) -> Result<Box<dyn SoapyDevice>> {
+        let mut device = driver.open(&config.args, Direction::Rx)?;
+        for (key, value) in &config.settings {
+            device.write_setting(key, value)?;
+        }
+        let mut channel_config = config.channel_config.clone();
+        channel_config.freq += config.freq_offset;
+        for (channel, _) in &config.channels {
+            // fails early if the channel doesn't exist
+            device.capabilities(*channel)?;
+            device.configure(*channel, &channel_config)?;
+        }
+        Ok(device)
+    }
+
+    /// Opens the device once before the flowgraph starts, so that a missing
+    /// device or settings it doesn't support are reported up front. Devices
+    /// which reconnect may show up later, but have to support the settings.
+    fn check(driver: &dyn SoapyDriver, config: &SoapySourceConfig) -> Result<()> {
+        match driver.open(&config.args, Direction::Rx) {
+            Err(_) if config.reconnect => Ok(()),
+            Err(e) => Err(e),
+            Ok(device) => {
+                // the device has to be closed before opening it again
+                drop(device);
+                SoapySource::open_device(driver, config).map(|_| ())
+            }
+        }
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        let mut device = SoapySource::open_device(self.driver.as_ref(), &self.config)?;
+        let stream = device.rx_stream(&self.channel_indices())?;
+        Ok(Connection { device, stream })
+    }
+
+    /// Waits for the other devices of the group, then starts streaming.
+    /// Devices with a hardware clock reset it on the next PPS edge and start
+    /// at the same timestamp, which aligns them as long as they share a PPS
+    /// signal and are released within the same second. Others simply start as
+    /// close together as possible.
+    async fn synchronized_start(
+        connection: &mut Connection,
+        sync: &SyncGroup,
+        status: &NodeStatus,
+    ) -> Result<()> {
+        let hardware_time = connection.device.has_hardware_time().unwrap_or(false);
+        sync.wait().await;
+        if !hardware_time {
+            return connection.stream.activate(None);
+        }
+        if let Err(e) = connection.device.set_hardware_time_at_pps(0) {
+            status
+                .warn(&e.context("Can't align the device clocks, starting along with the others"));
+            return connection.stream.activate(None);
+        }
+        Timer::after(SYNC_PPS_WAIT).await;
+        connection.stream.activate(Some(SYNC_START_NS))
+    }
+
+    fn with_device(&mut self, f: impl FnOnce(&mut dyn SoapyDevice) -> Result<()>) {
+        if let Some(connection) = &mut self.connection {
+            if let Err(e) = f(connection.device.as_mut()) {
+                self.status.warn(&e);
+            }
+        }
+    }
+
+    /// Applies a change to all the streamed channels if the device is
+    /// connected. Otherwise the change is picked up from the config when
+    /// (re)connecting.
+    fn apply(&mut self, f: impl Fn(&mut dyn SoapyDevice, usize) -> Result<()>) {
+        let channels = self.channel_indices();
+        self.with_device(|device| channels.iter().try_for_each(|channel| f(device, *channel)));
+    }
+
+    fn freq_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(freq) = p {
+                self.config.channel_config.freq = freq;
+                let freq = freq + self.config.freq_offset;
+                self.apply(|device, channel| device.set_frequency(channel, freq));
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn gain_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(gain) = p {
+                self.config.channel_config.gain = gain;
+                let config = &self.config.channel_config;
+                if !config.agc && !config.element_gains {
+                    self.apply(|device, channel| device.set_gain(channel, gain));
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    /// Handles the gain of the given element, whose input is named after it.
+    fn gain_element_handler<'a>(
+        &'a mut self,
+        element: &str,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        let element = element.to_string();
+        Box::pin(async move {
+            if let Pmt::Double(gain) = p {
+                let config = &mut self.config.channel_config;
+                for (_, element_gain) in config
+                    .gain_elements
+                    .iter_mut()
+                    .filter(|(name, _)| *name == element)
+                {
+                    *element_gain = gain;
+                }
+                if !config.agc && config.element_gains {
+                    self.apply(|device, channel| device.set_gain_element(channel, &element, gain));
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn antenna_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(antenna) = p {
+                if !antenna.is_empty() {
+                    self.apply(|device, channel| device.set_antenna(channel, &antenna));
+                }
+                self.config.channel_config.antenna = antenna;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn bandwidth_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(bandwidth) = p {
+                self.config.channel_config.bandwidth = bandwidth;
+                if bandwidth > 0.0 {
+                    self.apply(|device, channel| device.set_bandwidth(channel, bandwidth));
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn agc_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(agc) = p {
+                self.config.channel_config.agc = agc;
+                let config = self.config.channel_config.clone();
+                self.apply(|device, channel| {
+                    device.set_agc(channel, agc)?;
+                    if !agc {
+                        // back to the manual gains
+                        device.set_manual_gain(channel, &config)?;
+                    }
+                    Ok(())
+                });
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn ppm_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(ppm) = p {
+                self.config.channel_config.ppm = ppm;
+                self.apply(|device, channel| device.set_ppm(channel, ppm));
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn dc_offset_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(automatic) = p {
+                self.config.channel_config.dc_offset_auto = automatic;
+                self.apply(|device, channel| device.set_dc_offset_mode(channel, automatic));
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn iq_balance_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(automatic) = p {
+                self.config.channel_config.iq_balance_auto = automatic;
+                self.apply(|device, channel| device.set_iq_balance_mode(channel, automatic));
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn settings_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SoapySource>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(settings) = p {
+                match soapy::parse_settings(&settings) {
+                    Ok(settings) => {
+                        self.with_device(|device| {
+                            settings
+                                .iter()
+                                .try_for_each(|(key, value)| device.write_setting(key, value))
+                        });
+                        self.config.settings = settings;
+                    }
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    /// Connects to the device and starts streaming, once the other devices of
+    /// the sync group are ready.
+    async fn start(&mut self) {
+        let result = match (self.connect(), &self.config.sync) {
+            (Ok(mut connection), Some(sync)) => {
+                SoapySource::synchronized_start(&mut connection, sync, &self.status)
+                    .await
+                    .map(|_| connection)
+            }
+            (Ok(mut connection), None) => connection.stream.activate(None).map(|_| connection),
+            (Err(e), sync) => {
+                if let Some(sync) = sync {
+                    sync.leave();
+                }
+                Err(e)
+            }
+        };
+        match result {
+            Ok(connection) => self.connection = Some(connection),
+            Err(e) if self.config.reconnect => self.status.warn(&e),
+            Err(e) => self.status.fail(&e),
+        }
+    }
+
+    /// Tries to connect to the device again, returning whether it worked.
+    /// Devices that reconnect are no longer in sync with the others.
+    fn reconnect(&mut self) -> bool {
+        let result = self.connect().and_then(|mut connection| {
+            connection.stream.activate(None)?;
+            Ok(connection)
+        });
+        match result {
+            Ok(connection) => {
+                log::info!("Connected to device \"{}\"", self.config.args);
+                self.status.clear();
+                self.connection = Some(connection);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Reads from the device, returning the number of samples read into each
+    /// buffer. If the device goes away, the connection is dropped and the
+    /// error is reported.
+    fn read(&mut self, bufs: &mut [&mut [Complex32]]) -> usize {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => return 0,
+        };
+        match connection.stream.read(bufs) {
+            Ok(n) => n,
+            Err(StreamError::Timeout | StreamError::Underflow) => 0,
+            Err(StreamError::Overflow) => {
+                log::warn!("Device \"{}\" overflowed", self.config.args);
+                0
+            }
+            Err(StreamError::Fatal(e)) => {
+                self.connection = None;
+                if self.config.reconnect {
+                    self.status
+                        .warn(&e.context("Lost the device, trying to reconnect"));
+                } else {
+                    self.status.fail(&e);
+                }
+                0
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Kernel for SoapySource {
+    async fn init(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        self.start().await;
+        Ok(())
+    }
+
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        if self.connection.is_none() {
+            if !self.config.reconnect {
+                // stop cleanly, so that the rest of the flowgraph drains
+                io.finished = true;
+                return Ok(());
+            }
+            if !self.reconnect() {
+                Timer::after(RECONNECT_INTERVAL).await;
+            }
+            io.call_again = true;
+            return Ok(());
+        }
+
+        let outputs: Vec<&mut [Complex32]> = sio
+            .outputs_mut()
+            .iter_mut()
+            .map(|output| output.slice::<Complex32>())
+            .collect();
+        // channels are read in lockstep, so only as much as fits everywhere
+        let len = outputs.iter().map(|o| o.len()).min().unwrap_or(0);
+        let mut bufs: Vec<&mut [Complex32]> = outputs.into_iter().map(|o| &mut o[..len]).collect();
+        let n = self.read(&mut bufs);
+        for output in sio.outputs_mut() {
+            output.produce(n);
+        }
+        io.call_again = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::Severity;
+    use crate::radio::TxArm;
+    use crate::soapy::fake;
+    use crate::soapy::fake::FakeDriver;
+
+    use futuresdr::async_io::block_on;
+
+    fn config(reconnect: bool) -> SoapySourceConfig {
+        SoapySourceConfig {
+            args: fake::ARGS.to_string(),
+            channels: vec![(0, "out".to_string())],
+            channel_config: ChannelConfig {
+                freq: 100e6,
+                sample_rate: 1e6,
+                ..ChannelConfig::default()
+            },
+            settings: vec![],
+            freq_offset: 0.0,
+            reconnect,
+            sync: None,
+        }
+    }
+
+    fn source(driver: &FakeDriver, reconnect: bool) -> SoapySource {
+        SoapySource {
+            driver: Arc::new(driver.clone()),
+            config: config(reconnect),
+            status: NodeStatus::detached(TxArm::default()),
+            connection: None,
+        }
+    }
+
+    fn read(source: &mut SoapySource) -> usize {
+        let mut buf = [Complex32::default(); 16];
+        source.read(&mut [&mut buf[..]])
+    }
+
+    fn severity(source: &SoapySource) -> Option<Severity> {
+        source.status.reported().map(|(severity, _)| severity)
+    }
+
+    #[test]
+    fn sync_group_waits_for_every_member() {
+        let sync = Arc::new(SyncGroup::default());
+        sync.join();
+        sync.join();
+        let other = {
+            let sync = sync.clone();
+            std::thread::spawn(move || block_on(sync.wait()))
+        };
+        block_on(sync.wait());
+        other.join().unwrap();
+    }
+
+    #[test]
+    fn sync_group_releases_once_the_others_left() {
+        let sync = SyncGroup::default();
+        sync.join();
+        sync.join();
+        sync.leave();
+        block_on(sync.wait());
+    }
+
+    #[test]
+    fn missing_device_fails_the_check() {
+        let driver = FakeDriver::default();
+        assert!(SoapySource::check(&driver, &config(false)).is_err());
+        // it may still show up later
+        assert!(SoapySource::check(&driver, &config(true)).is_ok());
+    }
+
+    #[test]
+    fn unsupported_settings_fail_the_check() {
+        let driver = FakeDriver::plugged();
+        driver.state().max_sample_rate = Some(0.5e6);
+        assert!(SoapySource::check(&driver, &config(false)).is_err());
+        assert!(SoapySource::check(&driver, &config(true)).is_err());
+        driver.state().max_sample_rate = None;
+        assert!(SoapySource::check(&driver, &config(false)).is_ok());
+    }
+
+    #[test]
+    fn open_failure_fails_the_node() {
+        let driver = FakeDriver::default();
+        let mut source = source(&driver, false);
+        block_on(source.start());
+        assert!(source.connection.is_none());
+        assert_eq!(severity(&source), Some(Severity::Error));
+    }
+
+    #[test]
+    fn tunes_with_the_offset() {
+        let driver = FakeDriver::plugged();
+        let mut source = source(&driver, false);
+        source.config.freq_offset = 250e3;
+        block_on(source.start());
+        assert!(source.connection.is_some());
+        assert_eq!(driver.state().freq, 100.25e6);
+    }
+
+    #[test]
+    fn fatal_stream_error_fails_the_node() {
+        let driver = FakeDriver::plugged();
+        driver.state().reads_before_unplug = Some(1);
+        let mut source = source(&driver, false);
+        block_on(source.start());
+        assert_eq!(read(&mut source), 16);
+        assert_eq!(read(&mut source), 0);
+        assert!(source.connection.is_none());
+        assert_eq!(severity(&source), Some(Severity::Error));
+    }
+
+    #[test]
+    fn reconnects_once_the_device_is_back() {
+        let driver = FakeDriver::plugged();
+        let mut source = source(&driver, true);
+        block_on(source.start());
+        assert_eq!(read(&mut source), 16);
+
+        driver.state().plugged = false;
+        assert_eq!(read(&mut source), 0);
+        assert!(source.connection.is_none());
+        assert_eq!(severity(&source), Some(Severity::Warn));
+        assert!(!source.reconnect());
+
+        driver.state().plugged = true;
+        assert!(source.reconnect());
+        assert_eq!(severity(&source), None);
+        assert_eq!(read(&mut source), 16);
+        assert_eq!(driver.state().opened, 2);
+    }
+}
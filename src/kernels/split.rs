@@ -0,0 +1,126 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::marker::PhantomData;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Splits each item of the input into two outputs, e.g. to separate the I and
+/// Q components of a complex stream.
+pub struct Split<I, A, B, F>
+where
+    F: FnMut(&I) -> (A, B) + Send + 'static,
+{
+    f: F,
+    _types: PhantomData<fn(I) -> (A, B)>,
+    timer: WorkTimer,
+}
+
+impl<I, A, B, F> Split<I, A, B, F>
+where
+    I: Copy + Send + 'static,
+    A: Copy + Send + 'static,
+    B: Copy + Send + 'static,
+    F: FnMut(&I) -> (A, B) + Send + 'static,
+{
+    pub fn new(input: &str, outputs: [&str; 2], f: F, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Split").build(),
+            StreamIoBuilder::new()
+                .add_input(input, std::mem::size_of::<I>())
+                .add_output(outputs[0], std::mem::size_of::<A>())
+                .add_output(outputs[1], std::mem::size_of::<B>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Split::with_function(f, timer),
+        )
+    }
+
+    fn with_function(f: F, timer: WorkTimer) -> Self {
+        Split {
+            timer,
+            f,
+            _types: PhantomData,
+        }
+    }
+
+    /// Splits as many items as fit into both outputs, and returns how many.
+    fn split(&mut self, i: &[I], a: &mut [A], b: &mut [B]) -> usize {
+        let n = cmp::min(i.len(), cmp::min(a.len(), b.len()));
+        for ((i, a), b) in i[..n].iter().zip(&mut a[..n]).zip(&mut b[..n]) {
+            (*a, *b) = (self.f)(i);
+        }
+        n
+    }
+}
+
+#[async_trait]
+impl<I, A, B, F> Kernel for Split<I, A, B, F>
+where
+    I: Copy + Send + 'static,
+    A: Copy + Send + 'static,
+    B: Copy + Send + 'static,
+    F: FnMut(&I) -> (A, B) + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<I>();
+        let a = sio.output(0).slice::<A>();
+        let b = sio.output(1).slice::<B>();
+
+        let n = self.split(i, a, b);
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+        sio.output(1).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futuresdr::num_complex::Complex32;
+
+    fn iq_split() -> Split<Complex32, f32, f32, impl FnMut(&Complex32) -> (f32, f32) + Send> {
+        Split::with_function(|v: &Complex32| (v.re, v.im), WorkTimer::default())
+    }
+
+    #[test]
+    fn splits_items() {
+        let input = [Complex32::new(1.0, -1.0), Complex32::new(2.0, -2.0)];
+        let (mut i, mut q) = ([0.0; 3], [0.0; 3]);
+        assert_eq!(iq_split().split(&input, &mut i, &mut q), 2);
+        assert_eq!(i, [1.0, 2.0, 0.0]);
+        assert_eq!(q, [-1.0, -2.0, 0.0]);
+    }
+
+    #[test]
+    fn waits_for_room_on_both_outputs() {
+        let input = [Complex32::new(1.0, -1.0); 4];
+        let (mut i, mut q) = ([0.0; 3], [0.0; 1]);
+        assert_eq!(iq_split().split(&input, &mut i, &mut q), 1);
+        assert_eq!(iq_split().split(&input, &mut i, &mut []), 0);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::dsp::firdes;
+use crate::dsp::firdes::DesignMethod;
+use crate::dsp::firdes::FilterShape;
+use crate::dsp::firdes::FilterSpec;
+use crate::dsp::firdes::Taps;
+use crate::dsp::nco::Nco;
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::anyhow;
+use futuresdr::anyhow::bail;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+pub const SSB_MODES: [&str; 3] = ["USB", "LSB", "CW"];
+/// lower edge of the SSB passband, in audio frequency
+const LOW_CUT: f64 = 300.0;
+const TRANSITION: f64 = 150.0;
+const ATTENUATION: f64 = 60.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Usb,
+    Lsb,
+    Cw,
+}
+
+impl Mode {
+    fn parse(name: &str) -> Result<Mode> {
+        match name {
+            "USB" => Ok(Mode::Usb),
+            "LSB" => Ok(Mode::Lsb),
+            "CW" => Ok(Mode::Cw),
+            _ => Err(anyhow!("Unknown SSB mode {}", name)),
+        }
+    }
+}
+
+/// Everything the filter and the oscillators are derived from.
+#[derive(Clone, Copy)]
+struct Settings {
+    mode: Mode,
+    bandwidth: f64,
+    shift: f64,
+    pitch: f64,
+    cw_bandwidth: f64,
+}
+
+/// SSB and CW receiver using the Weaver method. The middle of the wanted
+/// passband is brought down to DC and lowpassed, which removes the opposite
+/// sideband, then it is brought back up to audio frequencies and the real
+/// part is kept. In CW mode, the carrier ends up at the BFO pitch.
+pub struct SsbReceiver {
+    sample_rate: f64,
+    settings: Settings,
+    down: Nco,
+    up: Nco,
+    taps: Vec<f32>,
+    /// mixed samples, the last `taps.len() - 1` ones are kept between calls
+    history: Vec<Complex32>,
+    status: NodeStatus,
+}
+
+impl SsbReceiver {
+    pub fn new(
+        sample_rate: f64,
+        mode: &str,
+        bandwidth: f64,
+        shift: f64,
+        pitch: f64,
+        cw_bandwidth: f64,
+        status: NodeStatus,
+    ) -> Result<Block> {
+        let mut receiver = SsbReceiver {
+            sample_rate,
+            settings: Settings {
+                mode: Mode::parse(mode)?,
+                bandwidth,
+                shift,
+                pitch,
+                cw_bandwidth,
+            },
+            down: Nco::new(0.0, sample_rate),
+            up: Nco::new(0.0, sample_rate),
+            taps: vec![],
+            history: vec![],
+            status,
+        };
+        receiver.configure(receiver.settings)?;
+        Ok(Block::new(
+            BlockMetaBuilder::new("SsbReceiver").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("mode", SsbReceiver::mode_handler)
+                .add_input("bandwidth", SsbReceiver::bandwidth_handler)
+                .add_input("shift", SsbReceiver::shift_handler)
+                .add_input("pitch", SsbReceiver::pitch_handler)
+                .add_input("cw bandwidth", SsbReceiver::cw_bandwidth_handler)
+                .build(),
+            receiver,
+        ))
+    }
+
+    /// Retunes the oscillators and redesigns the filter. The settings are
+    /// left untouched if they are invalid.
+    fn configure(&mut self, settings: Settings) -> Result<()> {
+        let (half_width, down, up) = match settings.mode {
+            Mode::Usb | Mode::Lsb => {
+                let sign = if settings.mode == Mode::Usb {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let center = sign * (LOW_CUT + settings.bandwidth / 2.0 + settings.shift);
+                (settings.bandwidth / 2.0, center, center)
+            }
+            Mode::Cw => (
+                settings.cw_bandwidth / 2.0,
+                settings.shift,
+                settings.shift + settings.pitch,
+            ),
+        };
+        if half_width <= 0.0 {
+            bail!("The bandwidth must be positive");
+        }
+        if down.abs() + half_width >= self.sample_rate / 2.0 || up.abs() >= self.sample_rate / 2.0 {
+            bail!("The passband must be below {} Hz", self.sample_rate / 2.0);
+        }
+        let taps = match firdes::design(&FilterSpec {
+            shape: FilterShape::Lowpass,
+            method: DesignMethod::Kaiser,
+            sample_rate: self.sample_rate,
+            cutoff: half_width,
+            cutoff_high: 0.0,
+            transition: TRANSITION,
+            attenuation: ATTENUATION,
+            symbol_rate: 0.0,
+            rolloff: 0.0,
+            num_taps: 0,
+            complex: false,
+        })? {
+            Taps::Real(taps) => taps,
+            Taps::Complex(_) => bail!("The SSB filter must be real"),
+        };
+
+        // keep the samples the new filter needs, padding with zeros if it
+        // is longer
+        let keep = taps.len() - 1;
+        if self.history.len() > keep {
+            self.history.drain(..self.history.len() - keep);
+        } else {
+            let missing = keep - self.history.len();
+            self.history
+                .splice(0..0, std::iter::repeat(Complex32::default()).take(missing));
+        }
+        self.taps = taps;
+        self.down.set_freq(-down, self.sample_rate);
+        self.up.set_freq(up, self.sample_rate);
+        self.settings = settings;
+        Ok(())
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut Settings)) {
+        let mut settings = self.settings;
+        change(&mut settings);
+        if let Err(e) = self.configure(settings) {
+            self.status.warn(&e);
+        }
+    }
+
+    fn mode_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SsbReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(name) = p {
+                match Mode::parse(&name) {
+                    Ok(mode) => self.update(|settings| settings.mode = mode),
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn bandwidth_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SsbReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(bandwidth) = p {
+                self.update(|settings| settings.bandwidth = bandwidth);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn shift_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SsbReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(shift) = p {
+                self.update(|settings| settings.shift = shift);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn pitch_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SsbReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(pitch) = p {
+                self.update(|settings| settings.pitch = pitch);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn cw_bandwidth_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<SsbReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(cw_bandwidth) = p {
+                self.update(|settings| settings.cw_bandwidth = cw_bandwidth);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for SsbReceiver {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<f32>();
+
+        let n = cmp::min(i.len(), o.len());
+        self.history
+            .extend(i[..n].iter().map(|x| x * self.down.next()));
+        let len = self.taps.len();
+        for (k, y) in o[..n].iter_mut().enumerate() {
+            let filtered: Complex32 = self.history[k..k + len]
+                .iter()
+                .rev()
+                .zip(&self.taps)
+                .map(|(x, tap)| x * tap)
+                .sum();
+            *y = (filtered * self.up.next()).re;
+        }
+        self.history.drain(..n);
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
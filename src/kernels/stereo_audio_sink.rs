@@ -0,0 +1,160 @@
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::traits::StreamTrait;
+use futuresdr::anyhow::anyhow;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// how much audio is buffered ahead of the device, in seconds
+const BUFFER_DURATION: f64 = 0.2;
+/// how long to wait when the buffer is full
+const FULL_BUFFER_WAIT: Duration = Duration::from_millis(5);
+
+/// Plays a left and a right stream on the default audio output. The audio
+/// stream can't be moved between threads, so it lives on its own thread until
+/// the block is dropped.
+pub struct StereoAudioSink {
+    sample_rate: u32,
+    /// interleaved samples waiting to be played
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    /// dropping it stops the audio thread
+    stop: Option<mpsc::Sender<()>>,
+    status: NodeStatus,
+}
+
+impl StereoAudioSink {
+    pub fn new(sample_rate: u32, status: NodeStatus) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("StereoAudioSink").blocking().build(),
+            StreamIoBuilder::new()
+                .add_input("left", std::mem::size_of::<f32>())
+                .add_input("right", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            StereoAudioSink {
+                sample_rate,
+                buffer: Arc::new(Mutex::new(VecDeque::new())),
+                stop: None,
+                status,
+            },
+        )
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let (started, result) = mpsc::channel();
+        let buffer = self.buffer.clone();
+        let sample_rate = self.sample_rate;
+        thread::spawn(move || {
+            let stream = (|| -> Result<cpal::Stream> {
+                let device = cpal::default_host()
+                    .default_output_device()
+                    .ok_or_else(|| anyhow!("No audio output device"))?;
+                let config = cpal::StreamConfig {
+                    channels: 2,
+                    sample_rate: cpal::SampleRate(sample_rate),
+                    buffer_size: cpal::BufferSize::Default,
+                };
+                let stream = device.build_output_stream(
+                    &config,
+                    move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                        let mut buffer = buffer.lock().unwrap();
+                        for sample in data.iter_mut() {
+                            // silence on underruns
+                            *sample = buffer.pop_front().unwrap_or(0.0);
+                        }
+                    },
+                    |e| log::error!("Audio output failed: {}", e),
+                )?;
+                stream.play()?;
+                Ok(stream)
+            })();
+            match stream {
+                Ok(_stream) => {
+                    let _ = started.send(Ok(()));
+                    // keeps the stream alive until the sender is dropped
+                    let _ = stopped.recv();
+                }
+                Err(e) => {
+                    let _ = started.send(Err(e));
+                }
+            }
+        });
+        result.recv()??;
+        self.stop = Some(stop);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Kernel for StereoAudioSink {
+    async fn init(
+        &mut self,
+        _sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        if let Err(e) = self.start() {
+            self.status.fail(&e);
+        }
+        Ok(())
+    }
+
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        if self.stop.is_none() {
+            io.finished = true;
+            return Ok(());
+        }
+
+        let left = sio.input(0).slice::<f32>();
+        let right = sio.input(1).slice::<f32>();
+
+        let capacity = (self.sample_rate as f64 * BUFFER_DURATION) as usize * 2;
+        let mut buffer = self.buffer.lock().unwrap();
+        let space = capacity.saturating_sub(buffer.len()) / 2;
+        let n = cmp::min(cmp::min(left.len(), right.len()), space);
+        for (l, r) in left[..n].iter().zip(&right[..n]) {
+            buffer.push_back(*l);
+            buffer.push_back(*r);
+        }
+        drop(buffer);
+
+        sio.input(0).consume(n);
+        sio.input(1).consume(n);
+
+        let finished = (sio.input(0).finished() && n == left.len())
+            || (sio.input(1).finished() && n == right.len());
+        if finished {
+            io.finished = true;
+        } else if space == 0 {
+            thread::sleep(FULL_BUFFER_WAIT);
+            io.call_again = true;
+        }
+        Ok(())
+    }
+}
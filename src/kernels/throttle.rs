@@ -0,0 +1,94 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::marker::PhantomData;
+use std::time::Duration;
+use std::time::Instant;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_io::Timer;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// how long to wait at most before checking again whether more samples may go
+const MAX_WAIT: Duration = Duration::from_millis(10);
+
+/// Lets samples through no faster than the sample rate, for flowgraphs
+/// without hardware setting the pace.
+pub struct Throttle<T> {
+    sample_rate: f64,
+    start: Option<Instant>,
+    passed: u64,
+    _type: PhantomData<T>,
+    timer: WorkTimer,
+}
+
+impl<T> Throttle<T>
+where
+    T: Copy + Send + 'static,
+{
+    pub fn new(sample_rate: f64, timer: WorkTimer) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("Throttle").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<T>())
+                .add_output("out", std::mem::size_of::<T>())
+                .build(),
+            MessageIoBuilder::new().build(),
+            Throttle::<T> {
+                timer,
+                sample_rate,
+                start: None,
+                passed: 0,
+                _type: PhantomData,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl<T> Kernel for Throttle<T>
+where
+    T: Copy + Send + 'static,
+{
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let timing = self.timer.start();
+        let i = sio.input(0).slice::<T>();
+        let o = sio.output(0).slice::<T>();
+
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let allowed = (start.elapsed().as_secs_f64() * self.sample_rate) as u64 - self.passed;
+        let n = cmp::min(cmp::min(i.len(), o.len()), allowed as usize);
+        o[..n].copy_from_slice(&i[..n]);
+        self.passed += n as u64;
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        } else if n < i.len() && n < o.len() {
+            // out of budget, wait until the next samples are due
+            drop(timing);
+            let due = Duration::from_secs_f64((self.passed + 1) as f64 / self.sample_rate);
+            Timer::after(cmp::min(due.saturating_sub(start.elapsed()), MAX_WAIT)).await;
+            io.call_again = true;
+        }
+
+        Ok(())
+    }
+}
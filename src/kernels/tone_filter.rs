@@ -0,0 +1,139 @@
+use crate::dsp::biquad;
+use crate::dsp::biquad::Biquad;
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::bail;
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Highpass and lowpass for audio, both 4th order Butterworth. A cutoff of 0
+/// disables the corresponding filter.
+pub struct ToneFilter {
+    sample_rate: f64,
+    highpass: Option<[Biquad; 2]>,
+    lowpass: Option<[Biquad; 2]>,
+    status: NodeStatus,
+}
+
+impl ToneFilter {
+    pub fn new(sample_rate: f64, low_cut: f64, high_cut: f64, status: NodeStatus) -> Result<Block> {
+        Ok(Block::new(
+            BlockMetaBuilder::new("ToneFilter").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<f32>())
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("low cut", ToneFilter::low_cut_handler)
+                .add_input("high cut", ToneFilter::high_cut_handler)
+                .build(),
+            ToneFilter {
+                sample_rate,
+                highpass: ToneFilter::sections(low_cut, sample_rate, Biquad::highpass)?,
+                lowpass: ToneFilter::sections(high_cut, sample_rate, Biquad::lowpass)?,
+                status,
+            },
+        ))
+    }
+
+    fn sections(
+        cutoff: f64,
+        sample_rate: f64,
+        design: fn(f64, f64, f64) -> Biquad,
+    ) -> Result<Option<[Biquad; 2]>> {
+        if cutoff < 0.0 || cutoff >= sample_rate / 2.0 {
+            bail!("The cutoffs must be below {} Hz", sample_rate / 2.0);
+        }
+        if cutoff == 0.0 {
+            return Ok(None);
+        }
+        Ok(Some([0, 1].map(|section| {
+            design(cutoff, biquad::BUTTERWORTH_Q[section], sample_rate)
+        })))
+    }
+
+    fn low_cut_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<ToneFilter>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(low_cut) = p {
+                match ToneFilter::sections(low_cut, self.sample_rate, Biquad::highpass) {
+                    Ok(highpass) => self.highpass = highpass,
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn high_cut_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<ToneFilter>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(high_cut) = p {
+                match ToneFilter::sections(high_cut, self.sample_rate, Biquad::lowpass) {
+                    Ok(lowpass) => self.lowpass = lowpass,
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for ToneFilter {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<f32>();
+        let o = sio.output(0).slice::<f32>();
+
+        let n = cmp::min(i.len(), o.len());
+        let sections = self
+            .highpass
+            .iter_mut()
+            .chain(self.lowpass.iter_mut())
+            .flatten();
+        o[..n].copy_from_slice(&i[..n]);
+        for section in sections {
+            for y in o[..n].iter_mut() {
+                *y = section.filter(*y);
+            }
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// how fast the gain follows volume changes, per sample, so that they don't
+/// click
+const GAIN_SMOOTHING: f32 = 0.002;
+
+/// Scales audio by a volume in dB, or mutes it.
+pub struct Volume {
+    volume: f64,
+    mute: bool,
+    gain: f32,
+    timer: WorkTimer,
+}
+
+impl Volume {
+    pub fn new(volume: f64, mute: bool, timer: WorkTimer) -> Block {
+        let mut kernel = Volume {
+            timer,
+            volume,
+            mute,
+            gain: 0.0,
+        };
+        kernel.gain = kernel.target();
+        Block::new(
+            BlockMetaBuilder::new("Volume").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<f32>())
+                .add_output("out", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("volume", Volume::volume_handler)
+                .add_input("mute", Volume::mute_handler)
+                .build(),
+            kernel,
+        )
+    }
+
+    fn target(&self) -> f32 {
+        if self.mute {
+            0.0
+        } else {
+            10f64.powf(self.volume / 20.0) as f32
+        }
+    }
+
+    fn volume_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Volume>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(volume) = p {
+                self.volume = volume;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn mute_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<Volume>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(mute) = p {
+                self.mute = mute;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for Volume {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<f32>();
+        let o = sio.output(0).slice::<f32>();
+
+        let target = self.target();
+        let n = cmp::min(i.len(), o.len());
+        for (x, y) in i[..n].iter().zip(o[..n].iter_mut()) {
+            self.gain += (target - self.gain) * GAIN_SMOOTHING;
+            *y = x * self.gain;
+        }
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(n);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use crate::dsp::fm;
+use crate::dsp::fm::Deemphasis;
+use crate::dsp::fm::Discriminator;
+use crate::dsp::fm::Pll;
+use crate::radio::NodeStatus;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+const DEVIATION: f64 = 75_000.0;
+const PILOT_FREQ: f64 = 19_000.0;
+const PILOT_RANGE: f64 = 20.0;
+const PILOT_LOOP_BANDWIDTH: f64 = 10.0;
+/// pilot levels, relative to the full deviation, between which the output
+/// blends from mono to stereo
+const BLEND_START: f32 = 0.02;
+const BLEND_END: f32 = 0.05;
+/// how fast the blend follows the pilot, per output sample
+const BLEND_SMOOTHING: f32 = 0.001;
+
+/// Broadcast FM receiver. The multiplex is demodulated at the input rate, the
+/// 38 kHz L-R subcarrier is brought down using a PLL locked on the 19 kHz
+/// pilot, and both L+R and L-R are filtered and decimated to the audio rate.
+/// The output blends to mono when the pilot is weak or missing.
+pub struct WbfmReceiver {
+    discriminator: Discriminator,
+    pll: Pll,
+    /// audio lowpass, at the input rate
+    taps: Vec<f32>,
+    decimation: usize,
+    /// L+R and L-R at the input rate, the last `taps.len() - 1` samples are
+    /// kept between calls
+    sum: Vec<f32>,
+    difference: Vec<f32>,
+    /// samples to wait for before the next output, beyond those kept
+    skip: usize,
+    blend: f32,
+    stereo: bool,
+    deemphasis: [Deemphasis; 2],
+    audio_rate: f64,
+    status: NodeStatus,
+}
+
+impl WbfmReceiver {
+    pub fn new(
+        sample_rate: f64,
+        taps: Vec<f32>,
+        decimation: usize,
+        deemphasis_tau: f64,
+        stereo: bool,
+        status: NodeStatus,
+    ) -> Block {
+        let audio_rate = sample_rate / decimation as f64;
+        Block::new(
+            BlockMetaBuilder::new("WbfmReceiver").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("left", std::mem::size_of::<f32>())
+                .add_output("right", std::mem::size_of::<f32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("de-emphasis", WbfmReceiver::deemphasis_handler)
+                .add_input("stereo", WbfmReceiver::stereo_handler)
+                .build(),
+            WbfmReceiver {
+                discriminator: Discriminator::new(DEVIATION, sample_rate),
+                pll: Pll::new(PILOT_FREQ, PILOT_RANGE, PILOT_LOOP_BANDWIDTH, sample_rate),
+                sum: vec![0.0; taps.len() - 1],
+                difference: vec![0.0; taps.len() - 1],
+                taps,
+                decimation,
+                skip: 0,
+                blend: 0.0,
+                stereo,
+                deemphasis: [
+                    Deemphasis::new(deemphasis_tau, audio_rate),
+                    Deemphasis::new(deemphasis_tau, audio_rate),
+                ],
+                audio_rate,
+                status,
+            },
+        )
+    }
+
+    fn deemphasis_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<WbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::String(name) = p {
+                match fm::deemphasis_tau(&name) {
+                    Ok(tau) => {
+                        for deemphasis in &mut self.deemphasis {
+                            deemphasis.set_tau(tau, self.audio_rate);
+                        }
+                    }
+                    Err(e) => self.status.warn(&e),
+                }
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn stereo_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<WbfmReceiver>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Bool(stereo) = p {
+                self.stereo = stereo;
+            }
+            Ok(Pmt::Null)
+        })
+    }
+
+    fn filter(&self, samples: &[f32]) -> f32 {
+        samples
+            .iter()
+            .rev()
+            .zip(&self.taps)
+            .map(|(x, tap)| x * tap)
+            .sum()
+    }
+}
+
+#[async_trait]
+impl Kernel for WbfmReceiver {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.status.work_timer().start();
+        let i = sio.input(0).slice::<Complex32>();
+        let left = sio.output(0).slice::<f32>();
+        let right = sio.output(1).slice::<f32>();
+
+        let space = cmp::min(left.len(), right.len());
+        let n = cmp::min(i.len(), space * self.decimation);
+        for x in &i[..n] {
+            let mpx = self.discriminator.demodulate(*x);
+            let pilot_phase = self.pll.track(mpx);
+            self.sum.push(mpx);
+            self.difference
+                .push(mpx * 2.0 * fm::stereo_subcarrier(pilot_phase));
+        }
+
+        let target = if self.stereo {
+            ((self.pll.level() - BLEND_START) / (BLEND_END - BLEND_START)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let len = self.taps.len();
+        let mut next = len - 1 + self.skip;
+        let mut produced = 0;
+        while next < self.sum.len() && produced < space {
+            let sum = self.filter(&self.sum[next + 1 - len..=next]);
+            let difference = self.filter(&self.difference[next + 1 - len..=next]);
+            self.blend += (target - self.blend) * BLEND_SMOOTHING;
+            left[produced] = self.deemphasis[0].filter(sum + self.blend * difference);
+            right[produced] = self.deemphasis[1].filter(sum - self.blend * difference);
+            produced += 1;
+            next += self.decimation;
+        }
+        self.skip = next - self.sum.len().min(next);
+        self.sum.drain(..self.sum.len() - (len - 1));
+        self.difference.drain(..self.difference.len() - (len - 1));
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(produced);
+        sio.output(1).produce(produced);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
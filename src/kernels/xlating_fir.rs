@@ -0,0 +1,125 @@
+use crate::dsp::nco::Nco;
+use crate::stats::WorkTimer;
+
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+
+use futuresdr::anyhow::Result;
+use futuresdr::async_trait::async_trait;
+use futuresdr::num_complex::Complex32;
+use futuresdr::runtime::Block;
+use futuresdr::runtime::BlockMeta;
+use futuresdr::runtime::BlockMetaBuilder;
+use futuresdr::runtime::Kernel;
+use futuresdr::runtime::MessageIo;
+use futuresdr::runtime::MessageIoBuilder;
+use futuresdr::runtime::Pmt;
+use futuresdr::runtime::StreamIo;
+use futuresdr::runtime::StreamIoBuilder;
+use futuresdr::runtime::WorkIo;
+
+/// Channel selector: brings the channel at `offset` down to DC, filters it and
+/// decimates it in one go. Only the samples kept by the decimation are
+/// filtered. The offset can be changed while running.
+pub struct XlatingFir {
+    nco: Nco,
+    sample_rate: f64,
+    taps: Vec<f32>,
+    decimation: usize,
+    /// mixed samples, the last `taps.len() - 1` ones are kept between calls
+    history: Vec<Complex32>,
+    /// samples to wait for before the next output, beyond those in `history`
+    skip: usize,
+    timer: WorkTimer,
+}
+
+impl XlatingFir {
+    pub fn new(
+        offset: f64,
+        sample_rate: f64,
+        taps: Vec<f32>,
+        decimation: usize,
+        timer: WorkTimer,
+    ) -> Block {
+        Block::new(
+            BlockMetaBuilder::new("XlatingFir").build(),
+            StreamIoBuilder::new()
+                .add_input("in", std::mem::size_of::<Complex32>())
+                .add_output("out", std::mem::size_of::<Complex32>())
+                .build(),
+            MessageIoBuilder::new()
+                .add_input("offset", XlatingFir::offset_handler)
+                .build(),
+            XlatingFir {
+                timer,
+                nco: Nco::new(-offset, sample_rate),
+                sample_rate,
+                history: vec![Complex32::default(); taps.len() - 1],
+                taps,
+                decimation,
+                skip: 0,
+            },
+        )
+    }
+
+    fn offset_handler<'a>(
+        &'a mut self,
+        _mio: &'a mut MessageIo<XlatingFir>,
+        _meta: &'a mut BlockMeta,
+        p: Pmt,
+    ) -> Pin<Box<dyn Future<Output = Result<Pmt>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Pmt::Double(offset) = p {
+                self.nco.set_freq(-offset, self.sample_rate);
+            }
+            Ok(Pmt::Null)
+        })
+    }
+}
+
+#[async_trait]
+impl Kernel for XlatingFir {
+    async fn work(
+        &mut self,
+        io: &mut WorkIo,
+        sio: &mut StreamIo,
+        _mio: &mut MessageIo<Self>,
+        _meta: &mut BlockMeta,
+    ) -> Result<()> {
+        let _timing = self.timer.start();
+        let i = sio.input(0).slice::<Complex32>();
+        let o = sio.output(0).slice::<Complex32>();
+
+        let len = self.taps.len();
+        let n = cmp::min(i.len(), o.len() * self.decimation);
+        let start = self.history.len();
+        self.history.resize(start + n, Complex32::default());
+        self.nco.mix(&i[..n], &mut self.history[start..]);
+
+        // index of the newest sample of the next output
+        let mut next = len - 1 + self.skip;
+        let mut produced = 0;
+        while next < self.history.len() && produced < o.len() {
+            o[produced] = self.history[next + 1 - len..=next]
+                .iter()
+                .rev()
+                .zip(&self.taps)
+                .map(|(x, tap)| x * tap)
+                .sum();
+            produced += 1;
+            next += self.decimation;
+        }
+        self.skip = next - self.history.len().min(next);
+        self.history.drain(..self.history.len() - (len - 1));
+
+        sio.input(0).consume(n);
+        sio.output(0).produce(produced);
+
+        if sio.input(0).finished() && n == i.len() {
+            io.finished = true;
+        }
+
+        Ok(())
+    }
+}
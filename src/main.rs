@@ -1,9 +1,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod blocks;
+mod console;
 mod consts;
+mod dsp;
+mod flowgraph_file;
+mod kernels;
+mod panels;
 mod params;
 mod radio;
+mod soapy;
+mod stats;
 mod ui;
 
 #[macro_use]
@@ -0,0 +1,211 @@
+use crate::ui::ESDRGraph;
+use crate::ui::ESDRResponse;
+use crate::ui::ESDRValueType;
+
+use std::collections::HashMap;
+
+use eframe::egui;
+use egui_node_graph::NodeId;
+use egui_node_graph::WidgetValueTrait;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PanelPlacement {
+    Docked,
+    Floating,
+}
+
+struct Panel {
+    node_id: NodeId,
+    placement: PanelPlacement,
+    open: bool,
+}
+
+impl Panel {
+    fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        graph: &mut ESDRGraph,
+        info: Option<&[(String, String)]>,
+    ) -> Vec<ESDRResponse> {
+        ui.horizontal(|ui| {
+            let (icon, placement) = match self.placement {
+                PanelPlacement::Docked => ("⬈", PanelPlacement::Floating),
+                PanelPlacement::Floating => ("⬋", PanelPlacement::Docked),
+            };
+            if ui.small_button(icon).clicked() {
+                self.placement = placement;
+            }
+            if ui.small_button("✖").clicked() {
+                self.open = false;
+            }
+        });
+        if let Some(info) = info {
+            node_info_ui(ui, self.node_id, info);
+            ui.separator();
+        }
+        node_params_ui(ui, graph, self.node_id, false)
+    }
+}
+
+/// Keeps track of which nodes were popped out of the graph editor and where
+/// they are shown. This lives in the graph state, and is saved along with the
+/// flowgraph.
+#[derive(Default)]
+pub struct PanelLayout {
+    panels: Vec<Panel>,
+    pub run_view: bool,
+}
+
+impl PanelLayout {
+    pub fn is_open(&self, node_id: NodeId) -> bool {
+        self.panels.iter().any(|panel| panel.node_id == node_id)
+    }
+
+    pub fn toggle(&mut self, node_id: NodeId) {
+        if self.is_open(node_id) {
+            self.close(node_id);
+        } else {
+            self.panels.push(Panel {
+                node_id,
+                placement: PanelPlacement::Docked,
+                open: true,
+            });
+        }
+    }
+
+    /// The open panels, in the order they are shown.
+    pub fn panels(&self) -> impl Iterator<Item = (NodeId, PanelPlacement)> + '_ {
+        self.panels
+            .iter()
+            .map(|panel| (panel.node_id, panel.placement))
+    }
+
+    pub fn open(&mut self, node_id: NodeId, placement: PanelPlacement) {
+        self.close(node_id);
+        self.panels.push(Panel {
+            node_id,
+            placement,
+            open: true,
+        });
+    }
+
+    pub fn close(&mut self, node_id: NodeId) {
+        self.panels.retain(|panel| panel.node_id != node_id);
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        graph: &mut ESDRGraph,
+        node_info: &HashMap<NodeId, Vec<(String, String)>>,
+    ) -> Vec<ESDRResponse> {
+        let mut responses = vec![];
+        self.panels
+            .retain(|panel| graph.nodes.contains_key(panel.node_id));
+
+        let is_docked = |panel: &&mut Panel| panel.placement == PanelPlacement::Docked;
+        if self
+            .panels
+            .iter()
+            .any(|panel| panel.placement == PanelPlacement::Docked)
+        {
+            egui::SidePanel::right("docked_panels")
+                .resizable(true)
+                .default_width(300.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for panel in self.panels.iter_mut().filter(is_docked) {
+                            egui::CollapsingHeader::new(graph[panel.node_id].label.clone())
+                                .id_source(panel.node_id)
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    responses.append(&mut panel.ui(
+                                        ui,
+                                        graph,
+                                        node_info.get(&panel.node_id).map(Vec::as_slice),
+                                    ));
+                                });
+                        }
+                    });
+                });
+        }
+
+        for panel in self.panels.iter_mut().filter(|panel| !is_docked(panel)) {
+            let mut open = panel.open;
+            egui::Window::new(graph[panel.node_id].label.clone())
+                .id(egui::Id::new(("panel", panel.node_id)))
+                .open(&mut open)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    responses.append(&mut panel.ui(
+                        ui,
+                        graph,
+                        node_info.get(&panel.node_id).map(Vec::as_slice),
+                    ));
+                });
+            panel.open &= open;
+        }
+
+        self.panels.retain(|panel| panel.open);
+        responses
+    }
+}
+
+/// Shows the values published by a running block, like the name of the
+/// station being received.
+pub fn node_info_ui(ui: &mut egui::Ui, node_id: NodeId, info: &[(String, String)]) {
+    egui::Grid::new(("node_info", node_id))
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (name, value) in info {
+                ui.label(name);
+                ui.monospace(value);
+                ui.end_row();
+            }
+        });
+}
+
+/// Shows the widgets for the constant parameters of a node. When
+/// `tunable_only` is set, only parameters that can be updated while the radio
+/// is running are shown.
+pub fn node_params_ui(
+    ui: &mut egui::Ui,
+    graph: &mut ESDRGraph,
+    node_id: NodeId,
+    tunable_only: bool,
+) -> Vec<ESDRResponse> {
+    let mut responses = vec![];
+    for (name, input_id) in graph[node_id].inputs.clone() {
+        let value = &mut graph.inputs[input_id].value;
+        if let ESDRValueType::InputStream { .. } = value {
+            continue;
+        }
+        if tunable_only && !value.allow_updates() {
+            continue;
+        }
+        responses.append(&mut value.value_widget(&name, ui));
+    }
+    responses
+}
+
+/// The "run view" hides the graph editor and only shows the parameters which
+/// can be tuned while the radio is running.
+pub fn run_view_ui(ui: &mut egui::Ui, graph: &mut ESDRGraph) -> Vec<ESDRResponse> {
+    let mut responses = vec![];
+    let node_ids: Vec<NodeId> = graph.nodes.keys().collect();
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for node_id in node_ids {
+            let has_tunables = graph[node_id]
+                .inputs
+                .iter()
+                .any(|(_, input_id)| graph.inputs[*input_id].value.allow_updates());
+            if !has_tunables {
+                continue;
+            }
+            ui.heading(&graph[node_id].label);
+            responses.append(&mut node_params_ui(ui, graph, node_id, true));
+            ui.separator();
+        }
+    });
+    responses
+}
@@ -0,0 +1,139 @@
+use crate::params::Param;
+use crate::params::ParamTrait;
+use crate::ui::ESDRDataType;
+use crate::ui::ESDRGraph;
+use crate::ui::ESDRResponse;
+use crate::ui::ESDRValueType;
+use crate::ui::UpdateParamPayload;
+
+use eframe::egui;
+use egui_node_graph::InputParamKind;
+use egui_node_graph::NodeId;
+use futuresdr::runtime::Pmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Choice {
+    pub label: String,
+    pub value: String,
+}
+
+impl Choice {
+    pub fn new(label: &str, value: &str) -> Choice {
+        Choice {
+            label: label.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+#[derive(Default, Clone, Builder, Debug)]
+#[builder(public, setter(into), build_fn(private, name = "build_impl"))]
+pub struct ChoiceParam {
+    pub name: String,
+    #[builder(default = "vec![]")]
+    pub choices: Vec<Choice>,
+    /// Function used to refresh the list of choices (e.g. available devices).
+    /// It is called whenever the list is shown, so it has to be quick.
+    /// Choices passed through `choices` are always listed first.
+    #[builder(default = "None", setter(custom))]
+    pub refresh: Option<fn() -> Vec<Choice>>,
+    #[builder(default = "vec![]", setter(skip))]
+    refreshed: Vec<Choice>,
+    #[builder(default = "false")]
+    pub allow_updates: bool,
+}
+
+impl ChoiceParam {
+    fn all_choices(&self) -> impl Iterator<Item = &Choice> {
+        self.choices.iter().chain(self.refreshed.iter())
+    }
+
+    fn refresh(&mut self) {
+        if let Some(refresh) = self.refresh {
+            self.refreshed = refresh();
+        }
+    }
+
+    pub fn set_choices(&mut self, choices: Vec<Choice>) {
+        self.choices = choices;
+    }
+}
+
+impl ParamTrait<&mut String> for ChoiceParam {
+    fn add_param(mut self, graph: &mut ESDRGraph, node_id: NodeId) -> () {
+        self.refresh();
+        let value = self
+            .all_choices()
+            .next()
+            .map(|choice| choice.value.clone())
+            .unwrap_or_default();
+        graph.add_input_param(
+            node_id,
+            self.name.clone(),
+            ESDRDataType::Scalar,
+            ESDRValueType::Choice {
+                node_id,
+                value,
+                config: self,
+            },
+            InputParamKind::ConstantOnly,
+            true,
+        );
+    }
+
+    fn widget(
+        &mut self,
+        ui: &mut egui::Ui,
+        node_id: NodeId,
+        value: &mut String,
+    ) -> Vec<ESDRResponse> {
+        let mut responses = vec![];
+        let previous = value.clone();
+        ui.horizontal(|ui| {
+            ui.label(&self.name);
+            let selected = self
+                .all_choices()
+                .find(|choice| choice.value == *value)
+                .map(|choice| choice.label.clone())
+                .unwrap_or_else(|| value.clone());
+            egui::ComboBox::from_id_source((node_id, &self.name))
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    // picks up choices which showed up since (e.g. devices
+                    // enumerated in the background)
+                    self.refresh();
+                    for choice in self.all_choices() {
+                        ui.selectable_value(value, choice.value.clone(), &choice.label);
+                    }
+                });
+            if self.refresh.is_some() && ui.small_button("⟳").on_hover_text("Refresh").clicked() {
+                self.refresh();
+                responses.push(ESDRResponse::RefreshNode(node_id));
+            }
+        });
+        if *value != previous {
+            if self.refresh.is_some() {
+                // refreshable choices describe something outside of eSDR (e.g.
+                // a device), so the rest of the node might need to change too
+                responses.push(ESDRResponse::RefreshNode(node_id));
+            }
+            responses.push(ESDRResponse::UpdateParam(UpdateParamPayload {
+                node_id,
+                field: self.name.to_string(),
+                value: Pmt::String(value.clone()),
+            }));
+        }
+        responses
+    }
+}
+
+impl ChoiceParamBuilder {
+    pub fn refresh(&mut self, refresh: fn() -> Vec<Choice>) -> &mut Self {
+        self.refresh = Some(Some(refresh));
+        self
+    }
+
+    pub fn build(&self) -> Param {
+        Param::Choice(self.build_impl().unwrap())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::params::Param;
 use crate::params::ParamTrait;
+use crate::params::StreamType;
 use crate::ui::ESDRDataType;
 use crate::ui::ESDRGraph;
 use crate::ui::ESDRResponse;
@@ -13,6 +14,14 @@ use egui_node_graph::NodeId;
 #[builder(public, setter(into), build_fn(private, name = "build_impl"))]
 pub struct InputStream {
     name: String,
+    #[builder(default = "StreamType::Complex32")]
+    item_type: StreamType,
+}
+
+impl InputStream {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl ParamTrait<()> for InputStream {
@@ -20,7 +29,7 @@ impl ParamTrait<()> for InputStream {
         graph.add_input_param(
             node_id,
             self.name.clone(),
-            ESDRDataType::Stream,
+            ESDRDataType::Stream(self.item_type),
             ESDRValueType::InputStream {
                 node_id,
                 config: self,
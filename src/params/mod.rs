@@ -1,21 +1,62 @@
+use crate::params::choice::ChoiceParamBuilder;
 use crate::params::input_stream::InputStreamBuilder;
 use crate::params::output_stream::OutputStreamBuilder;
 use crate::params::scalar::ScalarParamBuilder;
+use crate::params::text::TextParamBuilder;
+use crate::params::toggle::ToggleParamBuilder;
 use crate::ui::ESDRGraph;
 use crate::ui::ESDRResponse;
 
 use eframe::egui;
 use egui_node_graph::NodeId;
+use futuresdr::num_complex::Complex32;
+use serde::Deserialize;
+use serde::Serialize;
 
+pub mod choice;
 pub mod input_stream;
 pub mod output_stream;
 pub mod scalar;
+pub mod text;
+pub mod toggle;
 
 #[derive(Clone, Debug)]
 pub enum Param {
     Scalar(self::scalar::ScalarParam),
     InputStream(self::input_stream::InputStream),
     OutputStream(self::output_stream::OutputStream),
+    Toggle(self::toggle::ToggleParam),
+    Choice(self::choice::ChoiceParam),
+    Text(self::text::TextParam),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StreamType {
+    #[default]
+    Complex32,
+    F32,
+    I16,
+    I8,
+}
+
+impl StreamType {
+    pub fn item_size(self) -> usize {
+        match self {
+            StreamType::Complex32 => std::mem::size_of::<Complex32>(),
+            StreamType::F32 => std::mem::size_of::<f32>(),
+            StreamType::I16 => std::mem::size_of::<i16>(),
+            StreamType::I8 => std::mem::size_of::<i8>(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamType::Complex32 => "complex",
+            StreamType::F32 => "float",
+            StreamType::I16 => "short",
+            StreamType::I8 => "char",
+        }
+    }
 }
 
 pub trait ParamTrait<T> {
@@ -24,6 +65,30 @@ pub trait ParamTrait<T> {
 }
 
 impl Param {
+    pub fn name(&self) -> &str {
+        match self {
+            Param::Scalar(p) => &p.name,
+            Param::InputStream(p) => p.name(),
+            Param::OutputStream(p) => p.name(),
+            Param::Toggle(p) => &p.name,
+            Param::Choice(p) => &p.name,
+            Param::Text(p) => &p.name,
+        }
+    }
+
+    pub fn add_param(self, graph: &mut ESDRGraph, node_id: NodeId) {
+        // this is needed because enum_dispatch doesn't work with traits that have
+        // associated types. see https://gitlab.com/antonok/enum_dispatch/-/issues/50
+        match self {
+            Param::Scalar(p) => p.add_param(graph, node_id),
+            Param::InputStream(p) => p.add_param(graph, node_id),
+            Param::OutputStream(p) => p.add_param(graph, node_id),
+            Param::Toggle(p) => p.add_param(graph, node_id),
+            Param::Choice(p) => p.add_param(graph, node_id),
+            Param::Text(p) => p.add_param(graph, node_id),
+        }
+    }
+
     pub fn input_stream(name: &str) -> InputStreamBuilder {
         InputStreamBuilder::default().name(name).clone()
     }
@@ -35,4 +100,16 @@ impl Param {
     pub fn scalar(name: &str) -> ScalarParamBuilder {
         ScalarParamBuilder::default().name(name).clone()
     }
+
+    pub fn toggle(name: &str) -> ToggleParamBuilder {
+        ToggleParamBuilder::default().name(name).clone()
+    }
+
+    pub fn choice(name: &str) -> ChoiceParamBuilder {
+        ChoiceParamBuilder::default().name(name).clone()
+    }
+
+    pub fn text(name: &str) -> TextParamBuilder {
+        TextParamBuilder::default().name(name).clone()
+    }
 }
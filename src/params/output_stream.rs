@@ -1,5 +1,6 @@
 use crate::params::Param;
 use crate::params::ParamTrait;
+use crate::params::StreamType;
 use crate::ui::ESDRDataType;
 use crate::ui::ESDRGraph;
 use crate::ui::ESDRResponse;
@@ -11,11 +12,23 @@ use egui_node_graph::NodeId;
 #[builder(public, setter(into), build_fn(private, name = "build_impl"))]
 pub struct OutputStream {
     name: String,
+    #[builder(default = "StreamType::Complex32")]
+    item_type: StreamType,
+}
+
+impl OutputStream {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl ParamTrait<()> for OutputStream {
     fn add_param(self, graph: &mut ESDRGraph, node_id: NodeId) -> () {
-        graph.add_output_param(node_id, self.name.clone(), ESDRDataType::Stream);
+        graph.add_output_param(
+            node_id,
+            self.name.clone(),
+            ESDRDataType::Stream(self.item_type),
+        );
     }
 
     fn widget(&mut self, ui: &mut egui::Ui, _node_id: NodeId, _value: ()) -> Vec<ESDRResponse> {
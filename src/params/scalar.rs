@@ -4,11 +4,12 @@ use crate::ui::ESDRDataType;
 use crate::ui::ESDRGraph;
 use crate::ui::ESDRResponse;
 use crate::ui::ESDRValueType;
-use crate::ui::UpdateScalarPayload;
+use crate::ui::UpdateParamPayload;
 
 use eframe::egui::{self, DragValue};
 use egui_node_graph::InputParamKind;
 use egui_node_graph::NodeId;
+use futuresdr::runtime::Pmt;
 
 #[derive(Default, Clone, Builder, Debug)]
 #[builder(public, setter(into), build_fn(private, name = "build_impl"))]
@@ -18,6 +19,10 @@ pub struct ScalarParam {
     pub initial_value: f64,
     #[builder(default = "false")]
     pub allow_updates: bool,
+    /// Whether changing the value refreshes the node, for values setting
+    /// its ports.
+    #[builder(default = "false")]
+    pub refresh: bool,
 }
 
 impl ParamTrait<&mut f64> for ScalarParam {
@@ -41,10 +46,13 @@ impl ParamTrait<&mut f64> for ScalarParam {
         ui.horizontal(|ui| {
             ui.label(&self.name);
             if ui.add(DragValue::new(value)).changed() {
-                responses.push(ESDRResponse::UpdateScalar(UpdateScalarPayload {
+                if self.refresh {
+                    responses.push(ESDRResponse::RefreshNode(node_id));
+                }
+                responses.push(ESDRResponse::UpdateParam(UpdateParamPayload {
                     node_id,
                     field: self.name.to_string(),
-                    value: *value,
+                    value: Pmt::Double(*value),
                 }));
             }
         });
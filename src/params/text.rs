@@ -0,0 +1,66 @@
+use crate::params::Param;
+use crate::params::ParamTrait;
+use crate::ui::ESDRDataType;
+use crate::ui::ESDRGraph;
+use crate::ui::ESDRResponse;
+use crate::ui::ESDRValueType;
+use crate::ui::UpdateParamPayload;
+
+use eframe::egui;
+use egui_node_graph::InputParamKind;
+use egui_node_graph::NodeId;
+use futuresdr::runtime::Pmt;
+
+#[derive(Default, Clone, Builder, Debug)]
+#[builder(public, setter(into), build_fn(private, name = "build_impl"))]
+pub struct TextParam {
+    pub name: String,
+    #[builder(default = "String::new()")]
+    pub initial_value: String,
+    #[builder(default = "false")]
+    pub allow_updates: bool,
+}
+
+impl ParamTrait<&mut String> for TextParam {
+    fn add_param(self, graph: &mut ESDRGraph, node_id: NodeId) -> () {
+        graph.add_input_param(
+            node_id,
+            self.name.clone(),
+            ESDRDataType::Scalar,
+            ESDRValueType::Text {
+                node_id,
+                value: self.initial_value.clone(),
+                config: self,
+            },
+            InputParamKind::ConstantOnly,
+            true,
+        );
+    }
+
+    fn widget(
+        &mut self,
+        ui: &mut egui::Ui,
+        node_id: NodeId,
+        value: &mut String,
+    ) -> Vec<ESDRResponse> {
+        let mut responses = vec![];
+        ui.horizontal(|ui| {
+            ui.label(&self.name);
+            // only send updates once the user is done typing
+            if ui.text_edit_singleline(value).lost_focus() {
+                responses.push(ESDRResponse::UpdateParam(UpdateParamPayload {
+                    node_id,
+                    field: self.name.to_string(),
+                    value: Pmt::String(value.clone()),
+                }));
+            }
+        });
+        responses
+    }
+}
+
+impl TextParamBuilder {
+    pub fn build(&self) -> Param {
+        Param::Text(self.build_impl().unwrap())
+    }
+}
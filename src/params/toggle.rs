@@ -0,0 +1,62 @@
+use crate::params::Param;
+use crate::params::ParamTrait;
+use crate::ui::ESDRDataType;
+use crate::ui::ESDRGraph;
+use crate::ui::ESDRResponse;
+use crate::ui::ESDRValueType;
+use crate::ui::UpdateParamPayload;
+
+use eframe::egui;
+use egui_node_graph::InputParamKind;
+use egui_node_graph::NodeId;
+use futuresdr::runtime::Pmt;
+
+#[derive(Default, Clone, Builder, Debug)]
+#[builder(public, setter(into), build_fn(private, name = "build_impl"))]
+pub struct ToggleParam {
+    pub name: String,
+    #[builder(default = "false")]
+    pub initial_value: bool,
+    #[builder(default = "false")]
+    pub allow_updates: bool,
+}
+
+impl ParamTrait<&mut bool> for ToggleParam {
+    fn add_param(self, graph: &mut ESDRGraph, node_id: NodeId) -> () {
+        graph.add_input_param(
+            node_id,
+            self.name.clone(),
+            ESDRDataType::Scalar,
+            ESDRValueType::Toggle {
+                node_id,
+                value: self.initial_value,
+                config: self,
+            },
+            InputParamKind::ConstantOnly,
+            true,
+        );
+    }
+
+    fn widget(
+        &mut self,
+        ui: &mut egui::Ui,
+        node_id: NodeId,
+        value: &mut bool,
+    ) -> Vec<ESDRResponse> {
+        let mut responses = vec![];
+        if ui.checkbox(value, &self.name).changed() {
+            responses.push(ESDRResponse::UpdateParam(UpdateParamPayload {
+                node_id,
+                field: self.name.to_string(),
+                value: Pmt::Bool(*value),
+            }));
+        }
+        responses
+    }
+}
+
+impl ToggleParamBuilder {
+    pub fn build(&self) -> Param {
+        Param::Toggle(self.build_impl().unwrap())
+    }
+}
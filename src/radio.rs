@@ -1,53 +1,312 @@
 use crate::blocks::ESDRBlock;
 use crate::blocks::ESDRBlockInput;
+use crate::console::NodeContext;
+use crate::console::Severity;
+use crate::kernels::soapy_source::SyncGroup;
+use crate::stats::FlowgraphStats;
+use crate::stats::NodeStats;
+use crate::stats::WorkTimer;
+use crate::ui::ESDRDataType;
 use crate::ui::ESDRGraph;
-use crate::ui::ESDRValueType;
+use crate::ui::ESDRNodeData;
 
 use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
-use async_task::Task;
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use eframe::egui;
+use egui_node_graph::InputId;
+use egui_node_graph::Node;
 use egui_node_graph::NodeId;
+use egui_node_graph::OutputId;
 use futuresdr::async_io;
-use futuresdr::runtime::scheduler::SmolScheduler;
+use futuresdr::runtime::Block;
 use futuresdr::runtime::Flowgraph;
 use futuresdr::runtime::FlowgraphHandle;
 use futuresdr::runtime::Pmt;
 use futuresdr::runtime::Runtime;
 
-#[allow(dead_code)]
+/// how often the UI is refreshed to show new statistics while running
+const STATS_INTERVAL: Duration = Duration::from_millis(250);
+/// message input of the blocks which transmit, told when TX is (dis)armed
+pub const TX_ARM_INPUT: &str = "tx arm";
+
+#[derive(Clone, Debug)]
+pub enum RadioState {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    Failed(String),
+}
+
+impl RadioState {
+    pub fn is_transitioning(&self) -> bool {
+        matches!(self, RadioState::Starting | RadioState::Stopping)
+    }
+}
+
+/// Safety interlock for transmitting: sinks only transmit while it is armed.
+#[derive(Clone, Default)]
+pub struct TxArm(Arc<AtomicBool>);
+
+impl TxArm {
+    pub fn is_armed(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, armed: bool) {
+        self.0.store(armed, Ordering::Relaxed);
+    }
+}
+
+/// Run on the thread driving the flowgraph before starting it, e.g. to make
+/// sure that a device can be opened.
+type Check = Box<dyn FnOnce() -> Result<()> + Send>;
+
+/// A message for a block of the running flowgraph: the node it is reported
+/// on, the block and message input ids, and the value.
+type Call = (NodeId, usize, usize, Pmt);
+
+/// State shared between the UI thread and the thread driving the flowgraph.
+struct Shared {
+    state: RadioState,
+    handle: Option<FlowgraphHandle>,
+    node_status: HashMap<NodeId, (Severity, String)>,
+    node_info: HashMap<NodeId, Vec<(String, String)>>,
+    events: Vec<(Severity, NodeId, String)>,
+    failure: Option<String>,
+    sync_groups: HashMap<String, Arc<SyncGroup>>,
+    tx_arm: TxArm,
+    checks: Vec<(Arc<NodeContext>, Check)>,
+}
+
+impl Shared {
+    fn new(tx_arm: TxArm) -> Shared {
+        Shared {
+            state: RadioState::Starting,
+            handle: None,
+            node_status: HashMap::new(),
+            node_info: HashMap::new(),
+            events: vec![],
+            failure: None,
+            sync_groups: HashMap::new(),
+            tx_arm,
+            checks: vec![],
+        }
+    }
+}
+
+/// Handed to blocks so that they can report problems on their node while the
+/// flowgraph is running.
+#[derive(Clone)]
+pub struct NodeStatus {
+    context: Arc<NodeContext>,
+    shared: Arc<Mutex<Shared>>,
+    ctx: egui::Context,
+    work_timer: WorkTimer,
+}
+
+impl NodeStatus {
+    fn report(&self, severity: Severity, error: &anyhow::Error) {
+        let node_id = self.context.node_id;
+        let message = format!("{:#}", error);
+        let mut shared = self.shared.lock().unwrap();
+        shared
+            .events
+            .push((severity, node_id, format!("{}: {}", self.context, message)));
+        shared.node_status.insert(node_id, (severity, message));
+        self.ctx.request_repaint();
+    }
+
+    /// Reports an error which made the block stop. The radio ends up in the
+    /// `Failed` state once the flowgraph is done.
+    pub fn fail(&self, error: &anyhow::Error) {
+        self.report(Severity::Error, error);
+        let mut shared = self.shared.lock().unwrap();
+        if shared.failure.is_none() {
+            shared.failure = Some(format!("{:#}", error));
+        }
+    }
+
+    pub fn warn(&self, error: &anyhow::Error) {
+        self.report(Severity::Warn, error);
+    }
+
+    /// Returns the group used to start several devices together. Groups are
+    /// shared by all the nodes of the running flowgraph.
+    pub fn sync_group(&self, name: &str) -> Arc<SyncGroup> {
+        self.shared
+            .lock()
+            .unwrap()
+            .sync_groups
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Publishes a value decoded by the block (e.g. the name of a station),
+    /// shown on its node and panel.
+    pub fn publish(&self, name: &str, value: &str) {
+        let mut shared = self.shared.lock().unwrap();
+        let info = shared.node_info.entry(self.context.node_id).or_default();
+        match info.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => info.push((name.to_string(), value.to_string())),
+        }
+        self.ctx.request_repaint();
+    }
+
+    /// Registers a check made before the flowgraph starts. If it fails, the
+    /// error is reported on the node and the flowgraph isn't started at all.
+    /// Checks run off the UI thread, so they can take a while.
+    pub fn check_before_start(&self, check: impl FnOnce() -> Result<()> + Send + 'static) {
+        self.shared
+            .lock()
+            .unwrap()
+            .checks
+            .push((self.context.clone(), Box::new(check)));
+    }
+
+    /// Returns the timer measuring the work calls of the block.
+    pub fn work_timer(&self) -> WorkTimer {
+        self.work_timer.clone()
+    }
+
+    pub fn tx_arm(&self) -> TxArm {
+        self.shared.lock().unwrap().tx_arm.clone()
+    }
+
+    pub fn clear(&self) {
+        self.shared
+            .lock()
+            .unwrap()
+            .node_status
+            .remove(&self.context.node_id);
+        self.ctx.request_repaint();
+    }
+}
+
 pub struct Radio {
-    running: Option<(
-        Task<Result<Flowgraph, anyhow::Error>>,
-        FlowgraphHandle,
-        Runtime<SmolScheduler>,
-    )>,
+    shared: Arc<Mutex<Shared>>,
     node_id_to_block_id: HashMap<NodeId, usize>,
     message_id_for_field: HashMap<(NodeId, String), usize>,
+    /// blocks to tell when TX is (dis)armed, along with their node and
+    /// message input
+    tx_arm_inputs: Vec<(NodeId, usize, usize)>,
+    /// messages are sent from their own thread, so that a busy flowgraph
+    /// doesn't block the UI
+    calls: mpsc::Sender<Call>,
+    stats: FlowgraphStats,
+}
+
+fn input_name(graph: &ESDRGraph, input_id: InputId) -> Result<String> {
+    let node = &graph[graph.get_input(input_id).node];
+    node.inputs
+        .iter()
+        .find(|(_, id)| *id == input_id)
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| anyhow!("Unknown input {:?}", input_id))
+}
+
+fn output_name(graph: &ESDRGraph, output_id: OutputId) -> Result<String> {
+    let node = &graph[graph.get_output(output_id).node];
+    node.outputs
+        .iter()
+        .find(|(_, id)| *id == output_id)
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| anyhow!("Unknown output {:?}", output_id))
+}
+
+/// Checks that the stream ports of a node match the ones of the block built
+/// for it, so that mismatches are reported before connecting anything.
+fn validate_ports(graph: &ESDRGraph, node: &Node<ESDRNodeData>, block: &Block) -> Result<()> {
+    for (name, input_id) in &node.inputs {
+        if let ESDRDataType::Stream(item_type) = graph.get_input(*input_id).typ {
+            if block.stream_input_name_to_id(name).is_none() {
+                bail!("Block has no stream input named \"{}\"", name);
+            }
+            let output_id = match graph.connections.get(*input_id) {
+                Some(output_id) => *output_id,
+                None => bail!("Input \"{}\" is not connected", name),
+            };
+            // the editor only connects matching types, but saved flowgraphs
+            // may not
+            if let ESDRDataType::Stream(output_type) = graph.get_output(output_id).typ {
+                if output_type != item_type {
+                    bail!(
+                        "Input \"{}\" takes {} items, but is connected to {} items",
+                        name,
+                        item_type.name(),
+                        output_type.name()
+                    );
+                }
+            }
+        }
+    }
+    for (name, output_id) in &node.outputs {
+        let connected = graph.connections.values().any(|o| o == output_id);
+        if connected && block.stream_output_name_to_id(name).is_none() {
+            bail!("Block has no stream output named \"{}\"", name);
+        }
+    }
+    Ok(())
 }
 
-pub fn start(graph: &ESDRGraph) -> Radio {
+/// Starts the flowgraph of the graph. With `probe_edges`, the throughput and
+/// buffer fill of every edge are measured too.
+pub fn start(
+    graph: &ESDRGraph,
+    ctx: &egui::Context,
+    tx_arm: TxArm,
+    probe_edges: bool,
+) -> Result<Radio> {
     let mut fg = Flowgraph::new();
     let mut node_id_to_block_id = HashMap::new();
     let mut message_id_for_field = HashMap::new();
+    let mut tx_arm_inputs = vec![];
+    let mut stats = FlowgraphStats::default();
+    let shared = Arc::new(Mutex::new(Shared::new(tx_arm)));
 
     for node in &graph.nodes {
-        let input = ESDRBlockInput::new(&graph, &node.1);
-        let block = node.1.user_data.block_type.block(input);
+        let status = NodeStatus {
+            context: Arc::new(NodeContext::new(graph, node.0)),
+            shared: shared.clone(),
+            ctx: ctx.clone(),
+            work_timer: stats.work_timer(node.0),
+        };
+        let input = ESDRBlockInput::new(&graph, &node.1, Some(status));
+        let block = node
+            .1
+            .user_data
+            .block_type
+            .block(input)
+            .and_then(|block| validate_ports(graph, node.1, &block).map(|_| block))
+            .with_context(|| NodeContext::new(graph, node.0))?;
         for (name, input_id) in &node.1.inputs {
-            let input = &graph.get_input(input_id.clone()).value;
-            let allow_updates = match input {
-                ESDRValueType::Scalar { config, .. } => config.allow_updates,
-                _ => false,
-            };
-            if allow_updates {
+            if graph.get_input(input_id.clone()).value.allow_updates() {
                 let message_id = block
                     .message_input_name_to_id(name)
-                    .expect(format!("Expected to find {}", name).as_str());
+                    .ok_or_else(|| anyhow!("Block has no message input for {}", name))
+                    .with_context(|| NodeContext::new(graph, node.0))?;
                 message_id_for_field.insert((node.0, name.clone()), message_id);
             }
         }
+        let tx_arm_input = block.message_input_name_to_id(TX_ARM_INPUT);
         let block_id = fg.add_block(block);
         node_id_to_block_id.insert(node.0, block_id);
+        if let Some(message_id) = tx_arm_input {
+            tx_arm_inputs.push((node.0, block_id, message_id));
+        }
     }
 
     for (input_id, output_id) in &graph.connections {
@@ -55,43 +314,366 @@ pub fn start(graph: &ESDRGraph) -> Radio {
         let output = graph.get_output(output_id.clone());
         let src = node_id_to_block_id[&output.node];
         let dest = node_id_to_block_id[&input.node];
-        fg.connect_stream(src, "out", dest, "in").unwrap();
+        let item_size = match output.typ {
+            ESDRDataType::Stream(item_type) => item_type.item_size(),
+            _ => bail!("Unexpected connection type"),
+        };
+        let src_port = output_name(graph, *output_id)?;
+        let dest_port = input_name(graph, input_id)?;
+        if !probe_edges {
+            fg.connect_stream(src, &src_port, dest, &dest_port)
+                .with_context(|| NodeContext::new(graph, input.node))?;
+            continue;
+        }
+        let probe = fg.add_block(stats.probe(
+            (output.node, src_port.clone()),
+            (input.node, dest_port.clone()),
+            item_size,
+        ));
+        fg.connect_stream(src, &src_port, probe, "in")
+            .with_context(|| NodeContext::new(graph, output.node))?;
+        fg.connect_stream(probe, "out", dest, &dest_port)
+            .with_context(|| NodeContext::new(graph, input.node))?;
     }
 
-    // TODO: turn this into an async function instead of blocking
-    let runtime = Runtime::new();
-    let (task, handle) = async_io::block_on(runtime.start(fg));
+    run(fg, shared.clone(), ctx.clone());
+    refresh_stats(shared.clone(), ctx.clone());
+    let calls = send_calls(shared.clone(), ctx.clone());
 
-    return Radio {
-        running: Some((task, handle, runtime)),
+    Ok(Radio {
+        shared,
         node_id_to_block_id,
         message_id_for_field,
-    };
+        tx_arm_inputs,
+        calls,
+        stats,
+    })
+}
+
+/// Runs the checks registered by the blocks, reporting failures on their node.
+/// Returns whether they all passed.
+fn run_checks(shared: &Arc<Mutex<Shared>>, ctx: &egui::Context) -> bool {
+    let checks = std::mem::take(&mut shared.lock().unwrap().checks);
+    let mut passed = true;
+    for (context, check) in checks {
+        if let Err(e) = check() {
+            let status = NodeStatus {
+                context,
+                shared: shared.clone(),
+                ctx: ctx.clone(),
+                work_timer: WorkTimer::default(),
+            };
+            status.fail(&e);
+            passed = false;
+        }
+    }
+    passed
+}
+
+/// Runs the flowgraph on its own thread, so that opening devices and draining
+/// the flowgraph don't block the UI.
+fn run(fg: Flowgraph, shared: Arc<Mutex<Shared>>, ctx: egui::Context) {
+    thread::spawn(move || {
+        if !run_checks(&shared, &ctx) {
+            let mut shared = shared.lock().unwrap();
+            shared.tx_arm.set(false);
+            let failure = shared.failure.take().unwrap_or_default();
+            shared.state = RadioState::Failed(failure);
+            ctx.request_repaint();
+            return;
+        }
+        let runtime = Runtime::new();
+        let result = async_io::block_on(async {
+            let (task, mut handle) = runtime.start(fg).await;
+            let stop_requested = {
+                let mut shared = shared.lock().unwrap();
+                shared.handle = Some(handle.clone());
+                match shared.state {
+                    RadioState::Stopping => true,
+                    _ => {
+                        shared.state = RadioState::Running;
+                        false
+                    }
+                }
+            };
+            ctx.request_repaint();
+            if stop_requested {
+                handle.terminate().await?;
+            }
+            task.await
+        });
+
+        let mut shared = shared.lock().unwrap();
+        shared.handle = None;
+        // never leave TX armed for the next run
+        shared.tx_arm.set(false);
+        shared.state = match result {
+            Ok(_) => match shared.failure.take() {
+                Some(failure) => RadioState::Failed(failure),
+                None => RadioState::Stopped,
+            },
+            Err(e) => {
+                log::error!("Flowgraph terminated with an error: {:#}", e);
+                RadioState::Failed(format!("{:#}", e))
+            }
+        };
+        ctx.request_repaint();
+    });
+}
+
+/// Sends messages to the blocks of the running flowgraph, in order, until the
+/// radio is dropped. Failures are reported on the node the message was for.
+fn send_calls(shared: Arc<Mutex<Shared>>, ctx: egui::Context) -> mpsc::Sender<Call> {
+    let (sender, receiver) = mpsc::channel::<Call>();
+    thread::spawn(move || {
+        for (node_id, block_id, port_id, value) in receiver {
+            let handle = shared.lock().unwrap().handle.clone();
+            // the flowgraph isn't running (yet, or anymore)
+            let mut handle = match handle {
+                Some(handle) => handle,
+                None => continue,
+            };
+            if let Err(e) = async_io::block_on(handle.call(block_id, port_id, value)) {
+                let message = format!("Failed to send a message to the block: {:#}", e);
+                shared
+                    .lock()
+                    .unwrap()
+                    .events
+                    .push((Severity::Error, node_id, message));
+                ctx.request_repaint();
+            }
+        }
+    });
+    sender
+}
+
+/// Repaints the UI now and then while the flowgraph runs, so that the
+/// statistics shown on the nodes stay current without repainting every frame.
+fn refresh_stats(shared: Arc<Mutex<Shared>>, ctx: egui::Context) {
+    thread::spawn(move || loop {
+        thread::sleep(STATS_INTERVAL);
+        match shared.lock().unwrap().state {
+            RadioState::Running => ctx.request_repaint(),
+            RadioState::Starting | RadioState::Stopping => {}
+            RadioState::Stopped | RadioState::Failed(_) => break,
+        }
+    });
 }
 
 impl Radio {
+    pub fn state(&self) -> RadioState {
+        self.shared.lock().unwrap().state.clone()
+    }
+
     pub fn stop(&mut self) {
-        // TODO: turn this into an async function
-        if let Some((task, mut handle, _)) = self.running.take() {
-            async_io::block_on(async move {
-                handle.terminate().await.unwrap();
-                task.await.unwrap();
+        self.set_tx_arm(false);
+        let mut shared = self.shared.lock().unwrap();
+        match shared.state {
+            RadioState::Starting | RadioState::Running => {}
+            _ => return,
+        }
+        shared.state = RadioState::Stopping;
+        // if the flowgraph is still starting up, it gets terminated as soon as
+        // it is up and running
+        if let Some(mut handle) = shared.handle.clone() {
+            let shared = self.shared.clone();
+            thread::spawn(move || {
+                if let Err(e) = async_io::block_on(handle.terminate()) {
+                    log::error!("Failed to terminate the flowgraph: {:#}", e);
+                    shared.lock().unwrap().state = RadioState::Failed(format!("{:#}", e));
+                }
             });
         }
     }
 
-    pub fn update_scalar(&mut self, node_id: NodeId, field: &str, value: f64) {
-        if let Some(ref mut running) = self.running {
-            let port_id = self.message_id_for_field[&(node_id, field.to_string())];
-            let block_id = self.node_id_to_block_id[&node_id];
-            // FIXME this is super hacky
-            let freq_offset = 250000.0;
-            async_io::block_on(
-                running
-                    .1
-                    .call(block_id, port_id, Pmt::Double(value + freq_offset)),
-            )
-            .unwrap();
+    pub fn node_status(&self) -> HashMap<NodeId, (Severity, String)> {
+        self.shared.lock().unwrap().node_status.clone()
+    }
+
+    pub fn node_info(&self) -> HashMap<NodeId, Vec<(String, String)>> {
+        self.shared.lock().unwrap().node_info.clone()
+    }
+
+    pub fn take_events(&self) -> Vec<(Severity, NodeId, String)> {
+        std::mem::take(&mut self.shared.lock().unwrap().events)
+    }
+
+    pub fn stats(&mut self) -> HashMap<NodeId, NodeStats> {
+        self.stats.refresh()
+    }
+
+    /// Arms or disarms TX, and tells the transmitting blocks right away so
+    /// that they don't wait for their next samples to follow.
+    pub fn set_tx_arm(&mut self, armed: bool) {
+        self.shared.lock().unwrap().tx_arm.set(armed);
+        for (node_id, block_id, message_id) in &self.tx_arm_inputs {
+            self.send(*node_id, *block_id, *message_id, Pmt::Bool(armed));
         }
     }
+
+    pub fn update_param(&mut self, node_id: NodeId, field: &str, value: Pmt) {
+        let port_id = match self.message_id_for_field.get(&(node_id, field.to_string())) {
+            Some(port_id) => *port_id,
+            // changes to other parameters are picked up on the next start
+            None => return,
+        };
+        if let Some(block_id) = self.node_id_to_block_id.get(&node_id) {
+            self.send(node_id, *block_id, port_id, value);
+        }
+    }
+
+    fn send(&self, node_id: NodeId, block_id: usize, port_id: usize, value: Pmt) {
+        // the thread sending them only ends along with the radio
+        let _ = self.calls.send((node_id, block_id, port_id, value));
+    }
+}
+
+#[cfg(test)]
+impl NodeStatus {
+    /// A status which isn't tied to a running radio, for testing kernels.
+    pub fn detached(tx_arm: TxArm) -> NodeStatus {
+        NodeStatus {
+            context: Arc::new(NodeContext {
+                node_id: NodeId::default(),
+                label: "Test".to_string(),
+            }),
+            shared: Arc::new(Mutex::new(Shared::new(tx_arm))),
+            ctx: egui::Context::default(),
+            work_timer: WorkTimer::default(),
+        }
+    }
+
+    /// Returns what was last reported on the node, if anything.
+    pub fn reported(&self) -> Option<(Severity, String)> {
+        self.shared
+            .lock()
+            .unwrap()
+            .node_status
+            .get(&self.context.node_id)
+            .cloned()
+    }
+
+    /// Runs the checks registered so far, like the radio does before starting.
+    pub fn run_checks(&self) -> bool {
+        run_checks(&self.shared, &self.ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::blocks::ESDRBlockType;
+
+    use egui_node_graph::NodeTemplateTrait;
+
+    fn add_node(graph: &mut ESDRGraph, block_type: ESDRBlockType) -> NodeId {
+        graph.add_node(
+            block_type.node_graph_label(),
+            block_type.user_data(),
+            |graph, node_id| block_type.build_node(graph, node_id),
+        )
+    }
+
+    fn connect(graph: &mut ESDRGraph, from: (NodeId, &str), to: (NodeId, &str)) {
+        let output_id = graph[from.0].get_output(from.1).unwrap();
+        let input_id = graph[to.0].get_input(to.1).unwrap();
+        graph.add_connection(output_id, input_id);
+    }
+
+    /// A float source feeding a sink.
+    fn flowgraph() -> (ESDRGraph, NodeId, NodeId) {
+        let mut graph = ESDRGraph::new();
+        let source = add_node(
+            &mut graph,
+            ESDRBlockType::FloatNullSource(Default::default()),
+        );
+        let sink = add_node(&mut graph, ESDRBlockType::FloatNullSink(Default::default()));
+        connect(&mut graph, (source, "out"), (sink, "in"));
+        (graph, source, sink)
+    }
+
+    fn edges(radio: &mut Radio) -> Vec<(NodeId, usize, usize)> {
+        let mut edges: Vec<_> = radio
+            .stats()
+            .into_iter()
+            .map(|(node_id, stats)| (node_id, stats.inputs.len(), stats.outputs.len()))
+            .filter(|(_, inputs, outputs)| inputs + outputs > 0)
+            .collect();
+        edges.sort_by_key(|(_, inputs, _)| *inputs);
+        edges
+    }
+
+    fn validate(graph: &ESDRGraph, node_id: NodeId) -> Result<()> {
+        let node = &graph[node_id];
+        let input = ESDRBlockInput::new(graph, node, None);
+        let block = node.user_data.block_type.block(input)?;
+        validate_ports(graph, node, &block)
+    }
+
+    #[test]
+    fn accepts_connected_ports() {
+        let mut graph = ESDRGraph::new();
+        let source = add_node(&mut graph, ESDRBlockType::NullSource(Default::default()));
+        let split = add_node(&mut graph, ESDRBlockType::IQSplit(Default::default()));
+        let add = add_node(&mut graph, ESDRBlockType::FloatAdd(Default::default()));
+        connect(&mut graph, (source, "out"), (split, "in"));
+        connect(&mut graph, (split, "i"), (add, "in 1"));
+        connect(&mut graph, (split, "q"), (add, "in 2"));
+        for node_id in [source, split, add] {
+            validate(&graph, node_id).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_unconnected_inputs() {
+        let mut graph = ESDRGraph::new();
+        let source = add_node(&mut graph, ESDRBlockType::NullSource(Default::default()));
+        let add = add_node(&mut graph, ESDRBlockType::Add(Default::default()));
+        connect(&mut graph, (source, "out"), (add, "in 1"));
+        let error = validate(&graph, add).unwrap_err();
+        assert_eq!(error.to_string(), "Input \"in 2\" is not connected");
+    }
+
+    #[test]
+    fn rejects_mismatched_stream_types() {
+        // as a saved flowgraph could have it, the editor doesn't allow it
+        let mut graph = ESDRGraph::new();
+        let source = add_node(
+            &mut graph,
+            ESDRBlockType::FloatNullSource(Default::default()),
+        );
+        let add = add_node(&mut graph, ESDRBlockType::Add(Default::default()));
+        connect(&mut graph, (source, "out"), (add, "in 1"));
+        connect(&mut graph, (source, "out"), (add, "in 2"));
+        let error = validate(&graph, add).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Input \"in 1\" takes complex items, but is connected to float items"
+        );
+    }
+
+    #[test]
+    fn probes_edges_only_when_asked() {
+        let (graph, source, sink) = flowgraph();
+        let ctx = egui::Context::default();
+
+        let mut radio = start(&graph, &ctx, TxArm::default(), false).unwrap();
+        assert!(edges(&mut radio).is_empty());
+        radio.stop();
+
+        let mut radio = start(&graph, &ctx, TxArm::default(), true).unwrap();
+        assert_eq!(edges(&mut radio), vec![(source, 0, 1), (sink, 1, 0)]);
+        radio.stop();
+    }
+
+    #[test]
+    fn times_the_work_of_every_node() {
+        let (graph, source, sink) = flowgraph();
+        let mut radio = start(&graph, &egui::Context::default(), TxArm::default(), false).unwrap();
+        let stats = radio.stats();
+        assert!(stats.contains_key(&source));
+        assert!(stats.contains_key(&sink));
+        radio.stop();
+    }
 }
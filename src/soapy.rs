@@ -0,0 +1,832 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Result;
+use futuresdr::num_complex::Complex32;
+use soapysdr::ErrorCode;
+
+pub use soapysdr::Direction;
+
+const READ_TIMEOUT_US: i64 = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub label: String,
+    /// arguments that can be used to open this specific device
+    pub args: String,
+}
+
+/// What a device reports it supports on a given channel.
+#[derive(Clone, Debug, Default)]
+pub struct Capabilities {
+    pub num_channels: usize,
+    pub antennas: Vec<String>,
+    pub gain_elements: Vec<String>,
+}
+
+/// Settings applied to a channel when opening a device. Empty or zero values
+/// leave the driver defaults alone.
+#[derive(Clone, Debug, Default)]
+pub struct ChannelConfig {
+    pub freq: f64,
+    pub sample_rate: f64,
+    pub gain: f64,
+    /// gains of the individual elements (e.g. "LNA"), which are applied
+    /// instead of the overall gain if `element_gains` is set
+    pub gain_elements: Vec<(String, f64)>,
+    pub element_gains: bool,
+    pub antenna: String,
+    pub bandwidth: f64,
+    pub agc: bool,
+    pub ppm: f64,
+    pub dc_offset_auto: bool,
+    pub iq_balance_auto: bool,
+}
+
+pub enum StreamError {
+    /// no samples were available, try again
+    Timeout,
+    /// samples were dropped by the device, but the stream is still usable
+    Overflow,
+    /// the device ran out of samples to transmit
+    Underflow,
+    /// the stream is unusable (e.g. the device was unplugged)
+    Fatal(anyhow::Error),
+}
+
+/// Thin abstraction over SoapySDR, so that the blocks driving devices can be
+/// exercised against a fake driver instead of real hardware.
+pub trait SoapyDriver: Send + Sync {
+    fn enumerate(&self, args: &str) -> Result<Vec<DeviceInfo>>;
+    /// Opens a device, whose settings then apply to the given direction.
+    fn open(&self, args: &str, direction: Direction) -> Result<Box<dyn SoapyDevice>>;
+}
+
+/// Picks the driver able to open the device described by `args`.
+pub fn driver(args: &str) -> Arc<dyn SoapyDriver> {
+    if NullDriver::handles(args) {
+        Arc::new(NullDriver)
+    } else {
+        Arc::new(SoapySDRDriver)
+    }
+}
+
+/// Combines the arguments identifying a device with extra user-provided
+/// arguments, e.g. `driver=rtlsdr,serial=01` and `buffers=16`.
+pub fn join_args(device: &str, extra: &str) -> String {
+    [device.trim(), extra.trim()]
+        .iter()
+        .filter(|args| !args.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parses `key=value` pairs separated by commas, as used for device settings.
+pub fn parse_settings(settings: &str) -> Result<Vec<(String, String)>> {
+    settings
+        .split(',')
+        .map(|setting| setting.trim())
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| match setting.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+            None => Err(anyhow!("Expected key=value, got \"{}\"", setting)),
+        })
+        .collect()
+}
+
+pub trait SoapyDevice: Send {
+    fn capabilities(&self, channel: usize) -> Result<Capabilities>;
+    fn set_frequency(&mut self, channel: usize, freq: f64) -> Result<()>;
+    fn set_sample_rate(&mut self, channel: usize, rate: f64) -> Result<()>;
+    fn set_gain(&mut self, channel: usize, gain: f64) -> Result<()>;
+    fn set_gain_element(&mut self, channel: usize, element: &str, gain: f64) -> Result<()>;
+    fn set_antenna(&mut self, channel: usize, antenna: &str) -> Result<()>;
+    fn set_bandwidth(&mut self, channel: usize, bandwidth: f64) -> Result<()>;
+    fn set_agc(&mut self, channel: usize, enabled: bool) -> Result<()>;
+    fn set_ppm(&mut self, channel: usize, ppm: f64) -> Result<()>;
+    fn set_dc_offset_mode(&mut self, channel: usize, automatic: bool) -> Result<()>;
+    fn set_iq_balance_mode(&mut self, channel: usize, automatic: bool) -> Result<()>;
+    fn write_setting(&mut self, key: &str, value: &str) -> Result<()>;
+    fn has_hardware_time(&self) -> Result<bool>;
+    /// Sets the hardware time once the next PPS edge comes in.
+    fn set_hardware_time_at_pps(&mut self, time_ns: i64) -> Result<()>;
+    /// Sets up a stream reading the given channels coherently. The stream has
+    /// to be activated before reading from it.
+    fn rx_stream(&mut self, channels: &[usize]) -> Result<Box<dyn SoapyRxStream>>;
+    fn tx_stream(&mut self, channel: usize) -> Result<Box<dyn SoapyTxStream>>;
+
+    fn configure(&mut self, channel: usize, config: &ChannelConfig) -> Result<()> {
+        self.set_sample_rate(channel, config.sample_rate)?;
+        self.set_frequency(channel, config.freq)?;
+        if !config.antenna.is_empty() {
+            self.set_antenna(channel, &config.antenna)?;
+        }
+        if config.bandwidth > 0.0 {
+            self.set_bandwidth(channel, config.bandwidth)?;
+        }
+        self.set_agc(channel, config.agc)?;
+        if !config.agc {
+            self.set_manual_gain(channel, config)?;
+        }
+        if config.ppm != 0.0 {
+            self.set_ppm(channel, config.ppm)?;
+        }
+        self.set_dc_offset_mode(channel, config.dc_offset_auto)?;
+        self.set_iq_balance_mode(channel, config.iq_balance_auto)?;
+        Ok(())
+    }
+
+    /// Sets either the overall gain or the gain of each element.
+    fn set_manual_gain(&mut self, channel: usize, config: &ChannelConfig) -> Result<()> {
+        if !config.element_gains {
+            return self.set_gain(channel, config.gain);
+        }
+        for (element, gain) in &config.gain_elements {
+            self.set_gain_element(channel, element, *gain)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait SoapyRxStream: Send {
+    /// Starts streaming, at the given hardware time if any.
+    fn activate(&mut self, time_ns: Option<i64>) -> Result<()>;
+    /// Reads the same number of samples into each buffer, one per channel.
+    fn read(&mut self, bufs: &mut [&mut [Complex32]]) -> Result<usize, StreamError>;
+}
+
+pub trait SoapyTxStream: Send {
+    fn activate(&mut self) -> Result<()>;
+    fn deactivate(&mut self) -> Result<()>;
+    fn write(&mut self, buf: &[Complex32]) -> Result<usize, StreamError>;
+}
+
+/// The actual SoapySDR library.
+pub struct SoapySDRDriver;
+
+impl SoapyDriver for SoapySDRDriver {
+    fn enumerate(&self, args: &str) -> Result<Vec<DeviceInfo>> {
+        let devices =
+            soapysdr::enumerate(args).map_err(|e| anyhow!("Failed to enumerate devices: {}", e))?;
+        Ok(devices
+            .iter()
+            .map(|device| {
+                let get = |key: &str| device.get(key).unwrap_or("").to_string();
+                let driver = get("driver");
+                let serial = get("serial");
+                let label = match device.get("label") {
+                    Some(label) => label.to_string(),
+                    None => format!("{} {}", driver, serial),
+                };
+                let mut args = vec![format!("driver={}", driver)];
+                if !serial.is_empty() {
+                    args.push(format!("serial={}", serial));
+                }
+                DeviceInfo {
+                    label,
+                    args: args.join(","),
+                }
+            })
+            .collect())
+    }
+
+    fn open(&self, args: &str, direction: Direction) -> Result<Box<dyn SoapyDevice>> {
+        let device = soapysdr::Device::new(args)
+            .map_err(|e| anyhow!("Failed to open device \"{}\": {}", args, e))?;
+        Ok(Box::new(SoapySDRDevice { device, direction }))
+    }
+}
+
+struct SoapySDRDevice {
+    device: soapysdr::Device,
+    direction: Direction,
+}
+
+impl SoapyDevice for SoapySDRDevice {
+    fn capabilities(&self, channel: usize) -> Result<Capabilities> {
+        let num_channels = self.device.num_channels(self.direction)?;
+        if channel >= num_channels {
+            bail!(
+                "Channel {} doesn't exist, the device has {} channel(s)",
+                channel,
+                num_channels
+            );
+        }
+        Ok(Capabilities {
+            num_channels,
+            antennas: self.device.antennas(self.direction, channel)?,
+            gain_elements: self.device.list_gains(self.direction, channel)?,
+        })
+    }
+
+    fn set_frequency(&mut self, channel: usize, freq: f64) -> Result<()> {
+        self.device
+            .set_frequency(self.direction, channel, freq, ())?;
+        Ok(())
+    }
+
+    fn set_sample_rate(&mut self, channel: usize, rate: f64) -> Result<()> {
+        let ranges = self.device.get_sample_rate_range(self.direction, channel)?;
+        if !ranges.is_empty()
+            && !ranges
+                .iter()
+                .any(|range| range.minimum <= rate && rate <= range.maximum)
+        {
+            bail!("Sample rate {} is not supported by the device", rate);
+        }
+        self.device.set_sample_rate(self.direction, channel, rate)?;
+        Ok(())
+    }
+
+    fn set_gain(&mut self, channel: usize, gain: f64) -> Result<()> {
+        self.device.set_gain(self.direction, channel, gain)?;
+        Ok(())
+    }
+
+    fn set_gain_element(&mut self, channel: usize, element: &str, gain: f64) -> Result<()> {
+        self.device
+            .set_gain_element(self.direction, channel, element, gain)?;
+        Ok(())
+    }
+
+    fn set_antenna(&mut self, channel: usize, antenna: &str) -> Result<()> {
+        self.device.set_antenna(self.direction, channel, antenna)?;
+        Ok(())
+    }
+
+    fn set_bandwidth(&mut self, channel: usize, bandwidth: f64) -> Result<()> {
+        self.device
+            .set_bandwidth(self.direction, channel, bandwidth)?;
+        Ok(())
+    }
+
+    fn set_agc(&mut self, channel: usize, enabled: bool) -> Result<()> {
+        if self.device.has_gain_mode(self.direction, channel)? {
+            self.device
+                .set_gain_mode(self.direction, channel, enabled)?;
+        } else if enabled {
+            bail!("The device doesn't support AGC");
+        }
+        Ok(())
+    }
+
+    fn set_ppm(&mut self, channel: usize, ppm: f64) -> Result<()> {
+        if self
+            .device
+            .has_frequency_correction(self.direction, channel)?
+        {
+            self.device
+                .set_frequency_correction(self.direction, channel, ppm)?;
+        } else if self
+            .device
+            .frequency_components(self.direction, channel)?
+            .iter()
+            .any(|component| component == "CORR")
+        {
+            // older drivers only expose it as the "CORR" component
+            self.device
+                .set_component_frequency(self.direction, channel, "CORR", ppm, ())?;
+        } else if ppm != 0.0 {
+            bail!("The device doesn't support frequency correction");
+        }
+        Ok(())
+    }
+
+    fn set_dc_offset_mode(&mut self, channel: usize, automatic: bool) -> Result<()> {
+        if self.device.has_dc_offset_mode(self.direction, channel)? {
+            self.device
+                .set_dc_offset_mode(self.direction, channel, automatic)?;
+        } else if automatic {
+            bail!("The device doesn't support automatic DC offset correction");
+        }
+        Ok(())
+    }
+
+    fn set_iq_balance_mode(&mut self, channel: usize, automatic: bool) -> Result<()> {
+        if self.device.has_iq_balance_mode(self.direction, channel)? {
+            self.device
+                .set_iq_balance_mode(self.direction, channel, automatic)?;
+        } else if automatic {
+            bail!("The device doesn't support automatic IQ balance correction");
+        }
+        Ok(())
+    }
+
+    fn write_setting(&mut self, key: &str, value: &str) -> Result<()> {
+        self.device.write_setting(key, value)?;
+        Ok(())
+    }
+
+    fn has_hardware_time(&self) -> Result<bool> {
+        Ok(self.device.has_hardware_time(None)?)
+    }
+
+    fn set_hardware_time_at_pps(&mut self, time_ns: i64) -> Result<()> {
+        self.device.set_hardware_time(Some("PPS"), time_ns)?;
+        Ok(())
+    }
+
+    fn rx_stream(&mut self, channels: &[usize]) -> Result<Box<dyn SoapyRxStream>> {
+        let stream = self.device.rx_stream::<Complex32>(channels)?;
+        Ok(Box::new(SoapySDRRxStream {
+            stream,
+            active: false,
+        }))
+    }
+
+    fn tx_stream(&mut self, channel: usize) -> Result<Box<dyn SoapyTxStream>> {
+        let stream = self.device.tx_stream::<Complex32>(&[channel])?;
+        Ok(Box::new(SoapySDRTxStream {
+            stream,
+            active: false,
+        }))
+    }
+}
+
+struct SoapySDRRxStream {
+    stream: soapysdr::RxStream<Complex32>,
+    active: bool,
+}
+
+impl SoapyRxStream for SoapySDRRxStream {
+    fn activate(&mut self, time_ns: Option<i64>) -> Result<()> {
+        self.stream.activate(time_ns)?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn read(&mut self, bufs: &mut [&mut [Complex32]]) -> Result<usize, StreamError> {
+        match self.stream.read(bufs, READ_TIMEOUT_US) {
+            Ok(n) => Ok(n),
+            Err(e) => match e.code {
+                ErrorCode::Timeout => Err(StreamError::Timeout),
+                ErrorCode::Overflow => Err(StreamError::Overflow),
+                _ => Err(StreamError::Fatal(anyhow!("Stream error: {}", e))),
+            },
+        }
+    }
+}
+
+impl Drop for SoapySDRRxStream {
+    fn drop(&mut self) {
+        if self.active {
+            let _ = self.stream.deactivate(None);
+        }
+    }
+}
+
+struct SoapySDRTxStream {
+    stream: soapysdr::TxStream<Complex32>,
+    active: bool,
+}
+
+impl SoapyTxStream for SoapySDRTxStream {
+    fn activate(&mut self) -> Result<()> {
+        self.stream.activate(None)?;
+        self.active = true;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<()> {
+        self.stream.deactivate(None)?;
+        self.active = false;
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &[Complex32]) -> Result<usize, StreamError> {
+        match self.stream.write(&[buf], None, false, READ_TIMEOUT_US) {
+            Ok(n) => Ok(n),
+            Err(e) => match e.code {
+                ErrorCode::Timeout => Err(StreamError::Timeout),
+                ErrorCode::Underflow => Err(StreamError::Underflow),
+                _ => Err(StreamError::Fatal(anyhow!("Stream error: {}", e))),
+            },
+        }
+    }
+}
+
+impl Drop for SoapySDRTxStream {
+    fn drop(&mut self) {
+        if self.active {
+            let _ = self.stream.deactivate(None);
+        }
+    }
+}
+
+/// Stand-in for a transmitting device, so that TX graphs can be tested
+/// without hardware. Opened with `driver=esdr-null`, so as not to shadow
+/// SoapySDR's own null device, it discards samples at the configured sample
+/// rate, or writes them to `file` as interleaved little endian 32 bit floats.
+pub struct NullDriver;
+
+impl NullDriver {
+    pub const ARGS: &'static str = "driver=esdr-null";
+
+    pub fn handles(args: &str) -> bool {
+        args.split(',').any(|arg| arg.trim() == NullDriver::ARGS)
+    }
+}
+
+impl SoapyDriver for NullDriver {
+    fn enumerate(&self, _args: &str) -> Result<Vec<DeviceInfo>> {
+        Ok(vec![DeviceInfo {
+            label: "Null sink".to_string(),
+            args: NullDriver::ARGS.to_string(),
+        }])
+    }
+
+    fn open(&self, args: &str, direction: Direction) -> Result<Box<dyn SoapyDevice>> {
+        if let Direction::Rx = direction {
+            bail!("The null device can only transmit");
+        }
+        let file = parse_settings(args)?
+            .into_iter()
+            .find(|(key, _)| key == "file")
+            .map(|(_, path)| path);
+        Ok(Box::new(NullDevice {
+            file,
+            sample_rate: 0.0,
+        }))
+    }
+}
+
+struct NullDevice {
+    file: Option<String>,
+    sample_rate: f64,
+}
+
+impl SoapyDevice for NullDevice {
+    fn capabilities(&self, channel: usize) -> Result<Capabilities> {
+        if channel > 0 {
+            bail!(
+                "Channel {} doesn't exist, the device has 1 channel(s)",
+                channel
+            );
+        }
+        Ok(Capabilities {
+            num_channels: 1,
+            antennas: vec![],
+            gain_elements: vec![],
+        })
+    }
+
+    fn set_frequency(&mut self, _channel: usize, _freq: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_sample_rate(&mut self, _channel: usize, rate: f64) -> Result<()> {
+        self.sample_rate = rate;
+        Ok(())
+    }
+
+    fn set_gain(&mut self, _channel: usize, _gain: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_gain_element(&mut self, _channel: usize, element: &str, _gain: f64) -> Result<()> {
+        bail!("Unknown gain element {}", element)
+    }
+
+    fn set_antenna(&mut self, _channel: usize, antenna: &str) -> Result<()> {
+        bail!("Unknown antenna {}", antenna)
+    }
+
+    fn set_bandwidth(&mut self, _channel: usize, _bandwidth: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_agc(&mut self, _channel: usize, enabled: bool) -> Result<()> {
+        if enabled {
+            bail!("The device doesn't support AGC");
+        }
+        Ok(())
+    }
+
+    fn set_ppm(&mut self, _channel: usize, _ppm: f64) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_dc_offset_mode(&mut self, _channel: usize, _automatic: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_iq_balance_mode(&mut self, _channel: usize, _automatic: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_setting(&mut self, key: &str, _value: &str) -> Result<()> {
+        bail!("Unknown setting {}", key)
+    }
+
+    fn has_hardware_time(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn set_hardware_time_at_pps(&mut self, _time_ns: i64) -> Result<()> {
+        bail!("The device has no hardware time")
+    }
+
+    fn rx_stream(&mut self, _channels: &[usize]) -> Result<Box<dyn SoapyRxStream>> {
+        bail!("The null device can only transmit")
+    }
+
+    fn tx_stream(&mut self, channel: usize) -> Result<Box<dyn SoapyTxStream>> {
+        self.capabilities(channel)?;
+        let file = match &self.file {
+            Some(path) => Some(BufWriter::new(
+                File::create(path).map_err(|e| anyhow!("Failed to create {}: {}", path, e))?,
+            )),
+            None => None,
+        };
+        Ok(Box::new(NullTxStream {
+            file,
+            sample_rate: self.sample_rate,
+            started: None,
+            written: 0,
+        }))
+    }
+}
+
+struct NullTxStream {
+    file: Option<BufWriter<File>>,
+    sample_rate: f64,
+    started: Option<Instant>,
+    written: u64,
+}
+
+impl SoapyTxStream for NullTxStream {
+    fn activate(&mut self) -> Result<()> {
+        self.started = Some(Instant::now());
+        self.written = 0;
+        Ok(())
+    }
+
+    fn deactivate(&mut self) -> Result<()> {
+        self.started = None;
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &[Complex32]) -> Result<usize, StreamError> {
+        let started = self
+            .started
+            .ok_or_else(|| StreamError::Fatal(anyhow!("The stream is not active")))?;
+        if let Some(file) = &mut self.file {
+            for sample in buf {
+                file.write_all(&sample.re.to_le_bytes())
+                    .and_then(|_| file.write_all(&sample.im.to_le_bytes()))
+                    .map_err(|e| StreamError::Fatal(e.into()))?;
+            }
+        }
+        self.written += buf.len() as u64;
+        // pace things like a device would
+        if self.sample_rate > 0.0 {
+            let due = Duration::from_secs_f64(self.written as f64 / self.sample_rate);
+            if let Some(ahead) = due.checked_sub(started.elapsed()) {
+                thread::sleep(ahead);
+            }
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Fake driver for testing the blocks driving devices without hardware. How
+/// the device behaves is controlled through its shared [`FakeState`].
+#[cfg(test)]
+pub mod fake {
+    use super::*;
+
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
+
+    pub const ARGS: &str = "driver=fake";
+
+    #[derive(Default)]
+    pub struct FakeState {
+        /// whether the device is there to be opened and streamed from
+        pub plugged: bool,
+        /// setting a higher sample rate fails, if set
+        pub max_sample_rate: Option<f64>,
+        /// reads that succeed before the device goes away, if set
+        pub reads_before_unplug: Option<usize>,
+        pub opened: usize,
+        pub freq: f64,
+        pub transmitting: bool,
+        pub transmitted: Vec<Complex32>,
+    }
+
+    #[derive(Clone, Default)]
+    pub struct FakeDriver(Arc<Mutex<FakeState>>);
+
+    impl FakeDriver {
+        pub fn plugged() -> FakeDriver {
+            let driver = FakeDriver::default();
+            driver.state().plugged = true;
+            driver
+        }
+
+        pub fn state(&self) -> MutexGuard<FakeState> {
+            self.0.lock().unwrap()
+        }
+    }
+
+    impl SoapyDriver for FakeDriver {
+        fn enumerate(&self, _args: &str) -> Result<Vec<DeviceInfo>> {
+            Ok(match self.state().plugged {
+                true => vec![DeviceInfo {
+                    label: "Fake device".to_string(),
+                    args: ARGS.to_string(),
+                }],
+                false => vec![],
+            })
+        }
+
+        fn open(&self, args: &str, _direction: Direction) -> Result<Box<dyn SoapyDevice>> {
+            let mut state = self.state();
+            if !state.plugged {
+                bail!("Failed to open device \"{}\": no device found", args);
+            }
+            state.opened += 1;
+            Ok(Box::new(FakeDevice(self.0.clone())))
+        }
+    }
+
+    struct FakeDevice(Arc<Mutex<FakeState>>);
+
+    impl SoapyDevice for FakeDevice {
+        fn capabilities(&self, channel: usize) -> Result<Capabilities> {
+            if channel > 0 {
+                bail!("Channel {} doesn't exist", channel);
+            }
+            Ok(Capabilities {
+                num_channels: 1,
+                antennas: vec!["RX".to_string()],
+                gain_elements: vec!["LNA".to_string()],
+            })
+        }
+
+        fn set_frequency(&mut self, _channel: usize, freq: f64) -> Result<()> {
+            self.0.lock().unwrap().freq = freq;
+            Ok(())
+        }
+
+        fn set_sample_rate(&mut self, _channel: usize, rate: f64) -> Result<()> {
+            match self.0.lock().unwrap().max_sample_rate {
+                Some(max) if rate > max => {
+                    bail!("Sample rate {} is not supported by the device", rate)
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn set_gain(&mut self, _channel: usize, _gain: f64) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_gain_element(&mut self, _channel: usize, _element: &str, _gain: f64) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_antenna(&mut self, _channel: usize, _antenna: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_bandwidth(&mut self, _channel: usize, _bandwidth: f64) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_agc(&mut self, _channel: usize, _enabled: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_ppm(&mut self, _channel: usize, _ppm: f64) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_dc_offset_mode(&mut self, _channel: usize, _automatic: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_iq_balance_mode(&mut self, _channel: usize, _automatic: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn write_setting(&mut self, _key: &str, _value: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn has_hardware_time(&self) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn set_hardware_time_at_pps(&mut self, _time_ns: i64) -> Result<()> {
+            bail!("The device has no hardware time")
+        }
+
+        fn rx_stream(&mut self, _channels: &[usize]) -> Result<Box<dyn SoapyRxStream>> {
+            Ok(Box::new(FakeStream(self.0.clone())))
+        }
+
+        fn tx_stream(&mut self, _channel: usize) -> Result<Box<dyn SoapyTxStream>> {
+            Ok(Box::new(FakeStream(self.0.clone())))
+        }
+    }
+
+    struct FakeStream(Arc<Mutex<FakeState>>);
+
+    impl FakeStream {
+        /// Fails like an unplugged device would, once out of reads.
+        fn check_plugged(state: &mut FakeState) -> Result<(), StreamError> {
+            match &mut state.reads_before_unplug {
+                Some(0) => state.plugged = false,
+                Some(reads) => *reads -= 1,
+                None => {}
+            }
+            match state.plugged {
+                true => Ok(()),
+                false => Err(StreamError::Fatal(anyhow!("Device unplugged"))),
+            }
+        }
+    }
+
+    impl SoapyRxStream for FakeStream {
+        fn activate(&mut self, _time_ns: Option<i64>) -> Result<()> {
+            Ok(())
+        }
+
+        fn read(&mut self, bufs: &mut [&mut [Complex32]]) -> Result<usize, StreamError> {
+            FakeStream::check_plugged(&mut self.0.lock().unwrap())?;
+            for buf in bufs.iter_mut() {
+                buf.fill(Complex32::new(1.0, 0.0));
+            }
+            Ok(bufs.iter().map(|buf| buf.len()).min().unwrap_or(0))
+        }
+    }
+
+    impl SoapyTxStream for FakeStream {
+        fn activate(&mut self) -> Result<()> {
+            self.0.lock().unwrap().transmitting = true;
+            Ok(())
+        }
+
+        fn deactivate(&mut self) -> Result<()> {
+            self.0.lock().unwrap().transmitting = false;
+            Ok(())
+        }
+
+        fn write(&mut self, buf: &[Complex32]) -> Result<usize, StreamError> {
+            let mut state = self.0.lock().unwrap();
+            FakeStream::check_plugged(&mut state)?;
+            if !state.transmitting {
+                return Err(StreamError::Fatal(anyhow!("The stream is not active")));
+            }
+            state.transmitted.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SoapySDR always comes with its own null device, so this runs wherever
+    /// the library is installed.
+    #[test]
+    fn opens_the_soapysdr_null_device() {
+        let devices = SoapySDRDriver.enumerate("driver=null").unwrap();
+        let device = devices.first().expect("the null device is always there");
+        assert_eq!(device.args, "driver=null");
+        assert!(SoapySDRDriver.open(&device.args, Direction::Rx).is_ok());
+    }
+
+    /// The null device has no frequency correction, so it takes no
+    /// correction and turns any other down.
+    #[test]
+    fn sets_the_ppm_only_where_supported() {
+        let mut device = SoapySDRDriver.open("driver=null", Direction::Rx).unwrap();
+        assert!(device.set_ppm(0, 0.0).is_ok());
+        assert!(device.set_ppm(0, 1.5).is_err());
+    }
+
+    #[test]
+    fn fails_to_open_missing_devices() {
+        assert!(SoapySDRDriver
+            .enumerate("driver=missing")
+            .unwrap()
+            .is_empty());
+        assert!(SoapySDRDriver
+            .open("driver=missing", Direction::Rx)
+            .is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use crate::kernels::probe::Probe;
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use egui_node_graph::NodeId;
+use futuresdr::runtime::Block;
+
+/// Counters updated by a [`Probe`] from within the flowgraph and read by the
+/// UI thread.
+#[derive(Default)]
+pub struct EdgeCounters {
+    pub items: AtomicU64,
+    pub queued: AtomicU64,
+    pub capacity: AtomicU64,
+    pub underflows: AtomicU64,
+    pub overflows: AtomicU64,
+}
+
+/// Time spent in the work calls of a block, updated from within the flowgraph
+/// and read by the UI thread.
+#[derive(Default)]
+pub struct WorkCounters {
+    pub calls: AtomicU64,
+    pub nanos: AtomicU64,
+}
+
+/// Handed to kernels to measure how long their work calls take. A timer
+/// which isn't registered with a running flowgraph measures nothing.
+#[derive(Clone, Default)]
+pub struct WorkTimer(Option<Arc<WorkCounters>>);
+
+impl WorkTimer {
+    /// Starts timing a work call, which ends when the returned guard is
+    /// dropped.
+    pub fn start(&self) -> WorkTiming {
+        WorkTiming {
+            counters: self.0.clone(),
+            start: Instant::now(),
+        }
+    }
+}
+
+pub struct WorkTiming {
+    counters: Option<Arc<WorkCounters>>,
+    start: Instant,
+}
+
+impl Drop for WorkTiming {
+    fn drop(&mut self) {
+        if let Some(counters) = &self.counters {
+            let nanos = self.start.elapsed().as_nanos() as u64;
+            counters.calls.fetch_add(1, Ordering::Relaxed);
+            counters.nanos.fetch_add(nanos, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EdgeStats {
+    /// items per second going through the edge
+    pub rate: f64,
+    /// how full the buffer towards the downstream block is, from 0 to 1
+    pub fill: f32,
+    pub underflows: u64,
+    pub overflows: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WorkStats {
+    /// average time spent in a work call
+    pub per_call: Duration,
+    /// share of the time spent in work calls, from 0 to 1
+    pub load: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NodeStats {
+    pub inputs: Vec<(String, EdgeStats)>,
+    pub outputs: Vec<(String, EdgeStats)>,
+    pub work: Option<WorkStats>,
+}
+
+struct Edge {
+    src: (NodeId, String),
+    dest: (NodeId, String),
+    counters: Arc<EdgeCounters>,
+    last_items: u64,
+    last_refresh: Instant,
+    rate: f64,
+}
+
+impl Edge {
+    fn refresh(&mut self) -> EdgeStats {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refresh).as_secs_f64();
+        let items = self.counters.items.load(Ordering::Relaxed);
+        if elapsed > 0.0 {
+            // smooth things out a bit, since the UI refreshes at an uneven pace
+            let rate = (items - self.last_items) as f64 / elapsed;
+            self.rate = 0.9 * self.rate + 0.1 * rate;
+        }
+        self.last_items = items;
+        self.last_refresh = now;
+
+        let capacity = self.counters.capacity.load(Ordering::Relaxed);
+        let queued = self.counters.queued.load(Ordering::Relaxed);
+        EdgeStats {
+            rate: self.rate,
+            fill: if capacity > 0 {
+                queued as f32 / capacity as f32
+            } else {
+                0.0
+            },
+            underflows: self.counters.underflows.load(Ordering::Relaxed),
+            overflows: self.counters.overflows.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Work {
+    node_id: NodeId,
+    counters: Arc<WorkCounters>,
+    last_calls: u64,
+    last_nanos: u64,
+    last_refresh: Instant,
+    stats: Option<WorkStats>,
+}
+
+impl Work {
+    fn refresh(&mut self) -> Option<WorkStats> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refresh).as_secs_f64();
+        let calls = self.counters.calls.load(Ordering::Relaxed);
+        let nanos = self.counters.nanos.load(Ordering::Relaxed);
+        if calls > self.last_calls && elapsed > 0.0 {
+            let spent = nanos - self.last_nanos;
+            self.stats = Some(WorkStats {
+                per_call: Duration::from_nanos(spent / (calls - self.last_calls)),
+                load: spent as f64 / 1e9 / elapsed,
+            });
+        }
+        self.last_calls = calls;
+        self.last_nanos = nanos;
+        self.last_refresh = now;
+        self.stats.clone()
+    }
+}
+
+/// Runtime statistics of a running flowgraph. FutureSDR doesn't expose
+/// per-block statistics, so the time spent in work calls is measured by the
+/// kernels of eSDR themselves, through a [`WorkTimer`]. Blocks from FutureSDR
+/// aren't timed, and neither are device sources and sinks, whose work calls
+/// mostly wait on the device. Throughput and buffer fill are only measured
+/// when asked for, by inserting a [`Probe`] on every edge, since each probe
+/// copies all the samples going through it.
+#[derive(Default)]
+pub struct FlowgraphStats {
+    edges: Vec<Edge>,
+    work: Vec<Work>,
+}
+
+impl FlowgraphStats {
+    pub fn probe(
+        &mut self,
+        src: (NodeId, String),
+        dest: (NodeId, String),
+        item_size: usize,
+    ) -> Block {
+        let counters = Arc::new(EdgeCounters::default());
+        self.edges.push(Edge {
+            src,
+            dest,
+            counters: counters.clone(),
+            last_items: 0,
+            last_refresh: Instant::now(),
+            rate: 0.0,
+        });
+        Probe::new(item_size, counters)
+    }
+
+    pub fn work_timer(&mut self, node_id: NodeId) -> WorkTimer {
+        let counters = Arc::new(WorkCounters::default());
+        self.work.push(Work {
+            node_id,
+            counters: counters.clone(),
+            last_calls: 0,
+            last_nanos: 0,
+            last_refresh: Instant::now(),
+            stats: None,
+        });
+        WorkTimer(Some(counters))
+    }
+
+    pub fn refresh(&mut self) -> HashMap<NodeId, NodeStats> {
+        let mut nodes: HashMap<NodeId, NodeStats> = HashMap::new();
+        for work in &mut self.work {
+            nodes.entry(work.node_id).or_default().work = work.refresh();
+        }
+        for edge in &mut self.edges {
+            let stats = edge.refresh();
+            nodes
+                .entry(edge.src.0)
+                .or_default()
+                .outputs
+                .push((edge.src.1.clone(), stats.clone()));
+            nodes
+                .entry(edge.dest.0)
+                .or_default()
+                .inputs
+                .push((edge.dest.1.clone(), stats));
+        }
+        nodes
+    }
+}
+
+pub fn format_rate(rate: f64) -> String {
+    if rate >= 1e6 {
+        format!("{:.2} MS/s", rate / 1e6)
+    } else if rate >= 1e3 {
+        format!("{:.1} kS/s", rate / 1e3)
+    } else {
+        format!("{:.0} S/s", rate)
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs >= 1e-3 {
+        format!("{:.1} ms", secs * 1e3)
+    } else {
+        format!("{:.0} µs", secs * 1e6)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn unregistered_timers_measure_nothing() {
+        let timer = WorkTimer::default();
+        drop(timer.start());
+        assert!(timer.0.is_none());
+    }
+
+    #[test]
+    fn sums_up_work_calls() {
+        let mut stats = FlowgraphStats::default();
+        let node_id = NodeId::default();
+        let timer = stats.work_timer(node_id);
+        assert!(stats.refresh()[&node_id].work.is_none());
+
+        for _ in 0..2 {
+            let _timing = timer.start();
+            thread::sleep(Duration::from_millis(5));
+        }
+        let work = stats.refresh()[&node_id].work.clone().unwrap();
+        assert!(work.per_call >= Duration::from_millis(5));
+        assert!(work.per_call < Duration::from_millis(500));
+        assert!(work.load > 0.0);
+
+        // the last figures are kept until the next calls
+        let idle = stats.refresh()[&node_id].work.clone().unwrap();
+        assert_eq!(idle.per_call, work.per_call);
+    }
+
+    #[test]
+    fn reports_edges_on_both_ends() {
+        let mut stats = FlowgraphStats::default();
+        let node_id = NodeId::default();
+        stats.probe((node_id, "out".to_string()), (node_id, "in".to_string()), 4);
+        let counters = stats.edges[0].counters.clone();
+        counters.items.store(1000, Ordering::Relaxed);
+        counters.queued.store(16, Ordering::Relaxed);
+        counters.capacity.store(64, Ordering::Relaxed);
+        counters.underflows.store(2, Ordering::Relaxed);
+        counters.overflows.store(3, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(10));
+
+        let node = &stats.refresh()[&node_id];
+        assert_eq!(node.outputs.len(), 1);
+        assert_eq!(node.inputs.len(), 1);
+        let (name, edge) = &node.outputs[0];
+        assert_eq!(name, "out");
+        assert_eq!(node.inputs[0].0, "in");
+        assert!(edge.rate > 0.0);
+        assert_eq!(edge.fill, 0.25);
+        assert_eq!((edge.underflows, edge.overflows), (2, 3));
+    }
+
+    #[test]
+    fn formats_figures() {
+        assert_eq!(format_rate(2_400_000.0), "2.40 MS/s");
+        assert_eq!(format_rate(48_000.0), "48.0 kS/s");
+        assert_eq!(format_rate(10.0), "10 S/s");
+        assert_eq!(format_duration(Duration::from_micros(1500)), "1.5 ms");
+        assert_eq!(format_duration(Duration::from_micros(20)), "20 µs");
+    }
+}
@@ -1,15 +1,35 @@
 use crate::blocks::ESDRBlock;
+use crate::blocks::ESDRBlockInput;
 use crate::blocks::ESDRBlockType;
+use crate::blocks::NodeCache;
+use crate::console::Console;
+use crate::console::NodeContext;
+use crate::console::Severity;
+use crate::flowgraph_file;
+use crate::panels;
+use crate::panels::PanelLayout;
+use crate::params::choice::ChoiceParam;
 use crate::params::input_stream::InputStream;
 use crate::params::scalar::ScalarParam;
+use crate::params::text::TextParam;
+use crate::params::toggle::ToggleParam;
 use crate::params::Param;
 use crate::params::ParamTrait;
+use crate::params::StreamType;
 use crate::radio;
+use crate::radio::RadioState;
+use crate::radio::TxArm;
+use crate::stats;
+use crate::stats::NodeStats;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::HashSet;
 
+use anyhow::Context;
 use eframe::egui;
 use egui_node_graph::*;
+use futuresdr::runtime::Pmt;
 use strum::IntoEnumIterator;
 use uuid::Uuid;
 
@@ -17,11 +37,14 @@ use uuid::Uuid;
 pub struct ESDRNodeData {
     uuid: Uuid,
     pub block_type: ESDRBlockType,
+    cache: NodeCache,
+    /// names of the params added by the last refresh
+    pub refreshed: Vec<String>,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ESDRDataType {
-    Stream,
+    Stream(StreamType),
     Scalar,
 }
 
@@ -36,34 +59,72 @@ pub enum ESDRValueType {
         value: f64,
         config: ScalarParam,
     },
+    Toggle {
+        node_id: NodeId,
+        value: bool,
+        config: ToggleParam,
+    },
+    Choice {
+        node_id: NodeId,
+        value: String,
+        config: ChoiceParam,
+    },
+    Text {
+        node_id: NodeId,
+        value: String,
+        config: TextParam,
+    },
+}
+
+impl ESDRValueType {
+    pub fn allow_updates(&self) -> bool {
+        match self {
+            ESDRValueType::Scalar { config, .. } => config.allow_updates,
+            ESDRValueType::Toggle { config, .. } => config.allow_updates,
+            ESDRValueType::Choice { config, .. } => config.allow_updates,
+            ESDRValueType::Text { config, .. } => config.allow_updates,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct UpdateScalarPayload {
+pub struct UpdateParamPayload {
     pub node_id: NodeId,
     pub field: String,
-    pub value: f64,
+    pub value: Pmt,
 }
 
 #[derive(Clone, Debug)]
 pub enum ESDRResponse {
-    UpdateScalar(UpdateScalarPayload),
+    UpdateParam(UpdateParamPayload),
+    RefreshNode(NodeId),
+    TogglePanel(NodeId),
+    JumpToNode(NodeId),
 }
 
 #[derive(Default)]
-pub struct ESDRGraphState {}
+pub struct ESDRGraphState {
+    pub panels: PanelLayout,
+    pub stats: HashMap<NodeId, NodeStats>,
+    pub node_status: HashMap<NodeId, (Severity, String)>,
+    pub node_info: HashMap<NodeId, Vec<(String, String)>>,
+}
 
 impl DataTypeTrait<ESDRGraphState> for ESDRDataType {
     fn data_type_color(&self, _user_state: &ESDRGraphState) -> egui::Color32 {
         match self {
-            ESDRDataType::Stream => egui::Color32::from_rgb(38, 109, 211),
+            ESDRDataType::Stream(StreamType::Complex32) => egui::Color32::from_rgb(38, 109, 211),
+            ESDRDataType::Stream(StreamType::F32) => egui::Color32::from_rgb(211, 109, 38),
+            ESDRDataType::Stream(StreamType::I16) => egui::Color32::from_rgb(109, 211, 38),
+            ESDRDataType::Stream(StreamType::I8) => egui::Color32::from_rgb(170, 109, 211),
             ESDRDataType::Scalar => egui::Color32::from_rgb(238, 207, 109),
         }
     }
 
     fn name(&self) -> Cow<'_, str> {
         match self {
-            ESDRDataType::Stream => Cow::Borrowed("stream"),
+            ESDRDataType::Stream(item_type) => Cow::Borrowed(item_type.name()),
             ESDRDataType::Scalar => Cow::Borrowed("scalar"),
         }
     }
@@ -86,6 +147,8 @@ impl NodeTemplateTrait for ESDRBlockType {
         ESDRNodeData {
             uuid: Uuid::new_v4(),
             block_type: self.clone(),
+            cache: NodeCache::default(),
+            refreshed: vec![],
         }
     }
 
@@ -95,13 +158,7 @@ impl NodeTemplateTrait for ESDRBlockType {
         node_id: NodeId,
     ) {
         for param in self.params() {
-            // this is needed because enum_dispatch doesn't work with traits that have
-            // associated types. see https://gitlab.com/antonok/enum_dispatch/-/issues/50
-            match param {
-                Param::Scalar(p) => p.add_param(graph, node_id),
-                Param::InputStream(p) => p.add_param(graph, node_id),
-                Param::OutputStream(p) => p.add_param(graph, node_id),
-            }
+            param.add_param(graph, node_id);
         }
     }
 }
@@ -132,6 +189,27 @@ impl WidgetValueTrait for ESDRValueType {
             } => {
                 responses.append(&mut config.widget(ui, *node_id, value));
             }
+            ESDRValueType::Toggle {
+                node_id,
+                value,
+                config,
+            } => {
+                responses.append(&mut config.widget(ui, *node_id, value));
+            }
+            ESDRValueType::Choice {
+                node_id,
+                value,
+                config,
+            } => {
+                responses.append(&mut config.widget(ui, *node_id, value));
+            }
+            ESDRValueType::Text {
+                node_id,
+                value,
+                config,
+            } => {
+                responses.append(&mut config.widget(ui, *node_id, value));
+            }
         }
         responses
     }
@@ -146,80 +224,399 @@ impl NodeDataTrait for ESDRNodeData {
 
     fn bottom_ui(
         &self,
-        _ui: &mut egui::Ui,
-        _node_id: NodeId,
-        _graph: &Graph<ESDRNodeData, ESDRDataType, ESDRValueType>,
-        _user_state: &Self::UserState,
+        ui: &mut egui::Ui,
+        node_id: NodeId,
+        graph: &Graph<ESDRNodeData, ESDRDataType, ESDRValueType>,
+        user_state: &Self::UserState,
     ) -> Vec<NodeResponse<ESDRResponse, ESDRNodeData>>
     where
         ESDRResponse: UserResponseTrait,
     {
-        let responses = vec![];
+        let mut responses = vec![];
+        self.block_type.node_ui(
+            ui,
+            ESDRBlockInput::new(graph, &graph[node_id], None),
+            &self.cache,
+        );
+        if let Some((severity, message)) = user_state.node_status.get(&node_id) {
+            ui.colored_label(severity.color(), format!("⚠ {}", message));
+        }
+        if let Some(info) = user_state.node_info.get(&node_id) {
+            panels::node_info_ui(ui, node_id, info);
+        }
+        if let Some(stats) = user_state.stats.get(&node_id) {
+            if let Some(work) = &stats.work {
+                ui.label(format!(
+                    "⏱ {}/call, {:.0}%",
+                    stats::format_duration(work.per_call),
+                    work.load * 100.0
+                ))
+                .on_hover_text("Time per work call / share of the time spent working");
+            }
+            for (port, edge) in stats.inputs.iter().chain(stats.outputs.iter()) {
+                ui.horizontal(|ui| {
+                    ui.label(port);
+                    ui.label(stats::format_rate(edge.rate));
+                    ui.add(egui::ProgressBar::new(edge.fill).desired_width(50.0))
+                        .on_hover_text("Buffer fill");
+                    if edge.underflows > 0 || edge.overflows > 0 {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("⬇{} ⬆{}", edge.underflows, edge.overflows),
+                        )
+                        .on_hover_text("Underflows / overflows");
+                    }
+                });
+            }
+        }
+        let label = if user_state.panels.is_open(node_id) {
+            "⬋ Close panel"
+        } else {
+            "⬈ Open panel"
+        };
+        if ui.small_button(label).clicked() {
+            responses.push(NodeResponse::User(ESDRResponse::TogglePanel(node_id)));
+        }
         responses
     }
 }
 
 pub type ESDRGraph = Graph<ESDRNodeData, ESDRDataType, ESDRValueType>;
-type ESDREditorState =
+pub type ESDREditorState =
     GraphEditorState<ESDRNodeData, ESDRDataType, ESDRValueType, ESDRBlockType, ESDRGraphState>;
 
 pub struct ESDRApp {
     state: ESDREditorState,
     radio: Option<radio::Radio>,
+    tx_arm: TxArm,
+    probe_edges: bool,
+    /// nodes to refresh once the radio is stopped or the block is done
+    /// looking things up
+    pending_refreshes: HashSet<NodeId>,
+    /// where the flowgraph is saved to and opened from
+    flowgraph_path: String,
+    console: Console,
 }
 
-impl Default for ESDRApp {
-    fn default() -> Self {
+impl ESDRApp {
+    fn new(console: Console) -> Self {
         Self {
             state: GraphEditorState::new(1.0, ESDRGraphState::default()),
             radio: None,
+            tx_arm: TxArm::default(),
+            probe_edges: false,
+            pending_refreshes: HashSet::new(),
+            flowgraph_path: "flowgraph.json".to_string(),
+            console,
+        }
+    }
+
+    fn start_radio(&mut self, ctx: &egui::Context) {
+        match radio::start(
+            &self.state.graph,
+            ctx,
+            self.tx_arm.clone(),
+            self.probe_edges,
+        ) {
+            Ok(radio) => self.radio = Some(radio),
+            Err(e) => self.console.error(&e.context("Failed to start the radio")),
+        }
+    }
+
+    fn save_flowgraph(&mut self) -> anyhow::Result<()> {
+        let text = flowgraph_file::save(&self.state)?;
+        std::fs::write(&self.flowgraph_path, text)
+            .with_context(|| format!("Failed to write {}", self.flowgraph_path))?;
+        self.console.log(
+            Severity::Info,
+            None,
+            format!("Saved {}", self.flowgraph_path),
+        );
+        Ok(())
+    }
+
+    /// Replaces the flowgraph with the saved one. Its nodes are refreshed
+    /// afterwards, so that they pick up the choices of the devices present.
+    fn open_flowgraph(&mut self) -> anyhow::Result<()> {
+        let text = std::fs::read_to_string(&self.flowgraph_path)
+            .with_context(|| format!("Failed to read {}", self.flowgraph_path))?;
+        self.state = flowgraph_file::load(&text)
+            .with_context(|| format!("Failed to open {}", self.flowgraph_path))?;
+        self.pending_refreshes = self.state.graph.nodes.keys().collect();
+        // its status refers to the nodes of the previous flowgraph
+        self.radio = None;
+        self.console.log(
+            Severity::Info,
+            None,
+            format!("Opened {}", self.flowgraph_path),
+        );
+        Ok(())
+    }
+
+    /// Refreshes the params of a node. Nodes aren't refreshed while the radio
+    /// is running, since its flowgraph owns the devices the blocks look at,
+    /// and changes to the graph only apply on the next start anyway.
+    fn refresh_node(&mut self, node_id: NodeId) -> anyhow::Result<()> {
+        self.pending_refreshes.remove(&node_id);
+        if !self.state.graph.nodes.contains_key(node_id) {
+            return Ok(());
+        }
+        let running = match &self.radio {
+            Some(radio) => !matches!(radio.state(), RadioState::Stopped | RadioState::Failed(_)),
+            None => false,
+        };
+        let graph = &mut self.state.graph;
+        let block_type = graph[node_id].user_data.block_type;
+        let params = match running {
+            true => None,
+            false => block_type.refresh(ESDRBlockInput::new(graph, &graph[node_id], None))?,
+        };
+        let params = match params {
+            Some(params) => params,
+            None => {
+                self.pending_refreshes.insert(node_id);
+                return Ok(());
+            }
+        };
+        let stale: Vec<String> = graph[node_id]
+            .user_data
+            .refreshed
+            .iter()
+            .filter(|name| !params.iter().any(|param| param.name() == name.as_str()))
+            .cloned()
+            .collect();
+        for name in stale {
+            if let Ok(input_id) = graph[node_id].get_input(&name) {
+                graph.remove_input_param(input_id);
+            }
+            if let Ok(output_id) = graph[node_id].get_output(&name) {
+                graph.remove_output_param(output_id);
+            }
+        }
+        graph[node_id].user_data.refreshed = params
+            .iter()
+            .map(|param| param.name().to_string())
+            .collect();
+        for param in params {
+            if let Param::OutputStream(_) = param {
+                if graph[node_id].get_output(param.name()).is_err() {
+                    param.add_param(graph, node_id);
+                }
+                continue;
+            }
+            match (graph[node_id].get_input(param.name()), param) {
+                (Ok(input_id), Param::Choice(p)) => {
+                    if let ESDRValueType::Choice { config, .. } = &mut graph.inputs[input_id].value
+                    {
+                        config.set_choices(p.choices);
+                    }
+                }
+                (Ok(_), _) => {}
+                (Err(_), param) => param.add_param(graph, node_id),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_response(&mut self, response: ESDRResponse) {
+        match response {
+            ESDRResponse::UpdateParam(ev) => {
+                if let Some(radio) = &mut self.radio {
+                    radio.update_param(ev.node_id, &ev.field, ev.value);
+                }
+            }
+            ESDRResponse::RefreshNode(node_id) => {
+                if let Err(e) = self
+                    .refresh_node(node_id)
+                    .with_context(|| NodeContext::new(&self.state.graph, node_id))
+                {
+                    self.console.error(&e);
+                }
+            }
+            ESDRResponse::TogglePanel(node_id) => {
+                self.state.user_state.panels.toggle(node_id);
+            }
+            ESDRResponse::JumpToNode(node_id) => {
+                if let Some(pos) = self.state.node_positions.get(node_id) {
+                    self.state.user_state.panels.run_view = false;
+                    self.state.selected_node = Some(node_id);
+                    self.state.pan_zoom.pan = egui::vec2(200.0, 200.0) - pos.to_vec2();
+                }
+            }
         }
     }
 }
 
 impl eframe::App for ESDRApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let radio_state = match &self.radio {
+            Some(radio) => radio.state(),
+            None => RadioState::Stopped,
+        };
+        self.state.user_state.stats = match (&mut self.radio, &radio_state) {
+            (Some(radio), RadioState::Running) => radio.stats(),
+            _ => HashMap::new(),
+        };
+        if radio_state.is_transitioning() {
+            ctx.request_repaint();
+        }
+        if let Some(radio) = &self.radio {
+            for (severity, node_id, message) in radio.take_events() {
+                self.console.log(severity, Some(node_id), message);
+            }
+            self.state.user_state.node_status = radio.node_status();
+            self.state.user_state.node_info = radio.node_info();
+        }
+
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_dark_light_mode_switch(ui);
                 ui.horizontal(|ui| {
+                    match &radio_state {
+                        RadioState::Starting | RadioState::Stopping => {
+                            ui.spinner();
+                        }
+                        RadioState::Running => {
+                            if ui.button("⏹").clicked() {
+                                if let Some(radio) = &mut self.radio {
+                                    radio.stop();
+                                }
+                            }
+                        }
+                        RadioState::Stopped | RadioState::Failed(_) => {
+                            if ui.button("▶").clicked() {
+                                self.start_radio(ctx);
+                            }
+                        }
+                    }
+                    match &radio_state {
+                        RadioState::Stopped => ui.label("Stopped"),
+                        RadioState::Starting => ui.label("Starting…"),
+                        RadioState::Running => ui.label("Running"),
+                        RadioState::Stopping => ui.label("Stopping…"),
+                        RadioState::Failed(e) => {
+                            ui.colored_label(egui::Color32::RED, format!("Failed: {}", e))
+                        }
+                    };
+                    let mut armed = self.tx_arm.is_armed();
+                    let text = if armed {
+                        egui::RichText::new("TX armed").color(egui::Color32::RED)
+                    } else {
+                        egui::RichText::new("Arm TX")
+                    };
+                    if ui
+                        .toggle_value(&mut armed, text)
+                        .on_hover_text("Sinks only transmit while armed")
+                        .changed()
+                    {
+                        match &mut self.radio {
+                            Some(radio) => radio.set_tx_arm(armed),
+                            None => self.tx_arm.set(armed),
+                        }
+                    }
+                    ui.separator();
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.flowgraph_path).desired_width(150.0),
+                    );
+                    if ui
+                        .button("💾")
+                        .on_hover_text("Save the flowgraph")
+                        .clicked()
+                    {
+                        if let Err(e) = self.save_flowgraph() {
+                            self.console.error(&e);
+                        }
+                    }
+                    let stopped =
+                        matches!(radio_state, RadioState::Stopped | RadioState::Failed(_));
                     if ui
-                        .button(if self.radio.is_some() { "⏹" } else { "▶" })
+                        .add_enabled(stopped, egui::Button::new("📂"))
+                        .on_hover_text("Open a flowgraph")
                         .clicked()
                     {
-                        if let Some(radio) = &mut self.radio {
-                            radio.stop();
-                            self.radio = None;
-                        } else {
-                            self.radio = Some(radio::start(&self.state.graph));
+                        if let Err(e) = self.open_flowgraph() {
+                            self.console.error(&e);
                         }
                     }
+                    ui.separator();
+                    ui.toggle_value(&mut self.probe_edges, "Edge stats")
+                        .on_hover_text(
+                            "Measure the throughput and buffer fill of every edge on the next \
+                         start, at the cost of copying all the samples once more",
+                        );
+                    ui.toggle_value(&mut self.state.user_state.panels.run_view, "Run view");
+                    ui.toggle_value(&mut self.console.open, "Console");
                 });
             });
         });
-        let graph_response = egui::CentralPanel::default()
-            .show(ctx, |ui| {
-                self.state.draw_graph_editor(ui, AllESDRBlockTypes)
-            })
-            .inner;
-        for response in graph_response.node_responses {
-            if let NodeResponse::User(user_event) = response {
-                match user_event {
-                    ESDRResponse::UpdateScalar(ev) => {
-                        if let Some(radio) = &mut self.radio {
-                            radio.update_scalar(ev.node_id, &ev.field, ev.value);
-                        }
+
+        let mut responses = vec![];
+        if self.console.open {
+            let console = &mut self.console;
+            responses.append(
+                &mut egui::TopBottomPanel::bottom("console")
+                    .resizable(true)
+                    .default_height(150.0)
+                    .show(ctx, |ui| console.ui(ui))
+                    .inner,
+            );
+        }
+
+        let user_state = &mut self.state.user_state;
+        responses.append(&mut user_state.panels.show(
+            ctx,
+            &mut self.state.graph,
+            &user_state.node_info,
+        ));
+
+        if self.state.user_state.panels.run_view {
+            let graph = &mut self.state.graph;
+            responses.append(
+                &mut egui::CentralPanel::default()
+                    .show(ctx, |ui| panels::run_view_ui(ui, graph))
+                    .inner,
+            );
+        } else {
+            let graph_response = egui::CentralPanel::default()
+                .show(ctx, |ui| {
+                    self.state.draw_graph_editor(ui, AllESDRBlockTypes)
+                })
+                .inner;
+            for response in graph_response.node_responses {
+                match response {
+                    NodeResponse::User(user_event) => responses.push(user_event),
+                    NodeResponse::CreatedNode(node_id) => {
+                        responses.push(ESDRResponse::RefreshNode(node_id));
                     }
+                    NodeResponse::DeleteNode(node_id) => {
+                        self.state.user_state.panels.close(node_id);
+                        self.pending_refreshes.remove(&node_id);
+                    }
+                    _ => {}
                 }
             }
         }
+
+        for response in responses {
+            self.handle_response(response);
+        }
+        if matches!(radio_state, RadioState::Stopped | RadioState::Failed(_)) {
+            for node_id in self.pending_refreshes.clone() {
+                self.handle_response(ESDRResponse::RefreshNode(node_id));
+            }
+            if !self.pending_refreshes.is_empty() {
+                // blocks looking things up in the background can't wake us up
+                ctx.request_repaint();
+            }
+        }
     }
 }
 
 pub fn run() -> ! {
+    let console = Console::init();
     eframe::run_native(
         "eSDR",
         eframe::NativeOptions::default(),
-        Box::new(|_cc| Box::new(ESDRApp::default())),
+        Box::new(|_cc| Box::new(ESDRApp::new(console))),
     );
 }